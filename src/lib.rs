@@ -0,0 +1,5816 @@
+// 代码生成逻辑：GenSpec 描述要生成的函数，generate_* 按它生成各层的代码
+// 界面和命令行等调用方共用这些函数，不依赖界面状态
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OperationType {
+    Database,
+    Network,
+    // 网络请求，服务端会多次推送响应，回调可以被调用多次
+    NetworkStream,
+    // 网络请求成功后把结果写入数据库缓存，再回调
+    NetworkThenDb,
+}
+
+impl std::fmt::Display for OperationType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OperationType::Database => write!(f, "数据库操作"),
+            OperationType::Network => write!(f, "网络请求"),
+            OperationType::NetworkStream => write!(f, "网络请求 (流式响应)"),
+            OperationType::NetworkThenDb => write!(f, "网络请求后写入数据库"),
+        }
+    }
+}
+
+impl OperationType {
+    pub const ALL: [OperationType; 4] = [
+        OperationType::Database,
+        OperationType::Network,
+        OperationType::NetworkStream,
+        OperationType::NetworkThenDb,
+    ];
+
+    // 命令行参数和导入文件中的操作类型名，也接受界面上显示的中文名
+    pub fn from_name(value: &str) -> Option<OperationType> {
+        match value.trim() {
+            "network" => Some(OperationType::Network),
+            "stream" => Some(OperationType::NetworkStream),
+            "database" | "db" => Some(OperationType::Database),
+            "network-then-db" => Some(OperationType::NetworkThenDb),
+            name => OperationType::ALL
+                .into_iter()
+                .find(|operation_type| operation_type.to_string() == name),
+        }
+    }
+
+    pub fn is_network(&self) -> bool {
+        matches!(
+            self,
+            OperationType::Network | OperationType::NetworkStream | OperationType::NetworkThenDb
+        )
+    }
+}
+
+// 回调参数的写法：泛型 CB 约束，或者 Box<dyn FnOnce> 特征对象
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CallbackStyle {
+    Generic,
+    BoxedDyn,
+}
+
+impl std::fmt::Display for CallbackStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallbackStyle::Generic => write!(f, "泛型 CB"),
+            CallbackStyle::BoxedDyn => write!(f, "Box<dyn FnOnce>"),
+        }
+    }
+}
+
+impl CallbackStyle {
+    pub const ALL: [CallbackStyle; 2] = [CallbackStyle::Generic, CallbackStyle::BoxedDyn];
+}
+
+// 回调池保存回调的方式：FnOnce 只能回调一次，Fn 可以多次回调 (约束需要 Fn + Sync)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CbPoolVariant {
+    FnOnce,
+    Fn,
+}
+
+impl std::fmt::Display for CbPoolVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CbPoolVariant::FnOnce => write!(f, "FnOnce 回调池"),
+            CbPoolVariant::Fn => write!(f, "Fn 回调池"),
+        }
+    }
+}
+
+impl CbPoolVariant {
+    pub const ALL: [CbPoolVariant; 2] = [CbPoolVariant::FnOnce, CbPoolVariant::Fn];
+}
+
+// db_agent 中和 db 模块之间的通道：oneshot 只返回一次结果，mpsc 用于分多次返回的流式结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChannelKind {
+    Oneshot,
+    Mpsc,
+}
+
+impl std::fmt::Display for ChannelKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChannelKind::Oneshot => write!(f, "oneshot (单次结果)"),
+            ChannelKind::Mpsc => write!(f, "mpsc (流式结果)"),
+        }
+    }
+}
+
+impl ChannelKind {
+    pub const ALL: [ChannelKind; 2] = [ChannelKind::Oneshot, ChannelKind::Mpsc];
+}
+
+// Request 中 pb 数据的类型：零拷贝的管道中使用 bytes::Bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PbBuffer {
+    VecU8,
+    Bytes,
+}
+
+impl std::fmt::Display for PbBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PbBuffer::VecU8 => write!(f, "Vec<u8>"),
+            PbBuffer::Bytes => write!(f, "Bytes (零拷贝)"),
+        }
+    }
+}
+
+impl PbBuffer {
+    pub const ALL: [PbBuffer; 2] = [PbBuffer::VecU8, PbBuffer::Bytes];
+
+    fn type_name(self) -> &'static str {
+        match self {
+            PbBuffer::VecU8 => "Vec<u8>",
+            PbBuffer::Bytes => "Bytes",
+        }
+    }
+}
+
+// 生成函数的可见性，未选择时各输出框使用各自的默认值
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Visibility {
+    Pub,
+    PubCrate,
+    Private,
+}
+
+impl std::fmt::Display for Visibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Visibility::Pub => write!(f, "pub"),
+            Visibility::PubCrate => write!(f, "pub(crate)"),
+            Visibility::Private => write!(f, "私有"),
+        }
+    }
+}
+
+impl Visibility {
+    pub const ALL: [Visibility; 3] = [Visibility::Pub, Visibility::PubCrate, Visibility::Private];
+
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Visibility::Pub => "pub ",
+            Visibility::PubCrate => "pub(crate) ",
+            Visibility::Private => "",
+        }
+    }
+}
+
+// 上层绑定的目标：JNI 供 Android 调用，FlutterFfi 生成 flutter_rust_bridge 风格的包装
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BindingTarget {
+    None,
+    Jni,
+    FlutterFfi,
+}
+
+impl std::fmt::Display for BindingTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BindingTarget::None => write!(f, "不生成"),
+            BindingTarget::Jni => write!(f, "JNI"),
+            BindingTarget::FlutterFfi => write!(f, "Flutter (flutter_rust_bridge)"),
+        }
+    }
+}
+
+impl BindingTarget {
+    pub const ALL: [BindingTarget; 3] = [
+        BindingTarget::None,
+        BindingTarget::Jni,
+        BindingTarget::FlutterFfi,
+    ];
+}
+
+// JNI 函数名中的 Java 包名和类名，生成后需要按实际的类修改
+const JNI_CLASS_PATH: &str = "com_example_sdk_NativeEngine";
+
+// 描述要生成的一个 SDK 函数：函数签名、操作类型以及各种生成选项
+// 可以从 JSON 反序列化，缺少的字段使用默认值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenSpec {
+    pub function_name: String,
+    pub function_params: String,
+    pub callback_return_type: String,
+    pub expected_return: String,
+    pub request_body_name: String,
+    // Request::get_method 返回的协议方法名
+    pub rmtp_method: String,
+    // 网络请求的 engine_async 上方生成 // Wire: 注释，请求结构体的字段上方生成 /// 文档
+    pub document_wire: bool,
+    // 请求体字段，每行 field_name: rust_type；填写后代替 proto / 参数推断 pb_req 的字段
+    pub request_fields: String,
+    // 请求结构体字段的说明，每行 name: description；开启 document_wire 时生成 /// 文档
+    pub param_docs: String,
+    pub operation_type: Option<OperationType>,
+    pub callback_style: CallbackStyle,
+    pub pass_params_to_request: bool,
+    // 不传参数时额外生成 default_request(cb) 便于测试构造请求
+    pub generate_default_request: bool,
+    // 关闭时 Request 不保存回调，发送前把回调放入回调池，收到响应时按 msg_uid 取出
+    pub request_stores_callback: bool,
+    pub use_builder_pattern: bool,
+    // request builder 返回 Result，pb_req 字段类型转换失败时用 ? 返回 guard_error
+    pub fallible_builder: bool,
+    // Request 实现使用原生 trait async fn，不再依赖 async_trait 宏
+    pub native_async_trait: bool,
+    // get_pb_data 的返回值和 deal_with_response 的 pb_data 参数类型
+    pub pb_buffer: PbBuffer,
+    // 关闭时 module、db_agent 和 engine 生成阻塞版本，供不使用 async 的调用方
+    pub async_mode: bool,
+    pub binding_target: BindingTarget,
+    // 测试方法包裹在 #[cfg(test)] mod tests 中
+    pub wrap_test_module: bool,
+    // 测试参数的默认值放进 #[cfg(test)] 的辅助函数中，参数相同的测试共用
+    pub shared_test_setup: bool,
+    pub tester: TesterChoice,
+    // 测试账号连接失败时打印原因并跳过测试，代替 connect().await.unwrap()
+    pub graceful_connect: bool,
+    // db_agent 使用的通道，mpsc 时直接返回接收端，由调用方逐条接收
+    pub channel_kind: ChannelKind,
+    pub prefer_owned_strings: bool,
+    pub suppress_warnings: bool,
+    pub supports_cancel: bool,
+    // 网络请求的超时时间 (毫秒)，为 None 时不加超时
+    pub timeout_ms: Option<u32>,
+    pub empty_test_vecs: bool,
+    pub keep_nested_type_paths: bool,
+    // 数据库操作的 engine_async 用 logger().log_result 记录结果，代替 match 拼接日志
+    pub use_log_result_helper: bool,
+    // engine_async 使用 #[tracing::instrument] 代替手动 trace 日志，和 log_result 互斥
+    pub use_tracing_instrument: bool,
+    // -T 日志中记录入参；敏感函数可以关闭，敏感名称的参数始终不记录
+    pub log_trace_params: bool,
+    pub trace_macro: String,
+    pub trace_tag_prefix: String,
+    // db_worker 中使用的日志宏
+    pub db_log_macro: String,
+    pub conv_type_name: String,
+    pub db_conv_type_name: String,
+    pub timestamp_type: String,
+    // 测试方法中整数参数按名称使用的取值，格式为 "name=value, ..."
+    pub test_value_table: String,
+    // 实际是枚举的整数参数，格式为 "name=EnumType, ..."，engine_sync 中转换为枚举
+    pub enum_param_table: String,
+    // deal_with_response 中需要重新映射的错误码，格式为 "code => error, ..."
+    pub error_code_table: String,
+    pub cancel_token_import: String,
+    // 测试模块中 ServerApi、SHARED_RUNTIME 和测试账号所在的模块路径
+    pub test_support_path: String,
+    // 网络请求超时时回调的错误
+    pub timeout_error: String,
+    // engine_async 被取消时回调的错误
+    pub cancel_error: String,
+    // engine_sync 开头生成参数校验，校验失败时回调 guard_error
+    pub generate_guards: bool,
+    pub guard_error: String,
+    // 签名和回调约束中用 result_alias<T> 代替 Result<T, EngineError>
+    pub use_result_alias: bool,
+    pub result_alias: String,
+    // 未填写回调返回值类型时使用的类型，engine、module、request 和 db 各层共用
+    pub default_return_type: String,
+    // engine_sync 中包装回调的函数名，以及回调池按 FnOnce 还是 Fn 保存回调
+    pub cb_pool_fn: String,
+    pub cb_pool_variant: CbPoolVariant,
+    // engine_sync 和 engine_async 包裹在 impl engine_type { ... } 中，便于直接放入新文件
+    pub wrap_engine_impl: bool,
+    pub engine_type: String,
+    // Request 结构体每个额外成员上方的属性模板，{field} 替换为成员名，为空时不生成
+    pub field_attribute_template: String,
+    pub feature_gate: String,
+    // 用户选择的可见性，None 时按输出框默认（engine 为 pub，module/request 为 pub(crate)）
+    pub visibility: Option<Visibility>,
+    // request 和测试也加上 feature 条件编译
+    pub feature_gate_request_and_test: bool,
+    // .proto 文件内容和要读取的消息名（为空时使用请求体名称）
+    pub proto_source: String,
+    pub proto_message_name: String,
+}
+
+impl Default for GenSpec {
+    fn default() -> Self {
+        Self {
+            function_name: String::new(),
+            function_params: String::new(),
+            callback_return_type: String::new(),
+            expected_return: String::new(),
+            request_body_name: String::new(),
+            rmtp_method: String::new(),
+            document_wire: false,
+            request_fields: String::new(),
+            param_docs: String::new(),
+            operation_type: Some(OperationType::Network),
+            callback_style: CallbackStyle::Generic,
+            pass_params_to_request: false,
+            generate_default_request: false,
+            request_stores_callback: true,
+            use_builder_pattern: false,
+            fallible_builder: false,
+            native_async_trait: false,
+            pb_buffer: PbBuffer::VecU8,
+            async_mode: true,
+            binding_target: BindingTarget::None,
+            wrap_test_module: false,
+            shared_test_setup: false,
+            tester: TesterChoice::A,
+            graceful_connect: false,
+            channel_kind: ChannelKind::Oneshot,
+            prefer_owned_strings: false,
+            suppress_warnings: true,
+            supports_cancel: false,
+            timeout_ms: None,
+            empty_test_vecs: false,
+            keep_nested_type_paths: false,
+            use_log_result_helper: false,
+            use_tracing_instrument: false,
+            log_trace_params: true,
+            trace_macro: "trace_i_json".to_string(),
+            trace_tag_prefix: "P".to_string(),
+            db_log_macro: "log_db_i".to_string(),
+            conv_type_name: "ConversationType".to_string(),
+            db_conv_type_name: "DbConversationType".to_string(),
+            timestamp_type: "i64".to_string(),
+            test_value_table: "limit=20, count=20, page_size=20, offset=0, start_time=0"
+                .to_string(),
+            enum_param_table: String::new(),
+            error_code_table: String::new(),
+            cancel_token_import: "tokio_util::sync::CancellationToken".to_string(),
+            test_support_path: "crate::common".to_string(),
+            timeout_error: "EngineError::Timeout".to_string(),
+            cancel_error: "EngineError::Cancelled".to_string(),
+            generate_guards: false,
+            guard_error: "EngineError::InvalidParam".to_string(),
+            use_result_alias: false,
+            result_alias: "EngineResult".to_string(),
+            default_return_type: "()".to_string(),
+            cb_pool_fn: "cb_pool_once".to_string(),
+            cb_pool_variant: CbPoolVariant::FnOnce,
+            wrap_engine_impl: false,
+            engine_type: "Engine".to_string(),
+            field_attribute_template: String::new(),
+            feature_gate: String::new(),
+            visibility: None,
+            feature_gate_request_and_test: false,
+            proto_source: String::new(),
+            proto_message_name: String::new(),
+        }
+    }
+}
+
+// 测试方法使用的测试账号：A 或 B 单独测试，AandB 同时连接两个客户端 (例如一端发送、另一端接收)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TesterChoice {
+    A,
+    B,
+    AandB,
+}
+
+impl std::fmt::Display for TesterChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TesterChoice::A => write!(f, "TESTER_A"),
+            TesterChoice::B => write!(f, "TESTER_B"),
+            TesterChoice::AandB => write!(f, "TESTER_A 和 TESTER_B"),
+        }
+    }
+}
+
+impl TesterChoice {
+    pub const ALL: [TesterChoice; 3] = [TesterChoice::A, TesterChoice::B, TesterChoice::AandB];
+
+    fn constants(self) -> &'static [&'static str] {
+        match self {
+            TesterChoice::A => &["TESTER_A"],
+            TesterChoice::B => &["TESTER_B"],
+            TesterChoice::AandB => &["TESTER_A", "TESTER_B"],
+        }
+    }
+}
+
+// 以下是每个输出框对应的生成函数，和界面中生成的内容一致 (已加上 feature 条件编译)
+pub fn generate_engine_sync(spec: &GenSpec) -> String {
+    spec.gate_item(spec.generate_engine_sync_function(&spec.rust_function_name()))
+}
+
+pub fn generate_engine_async(spec: &GenSpec) -> String {
+    spec.gate_item(spec.generate_engine_async_function(&spec.rust_function_name()))
+}
+
+// 关闭包裹或代码为空时原样返回；批量生成时对拼接后的全部函数只包裹一次
+pub fn wrap_in_engine_impl(spec: &GenSpec, code: &str) -> String {
+    if !spec.wrap_engine_impl || code.trim().is_empty() {
+        return code.to_string();
+    }
+    format!(
+        "impl {} {{\n{}\n}}",
+        spec.resolved_engine_type(),
+        indent_lines(code.trim_end(), 4)
+    )
+}
+
+pub fn generate_module(spec: &GenSpec) -> String {
+    spec.gate_item(spec.generate_module_function(&spec.rust_function_name()))
+}
+
+// 只有网络请求需要 request builder，其他操作类型返回空字符串
+pub fn generate_request_builder(spec: &GenSpec) -> String {
+    if !spec.is_network() {
+        return String::new();
+    }
+    spec.gate_request_and_test_item(
+        spec.generate_request_builder_function(&spec.rust_function_name()),
+    )
+}
+
+// 未填写请求体名称时返回空字符串
+pub fn generate_request_struct(spec: &GenSpec) -> String {
+    if spec.request_body_name.is_empty() {
+        return String::new();
+    }
+    let code = spec.generate_request_struct();
+    if spec.feature_gate_request_and_test {
+        spec.gate_file(code)
+    } else {
+        code
+    }
+}
+
+pub fn generate_from_conversion(spec: &GenSpec) -> String {
+    if spec.needs_from_conversion() {
+        spec.generate_from_conversion()
+    } else {
+        String::new()
+    }
+}
+
+pub fn generate_test_method(spec: &GenSpec) -> String {
+    generate_test_methods(std::slice::from_ref(spec))
+}
+
+// 多个函数的测试方法，共用的参数辅助函数按参数形状去重，放在所有测试之前；
+// 包裹模块和条件编译的选项取第一个函数的
+pub fn generate_test_methods(specs: &[GenSpec]) -> String {
+    let Some(first) = specs.first() else {
+        return String::new();
+    };
+    let mut items: Vec<String> = Vec::new();
+    for spec in specs {
+        let setup = spec.generate_shared_test_setup();
+        if !setup.is_empty() && !items.contains(&setup) {
+            items.push(setup);
+        }
+    }
+    items.extend(
+        specs
+            .iter()
+            .map(|spec| spec.generate_test_method(&spec.rust_function_name()))
+            .filter(|test_code| !test_code.is_empty()),
+    );
+
+    if first.wrap_test_module {
+        first.gate_request_and_test_item(first.wrap_in_test_module(&items.join("\n\n")))
+    } else {
+        items
+            .into_iter()
+            .map(|item| first.gate_request_and_test_item(item))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+pub fn generate_db_agent(spec: &GenSpec) -> String {
+    let (db_spec, name) = spec.db_function_target();
+    db_spec.generate_db_agent_function(&name)
+}
+
+pub fn generate_db_worker(spec: &GenSpec) -> String {
+    let (db_spec, name) = spec.db_function_target();
+    db_spec.generate_db_worker_function(&name)
+}
+
+pub fn generate_db_sqlite(spec: &GenSpec) -> String {
+    let (db_spec, name) = spec.db_function_target();
+    db_spec.generate_db_sqlite_function(&name)
+}
+
+pub fn generate_ffi(spec: &GenSpec) -> String {
+    spec.gate_item(spec.generate_ffi_function(&spec.rust_function_name()))
+}
+
+pub fn generate_binding(spec: &GenSpec) -> String {
+    spec.gate_item(spec.generate_binding_function(&spec.rust_function_name()))
+}
+
+pub fn generate_bench(spec: &GenSpec) -> String {
+    spec.generate_bench_function(&spec.rust_function_name())
+}
+
+impl GenSpec {
+    // 生成代码中的函数名：Java 风格的驼峰名转换为下划线命名
+    fn rust_function_name(&self) -> String {
+        to_snake_case(&self.function_name)
+    }
+
+    // db 模块中生成的函数：网络请求后写库时是模块调用的 save_xxx，参数为请求的结果，只返回是否写入成功
+    fn db_function_target(&self) -> (GenSpec, String) {
+        let rust_function_name = self.rust_function_name();
+        if self.operation_type != Some(OperationType::NetworkThenDb) {
+            return (self.clone(), rust_function_name);
+        }
+        let db_spec = GenSpec {
+            operation_type: Some(OperationType::Database),
+            function_params: format!("value: {}", self.resolved_return_type()),
+            callback_return_type: "()".to_string(),
+            channel_kind: ChannelKind::Oneshot,
+            ..self.clone()
+        };
+        (db_spec, format!("save_{}", rust_function_name))
+    }
+
+    // 自定义模板中可用的占位符：{name} 函数名、{params} 参数列表、{args} 参数名、
+    // {return} 回调返回值类型、{request} 请求体名称
+    pub fn template_vars(&self) -> HashMap<&'static str, String> {
+        HashMap::from([
+            ("name", self.rust_function_name()),
+            ("params", self.add_ref_to_str_params()),
+            ("args", self.extract_param_names()),
+            ("return", self.resolved_return_type()),
+            ("request", self.request_body_name.clone()),
+        ])
+    }
+
+    fn generate_engine_sync_function(&self, rust_function_name: &str) -> String {
+        let cb_type = self.resolved_return_type();
+
+        // engine_sync 接收原始整数，在函数体中转换为枚举后再传给下一层
+        let cleaned_params = self.clean_params_with(&self.function_params, false);
+        let str_conversions =
+            self.generate_str_to_string_conversions() + &self.generate_enum_param_conversions();
+        let guards = self.generate_param_guards();
+        let engine_binding = if self.async_mode {
+            "    let engine = self.engine.clone();\n"
+        } else {
+            ""
+        };
+
+        match self.operation_type {
+            Some(OperationType::Database) => {
+                format!(
+                    r#"{}fn {}{}({}){}
+{{
+{}{}    let cb = self.{}(cb);
+{}
+{}
+}}"#,
+                    self.visibility_keyword(Visibility::Pub),
+                    rust_function_name,
+                    self.callback_generics(),
+                    join_args(&[
+                        "&self",
+                        &cleaned_params,
+                        &format!("cb: {}", self.callback_param_type(&cb_type)),
+                    ]),
+                    self.callback_where_clause(&cb_type),
+                    guards,
+                    engine_binding,
+                    self.engine_sync_cb_pool_fn(),
+                    str_conversions,
+                    self.engine_sync_dispatch(&self.engine_sync_db_statements(rust_function_name))
+                )
+            }
+            Some(
+                OperationType::Network
+                | OperationType::NetworkStream
+                | OperationType::NetworkThenDb,
+            ) => {
+                format!(
+                    r#"{}fn {}{}({}){}
+{{
+{}{}    let callback = self.{}(cb);
+{}
+{}
+}}"#,
+                    self.visibility_keyword(Visibility::Pub),
+                    rust_function_name,
+                    self.callback_generics(),
+                    join_args(&[
+                        "&self",
+                        &cleaned_params,
+                        &format!("cb: {}", self.callback_param_type(&cb_type)),
+                    ]),
+                    self.callback_where_clause(&cb_type),
+                    guards,
+                    engine_binding,
+                    self.engine_sync_cb_pool_fn(),
+                    str_conversions,
+                    self.engine_sync_dispatch(&format!(
+                        "{}.{}({}){};",
+                        self.engine_sync_receiver(),
+                        rust_function_name,
+                        join_args(&[
+                            &self.extract_param_names_for_call(),
+                            &self.callback_arg("callback"),
+                        ]),
+                        self.await_suffix()
+                    ))
+                )
+            }
+            None => String::new(),
+        }
+    }
+
+    // engine_sync 中调用 engine 的语句：async 模式下 post 到运行时中执行；
+    // 阻塞模式下 engine 内部会 block_on，放进运行时的任务会报 Cannot start a runtime from within a runtime，
+    // 所以直接在调用方线程上同步调用
+    fn engine_sync_dispatch(&self, statements: &str) -> String {
+        if self.async_mode {
+            format!(
+                "    self.post(async move {{\n{}\n    }});",
+                indent_lines(statements, 8)
+            )
+        } else {
+            indent_lines(statements, 4)
+        }
+    }
+
+    fn engine_sync_receiver(&self) -> &'static str {
+        if self.async_mode {
+            "engine"
+        } else {
+            "self.engine"
+        }
+    }
+
+    fn generate_engine_async_function(&self, rust_function_name: &str) -> String {
+        let cb_type = self.resolved_return_type();
+
+        let params_with_ref = self.add_ref_to_str_params();
+        let param_names = self.extract_param_names();
+        let trace_macro = self.resolved_trace_macro();
+        // 日志标签格式为 <前缀>-<函数名>，调用处再追加 -T / -R 后缀
+        let trace_tag = format!(
+            "{}-{}",
+            self.resolved_trace_tag_prefix(),
+            rust_function_name
+        );
+
+        // 生成 match 表达式的 Ok 分支
+        // 注意：用户输入（返回值类型、参数名）只作为 format! 的参数传入，不会被当作格式串解析，
+        // 其中的 { } 和逗号都原样输出
+        let ok_match_pattern = if cb_type == "()" {
+            "Ok(()) => \"\".to_string()".to_string()
+        } else {
+            "Ok(_) => \"\".to_string()".to_string()
+        };
+
+        match self.operation_type {
+            Some(
+                OperationType::Network
+                | OperationType::NetworkStream
+                | OperationType::NetworkThenDb,
+            ) if self.cancellable() => {
+                self.wire_comment()
+                    + &self.generate_cancellable_engine_async_function(
+                        rust_function_name,
+                        &cb_type,
+                        &trace_tag,
+                        &ok_match_pattern,
+                    )
+            }
+            Some(
+                OperationType::Network
+                | OperationType::NetworkStream
+                | OperationType::NetworkThenDb,
+            ) => {
+                let call = match self.timeout() {
+                    Some(ms) => format!(
+                        r#"{}    let request = bugtags::{}({});
+    if tokio::time::timeout(std::time::Duration::from_millis({}), request)
+        .await
+        .is_err()
+    {{
+        if let Some(cb) = cb.lock().unwrap().take() {{
+            cb(Err({}));
+        }}
+    }}
+"#,
+                        self.shared_request_callback(&cb_type, "超时"),
+                        rust_function_name,
+                        join_args(&["&self.ctx", &param_names, &self.callback_arg("request_cb")]),
+                        ms,
+                        self.resolved_timeout_error()
+                    ),
+                    None => format!(
+                        "    bugtags::{}({}){};\n",
+                        rust_function_name,
+                        join_args(&["&self.ctx", &param_names, &self.callback_arg("cb")]),
+                        self.await_suffix()
+                    ),
+                };
+                format!(
+                    r#"{}{}{}{}fn {}{}({}){}
+{{
+{}{}}}"#,
+                    self.wire_comment(),
+                    self.instrument_attribute("skip(self, cb)"),
+                    self.visibility_keyword(Visibility::Pub),
+                    self.async_keyword(),
+                    rust_function_name,
+                    self.callback_generics(),
+                    join_args(&[
+                        "&self",
+                        &params_with_ref,
+                        &format!("cb: {}", self.callback_param_type(&cb_type)),
+                    ]),
+                    self.callback_where_clause(&cb_type),
+                    self.traced_callback_wrapper(&cb_type, &trace_tag, &ok_match_pattern),
+                    call
+                )
+            }
+            // 结果分多次返回，没有单个结果可以记录，只记录调用
+            Some(OperationType::Database) if self.streams_db_results() => {
+                format!(
+                    r#"{}{}{}fn {}({}) -> {} {{
+    let trace_id = self.ctx.logger().generate_trace_id();
+    {}!(self.ctx.logger(), "{}-T", trace_id{});
+    bugtags::{}({}){}
+}}"#,
+                    self.mpsc_import_note(),
+                    self.visibility_keyword(Visibility::Pub),
+                    self.async_keyword(),
+                    rust_function_name,
+                    join_args(&["&self", &params_with_ref]),
+                    self.db_return_type(&cb_type),
+                    trace_macro,
+                    trace_tag,
+                    self.trace_params_args(),
+                    rust_function_name,
+                    join_args(&["&self.ctx", &param_names]),
+                    self.await_suffix()
+                )
+            }
+            Some(OperationType::Database) if self.use_tracing_instrument => {
+                format!(
+                    r#"{}{}{}fn {}({}) -> {} {{
+    bugtags::{}({}){}
+}}"#,
+                    self.instrument_attribute("skip(self), err"),
+                    self.visibility_keyword(Visibility::Pub),
+                    self.async_keyword(),
+                    rust_function_name,
+                    join_args(&["&self", &params_with_ref]),
+                    self.result_type(&cb_type),
+                    rust_function_name,
+                    join_args(&["&self.ctx", &param_names]),
+                    self.await_suffix()
+                )
+            }
+            Some(OperationType::Database) if self.use_log_result_helper => {
+                format!(
+                    r#"{}{}fn {}({}) -> {} {{
+    let trace_id = self.ctx.logger().generate_trace_id();
+    {}!(self.ctx.logger(), "{}-T", trace_id{});
+    let ret = bugtags::{}({}){};
+    self.ctx.logger().log_result(&ret);
+    ret
+}}"#,
+                    self.visibility_keyword(Visibility::Pub),
+                    self.async_keyword(),
+                    rust_function_name,
+                    join_args(&["&self", &params_with_ref]),
+                    self.result_type(&cb_type),
+                    trace_macro,
+                    trace_tag,
+                    self.trace_params_args(),
+                    rust_function_name,
+                    join_args(&["&self.ctx", &param_names]),
+                    self.await_suffix()
+                )
+            }
+            Some(OperationType::Database) => {
+                format!(
+                    r#"{}{}fn {}({}) -> {} {{
+    let trace_id = self.ctx.logger().generate_trace_id();
+    {}!(self.ctx.logger(), "{}-T", trace_id{});
+    let ret = bugtags::{}({}){};
+    let str = match &ret {{
+        Ok(_) => "".to_string(),
+        Err(e) => e.to_string(),
+    }};
+    {}!(self.ctx.logger(), "{}-R", trace_id, "result", str);
+    ret
+}}"#,
+                    self.visibility_keyword(Visibility::Pub),
+                    self.async_keyword(),
+                    rust_function_name,
+                    join_args(&["&self", &params_with_ref]),
+                    self.result_type(&cb_type),
+                    trace_macro,
+                    trace_tag,
+                    self.trace_params_args(),
+                    rust_function_name,
+                    join_args(&["&self.ctx", &param_names]),
+                    self.await_suffix(),
+                    trace_macro,
+                    trace_tag
+                )
+            }
+            None => String::new(),
+        }
+    }
+
+    // 取消依赖 tokio::select!，只在生成 async 代码时可用
+    fn cancellable(&self) -> bool {
+        self.supports_cancel && self.async_mode
+    }
+
+    // 超时同样依赖 tokio，阻塞版本不加超时
+    fn timeout(&self) -> Option<u32> {
+        self.timeout_ms.filter(|_| self.async_mode)
+    }
+
+    fn resolved_timeout_error(&self) -> String {
+        let timeout_error = self.timeout_error.trim();
+        if timeout_error.is_empty() {
+            GenSpec::default().timeout_error
+        } else {
+            timeout_error.to_string()
+        }
+    }
+
+    fn resolved_test_support_path(&self) -> String {
+        let path = self.test_support_path.trim().trim_end_matches("::");
+        if path.is_empty() {
+            GenSpec::default().test_support_path
+        } else {
+            path.to_string()
+        }
+    }
+
+    fn resolved_cancel_error(&self) -> String {
+        let cancel_error = self.cancel_error.trim();
+        if cancel_error.is_empty() {
+            GenSpec::default().cancel_error
+        } else {
+            cancel_error.to_string()
+        }
+    }
+
+    // 流式响应可以多次回调，请求持有的回调只借用；其他情况回调一次后取走
+    fn shared_callback_access(&self) -> &'static str {
+        if self.operation_type == Some(OperationType::NetworkStream) {
+            "as_ref"
+        } else {
+            "take"
+        }
+    }
+
+    // 取消或超时时需要从请求之外完成回调，回调放进 Mutex<Option> 中共享，保证只回调一次
+    fn shared_request_callback(&self, cb_type: &str, reason: &str) -> String {
+        format!(
+            r#"    // {}时回调被取走，之后的响应不会再回调
+    let cb = Arc::new(std::sync::Mutex::new(Some(cb)));
+    let request_cb = cb.clone();
+    let request_cb = move |ret: {}| {{
+        if let Some(cb) = request_cb.lock().unwrap().{}() {{
+            cb(ret);
+        }}
+    }};
+"#,
+            reason,
+            self.result_type(cb_type),
+            self.shared_callback_access()
+        )
+    }
+
+    fn async_keyword(&self) -> &'static str {
+        if self.async_mode {
+            "async "
+        } else {
+            ""
+        }
+    }
+
+    fn await_suffix(&self) -> &'static str {
+        if self.async_mode {
+            ".await"
+        } else {
+            ""
+        }
+    }
+
+    // 等待一个 future：async 模式下 .await，阻塞模式下交给 runtime.block_on
+    fn blocking_call(&self, runtime: &str, future: &str) -> String {
+        if self.async_mode {
+            format!("{}.await", future)
+        } else {
+            format!("{}.block_on({})", runtime, future)
+        }
+    }
+
+    // 网络请求触发的协议方法和参数，便于对照抓包日志
+    fn wire_comment(&self) -> String {
+        if !self.document_wire {
+            return String::new();
+        }
+        let method = self.rmtp_method.trim();
+        format!(
+            "// Wire: method={}, params=[{}]\n",
+            if method.is_empty() { "TODO" } else { method },
+            self.clean_params(&self.function_params)
+        )
+    }
+
+    // 使用 tracing::instrument 时函数上方的属性，skip 中的参数不会记录到 span
+    fn instrument_attribute(&self, args: &str) -> String {
+        if self.use_tracing_instrument {
+            format!("#[tracing::instrument({})]\n", args)
+        } else {
+            String::new()
+        }
+    }
+
+    // 网络请求的日志和回调包装：手动 trace 时记录 -T/-R 日志；
+    // 使用 tracing::instrument 时调用由 span 记录，回调可能在 span 之外执行，需要重新进入 span 记录结果
+    fn traced_callback_wrapper(
+        &self,
+        cb_type: &str,
+        trace_tag: &str,
+        ok_match_pattern: &str,
+    ) -> String {
+        let (setup, log_result) = if self.use_tracing_instrument {
+            (
+                "    let span = tracing::Span::current();\n".to_string(),
+                "span.in_scope(|| tracing::info!(result = %str));".to_string(),
+            )
+        } else {
+            let trace_macro = self.resolved_trace_macro();
+            (
+                format!(
+                    r#"    let trace_id = self.ctx.logger().generate_trace_id();
+    {0}!(self.ctx.logger(), "{1}-T", trace_id{2});
+    let logger = self.ctx.logger().clone();
+"#,
+                    trace_macro,
+                    trace_tag,
+                    self.trace_params_args()
+                ),
+                format!(
+                    r#"{}!(logger, "{}-R", trace_id, "result", &str);"#,
+                    trace_macro, trace_tag
+                ),
+            )
+        };
+
+        format!(
+            r#"{}    let cb = move |ret: {}| {{
+        let str = match &ret {{
+            {},
+            Err(e) => e.to_string(),
+        }};
+        {}
+        cb(ret);
+    }};
+"#,
+            setup,
+            self.result_type(cb_type),
+            ok_match_pattern,
+            log_result
+        )
+    }
+
+    // 支持取消的网络请求：token 触发时用 Cancelled 错误完成回调，回调只会被调用一次
+    fn generate_cancellable_engine_async_function(
+        &self,
+        rust_function_name: &str,
+        cb_type: &str,
+        trace_tag: &str,
+        ok_match_pattern: &str,
+    ) -> String {
+        let params_with_ref = self.add_ref_to_str_params();
+        let param_names = self.extract_param_names();
+        // 同时设置了超时时，超时作为 select! 的第三个分支
+        let timeout_branch = match self.timeout() {
+            Some(ms) => format!(
+                r#"        _ = tokio::time::sleep(std::time::Duration::from_millis({})) => {{
+            if let Some(cb) = cb.lock().unwrap().take() {{
+                cb(Err({}));
+            }}
+        }}
+"#,
+                ms,
+                self.resolved_timeout_error()
+            ),
+            None => String::new(),
+        };
+        let reason = if timeout_branch.is_empty() {
+            "取消"
+        } else {
+            "取消或超时"
+        };
+
+        format!(
+            r#"// 需要引入: use {};
+{}{}async fn {}{}({}){}
+{{
+{}{}    tokio::select! {{
+        _ = bugtags::{}({}) => {{}}
+        _ = cancel_token.cancelled() => {{
+            if let Some(cb) = cb.lock().unwrap().take() {{
+                cb(Err({}));
+            }}
+        }}
+{}    }}
+}}"#,
+            self.resolved_cancel_token_import(),
+            self.instrument_attribute("skip(self, cancel_token, cb)"),
+            self.visibility_keyword(Visibility::Pub),
+            rust_function_name,
+            self.callback_generics(),
+            join_args(&[
+                "&self",
+                &params_with_ref,
+                &format!("cancel_token: {}", self.cancel_token_type()),
+                &format!("cb: {}", self.callback_param_type(cb_type)),
+            ]),
+            self.callback_where_clause(cb_type),
+            self.traced_callback_wrapper(cb_type, trace_tag, ok_match_pattern),
+            self.shared_request_callback(cb_type, reason),
+            rust_function_name,
+            join_args(&[
+                "&self.ctx",
+                &param_names,
+                "cancel_token.clone()",
+                &self.callback_arg("request_cb"),
+            ]),
+            self.resolved_cancel_error(),
+            timeout_branch
+        )
+    }
+
+    fn generate_module_function(&self, rust_function_name: &str) -> String {
+        let cb_type = self.resolved_return_type();
+
+        let params_with_ref = self.add_ref_to_str_params();
+        let param_names = self.extract_param_names();
+
+        match self.operation_type {
+            Some(OperationType::NetworkThenDb) => {
+                self.generate_network_then_db_module_function(rust_function_name)
+            }
+            Some(OperationType::Network | OperationType::NetworkStream) => {
+                format!(
+                    r#"{}{}fn {}{}(
+    ctx: &Arc<EngineContext>,{}{}
+    cb: {},
+){}
+{{
+{}{}
+}}"#,
+                    self.visibility_keyword(Visibility::PubCrate),
+                    self.async_keyword(),
+                    rust_function_name,
+                    self.callback_generics(),
+                    params_line(&params_with_ref),
+                    self.module_cancel_param(),
+                    self.callback_param_type(&cb_type),
+                    self.callback_where_clause(&cb_type),
+                    // 始终传递所有参数给 build_xxx_request 方法
+                    self.build_query_statement(rust_function_name, &param_names, "cb", "cb"),
+                    self.send_query_statement()
+                )
+            }
+            Some(OperationType::Database) => {
+                format!(
+                    r#"{}{}{}fn {}(
+    ctx: &Arc<EngineContext>,{}
+) -> {} {{
+    ctx.db_agent()
+        .{}({}){}
+}}"#,
+                    self.mpsc_import_note(),
+                    self.visibility_keyword(Visibility::PubCrate),
+                    self.async_keyword(),
+                    rust_function_name,
+                    params_line(&params_with_ref),
+                    self.db_return_type(&cb_type),
+                    rust_function_name,
+                    param_names,
+                    if self.async_mode {
+                        "\n        .await"
+                    } else {
+                        ""
+                    }
+                )
+            }
+            None => String::new(),
+        }
+    }
+
+    // 支持取消时 engine_async 会额外传入 token，模块在发送请求时监听取消
+    fn module_cancel_param(&self) -> String {
+        if !self.cancellable() {
+            return String::new();
+        }
+        format!("\n    cancel_token: {},", self.cancel_token_type())
+    }
+
+    // 模块中发送请求，可取消时和 token 一起 select!，取消后不再等待请求完成
+    fn send_query_statement(&self) -> String {
+        if !self.cancellable() {
+            return format!(
+                "    {};",
+                self.blocking_call("ctx.runtime()", "ctx.send_query(query)")
+            );
+        }
+        r#"    tokio::select! {
+        _ = ctx.send_query(query) => {}
+        // engine_async 已经在取消时回调了错误，这里只停止发送
+        _ = cancel_token.cancelled() => {}
+    }"#
+        .to_string()
+    }
+
+    // 模块中调用 build_xxx_request 得到 query，cb_arg 是传给 builder 的回调表达式
+    // builder 可能失败时回调先放进共享的 Option 再交给请求，构建失败时取出回调返回错误
+    fn build_query_statement(
+        &self,
+        rust_function_name: &str,
+        param_names: &str,
+        cb_var: &str,
+        cb_arg: &str,
+    ) -> String {
+        if !self.fallible_builder {
+            return format!(
+                r#"    let query = ctx
+        .request_builder()
+        .build_{}_request({});
+"#,
+                rust_function_name,
+                join_args(&[param_names, cb_arg])
+            );
+        }
+
+        format!(
+            r#"    // 构建请求失败时回调还没有交给请求，从共享的 Option 中取出回调返回错误
+    let {0} = Arc::new(std::sync::Mutex::new(Some({0})));
+    let request_cb = {0}.clone();
+    let request_cb = move |ret: {1}| {{
+        if let Some(cb) = request_cb.lock().unwrap().{2}() {{
+            cb(ret);
+        }}
+    }};
+    let query = match ctx
+        .request_builder()
+        .build_{3}_request({4})
+    {{
+        Ok(query) => query,
+        Err(e) => {{
+            if let Some(cb) = {0}.lock().unwrap().take() {{
+                cb(Err(e));
+            }}
+            return;
+        }}
+    }};
+"#,
+            cb_var,
+            self.result_type(&self.resolved_return_type()),
+            self.shared_callback_access(),
+            rust_function_name,
+            join_args(&[param_names, &self.callback_arg("request_cb")])
+        )
+    }
+
+    // 网络请求后写库：请求成功时把结果交给 db_agent 的 save_xxx 写入缓存，写入成功后再回调原值；
+    // save_xxx 和 db 模块中的其他函数一样由 db_agent / db_worker / db_sqlite 输出框生成
+    fn generate_network_then_db_module_function(&self, rust_function_name: &str) -> String {
+        let cb_type = self.resolved_return_type();
+
+        let params_with_ref = self.add_ref_to_str_params();
+        let param_names = self.extract_param_names();
+
+        format!(
+            r#"{}{}fn {}{}(
+    ctx: &Arc<EngineContext>,{}{}
+    cb: {},
+){}
+{{
+    let db_ctx = ctx.clone();
+    let on_response = move |ret: {}| {{
+        {} {{
+            let ret = match ret {{
+                Ok(value) => {{
+                    let saved = db_ctx.db_agent().save_{}(value.clone()){};
+                    saved.map(|_| value)
+                }}
+                Err(e) => Err(e),
+            }};
+            cb(ret);
+        }});
+    }};
+{}{}
+}}"#,
+            self.visibility_keyword(Visibility::PubCrate),
+            self.async_keyword(),
+            rust_function_name,
+            self.callback_generics(),
+            params_line(&params_with_ref),
+            self.module_cancel_param(),
+            self.callback_param_type(&cb_type),
+            self.callback_where_clause(&cb_type),
+            self.result_type(&cb_type),
+            // 阻塞版本的 db_agent 内部会 block_on，不能在 runtime 的任务中调用，写库放到独立线程
+            if self.async_mode {
+                "tokio::spawn(async move"
+            } else {
+                "std::thread::spawn(move ||"
+            },
+            rust_function_name,
+            self.await_suffix(),
+            self.build_query_statement(
+                rust_function_name,
+                &param_names,
+                "on_response",
+                &self.callback_arg("on_response")
+            ),
+            self.send_query_statement()
+        )
+    }
+
+    fn generate_request_builder_function(&self, rust_function_name: &str) -> String {
+        let cb_type = self.resolved_return_type();
+
+        // 使用规范化的参数处理方法
+        let params_with_ref = self.normalize_params_for_request_builder();
+
+        // 如果没有请求体名称，返回空字符串
+        if self.request_body_name.is_empty() {
+            return String::new();
+        }
+
+        // 生成 Pb 结构体名称（添加 "Pb" 前缀）
+        let pb_request_name = format!("Pb{}", self.request_body_name);
+
+        // 请求体结构名称（不带 "Pb" 前缀）
+        let request_name = &self.request_body_name;
+
+        // 构建函数名：在 rust_function_name 前添加 "build_"
+        let build_function_name = format!("build_{}_request", rust_function_name);
+
+        // 加载了 proto 时按字段生成 set_ 调用；没有设置任何字段时，消除警告需去掉 mut
+        let (pb_req_setters, has_setters) = self.generate_pb_req_setters(&params_with_ref);
+        let pb_req_mut = if self.suppress_warnings && !has_setters {
+            ""
+        } else {
+            "mut "
+        };
+
+        // Request 使用 Builder 模式时，必填参数传给 Builder::new，Option 参数通过 with_ 方法传入
+        let construct_req = if self.uses_request_builder() {
+            let arg = |name: &str, field_type: &str| {
+                if field_type == "String" && !self.prefer_owned_strings {
+                    format!("{}.to_string()", name)
+                } else {
+                    name.to_string()
+                }
+            };
+            let (optional, required): (Vec<_>, Vec<_>) = self
+                .request_struct_field_list()
+                .into_iter()
+                .partition(|(_, field_type)| is_option_type(field_type));
+            let new_args = required
+                .iter()
+                .map(|(name, field_type)| arg(name, field_type))
+                .collect::<Vec<_>>();
+            let with_calls = optional
+                .iter()
+                .map(|(name, field_type)| {
+                    format!("\n        .with_{}({})", name, arg(name, field_type))
+                })
+                .collect::<String>();
+            format!(
+                "{}Builder::new({}){}\n        .build({})",
+                request_name,
+                join_args(&["pb_req", &new_args.join(", ")]),
+                with_calls,
+                self.request_callback_arg()
+            )
+        } else if self.pass_params_to_request {
+            // 参数传递到 Request 时，按结构体 new 的签名转发额外参数
+            let extra_args = self.extract_param_names_for_request_new();
+            format!(
+                "{}::new({})",
+                request_name,
+                join_args(&["pb_req", self.request_callback_arg(), &extra_args])
+            )
+        } else {
+            format!(
+                "{}::new({})",
+                request_name,
+                join_args(&["pb_req", self.request_callback_arg()])
+            )
+        };
+
+        let build_query = "self.build_query(req.get_method(), \"\", req.get_qos(), Box::new(req))";
+        // Request 不保存回调时，回调按 query 的 msg_uid 放入回调池
+        let build_query = if self.request_stores_callback {
+            build_query.to_string()
+        } else {
+            format!(
+                "// 回调没有保存在 Request 中，放入回调池，收到响应时按 msg_uid 取出\n    let query = {};\n    self.insert_callback(query.msg_uid(), cb);\n    query",
+                build_query
+            )
+        };
+        let (return_type, build_query) = if self.fallible_builder {
+            let build_query = match build_query.rsplit_once('\n') {
+                Some((head, query)) => format!("{}\n    Ok({})", head, query.trim()),
+                None => format!("Ok({})", build_query),
+            };
+            (self.result_type("RmtpQuery"), build_query)
+        } else {
+            ("RmtpQuery".to_string(), build_query)
+        };
+
+        format!(
+            r#"{}fn {}{}(
+    &self,{}
+    cb: {},
+) -> {}{}
+{{
+    let {}pb_req = {}::new();{}
+    let req = {};
+    {}
+}}"#,
+            self.visibility_keyword(Visibility::PubCrate),
+            build_function_name,
+            self.callback_generics(),
+            params_line(&params_with_ref),
+            self.callback_param_type(&cb_type),
+            return_type,
+            self.callback_where_clause(&cb_type),
+            pb_req_mut,
+            pb_request_name,
+            pb_req_setters,
+            construct_req,
+            build_query
+        )
+    }
+
+    // proto 消息名，为空时使用请求体名称
+    pub fn resolved_proto_message_name(&self) -> String {
+        let name = self.proto_message_name.trim();
+        if name.is_empty() {
+            self.request_body_name.clone()
+        } else {
+            name.to_string()
+        }
+    }
+
+    pub fn proto_fields(&self) -> Vec<ProtoField> {
+        if self.proto_source.is_empty() {
+            return Vec::new();
+        }
+        parse_proto_message(&self.proto_source, &self.resolved_proto_message_name())
+    }
+
+    // 为每个 proto 字段生成 pb_req.set_<field>(...)，有同名参数时直接传入，否则生成注释
+    // 返回生成的代码和是否有实际的 set 调用
+    fn generate_pb_req_setters(&self, builder_params: &str) -> (String, bool) {
+        let params: Vec<(&str, &str)> = split_top_level_params(builder_params)
+            .into_iter()
+            .filter_map(|param| {
+                let (name, param_type) = param.split_once(':')?;
+                Some((name.trim(), param_type.trim()))
+            })
+            .collect();
+
+        let mut has_setters = false;
+        let setters = self
+            .pb_request_fields()
+            .iter()
+            .map(|(field_name, field_type)| {
+                match params.iter().find(|(name, _)| name == field_name) {
+                    Some((name, param_type)) => {
+                        has_setters = true;
+                        let value = if field_type.starts_with("Vec<") {
+                            format!("{}.into()", name)
+                        } else if *param_type == "&str" {
+                            format!("{}.to_string()", name)
+                        } else if self.fallible_builder && param_type != field_type {
+                            // 例如 i32 转换为 proto 枚举，取值无效时返回错误
+                            format!(
+                                "{}.try_into().map_err(|_| {})?",
+                                name,
+                                self.resolved_guard_error()
+                            )
+                        } else {
+                            name.to_string()
+                        };
+                        format!("\n    pb_req.set_{}({});", field_name, value)
+                    }
+                    None => format!("\n    // pb_req.set_{}(...);", field_name),
+                }
+            })
+            .collect();
+        (setters, has_setters)
+    }
+
+    // pb_req 的字段 (名称, rust 类型)：优先使用手填的请求体字段，否则取 proto 消息字段
+    fn pb_request_fields(&self) -> Vec<(String, String)> {
+        let fields = parse_request_fields(&self.request_fields);
+        if !fields.is_empty() {
+            return fields;
+        }
+        self.proto_fields()
+            .iter()
+            .map(|field| (field.name.clone(), field.rust_type()))
+            .collect()
+    }
+
+    // 函数签名上的泛型参数，Box<dyn FnOnce> 风格不需要泛型
+    fn callback_generics(&self) -> &'static str {
+        match self.callback_style {
+            CallbackStyle::Generic => "<CB>",
+            CallbackStyle::BoxedDyn => "",
+        }
+    }
+
+    pub fn is_network(&self) -> bool {
+        self.operation_type
+            .as_ref()
+            .is_some_and(OperationType::is_network)
+    }
+
+    // 回调的约束：流式响应的回调会被多次调用，需要 Fn，并且 &self 跨 await 需要 Sync
+    fn callback_bound(&self, cb_type: &str) -> String {
+        if self.multi_shot_callback() || self.cb_pool_variant == CbPoolVariant::Fn {
+            format!("Fn({}) + Send + Sync + 'static", self.result_type(cb_type))
+        } else {
+            format!("FnOnce({}) + Send + 'static", self.result_type(cb_type))
+        }
+    }
+
+    // 流式响应和 mpsc 通道分批返回的数据库结果都会多次回调
+    fn multi_shot_callback(&self) -> bool {
+        self.operation_type == Some(OperationType::NetworkStream) || self.streams_db_results()
+    }
+
+    // 数据库操作使用 mpsc 通道时，db_agent、module 和 engine_async 都返回接收端
+    fn streams_db_results(&self) -> bool {
+        self.operation_type == Some(OperationType::Database)
+            && self.channel_kind == ChannelKind::Mpsc
+    }
+
+    // 数据库操作在 module 和 engine_async 中的返回值类型，和 db_agent 保持一致
+    fn db_return_type(&self, cb_type: &str) -> String {
+        if self.streams_db_results() {
+            format!("mpsc::Receiver<{}>", self.result_type(cb_type))
+        } else {
+            self.result_type(cb_type)
+        }
+    }
+
+    fn mpsc_import_note(&self) -> &'static str {
+        if self.streams_db_results() {
+            MPSC_IMPORT_NOTE
+        } else {
+            ""
+        }
+    }
+
+    // engine_sync 中调用数据库操作后回调结果，mpsc 通道每收到一批结果回调一次
+    fn engine_sync_db_statements(&self, rust_function_name: &str) -> String {
+        let call = format!(
+            "{}.{}({}){}",
+            self.engine_sync_receiver(),
+            rust_function_name,
+            self.extract_param_names_for_call(),
+            self.await_suffix()
+        );
+        if !self.streams_db_results() {
+            return format!("let ret = {};\ncb(ret);", call);
+        }
+        let recv = if self.async_mode {
+            "recv().await"
+        } else {
+            "blocking_recv()"
+        };
+        format!(
+            "let mut rx = {};\nwhile let Some(ret) = rx.{} {{\n    cb(ret);\n}}",
+            call, recv
+        )
+    }
+
+    // engine 层的结果类型，开启别名时写成 EngineResult<T>
+    fn result_type(&self, ok_type: &str) -> String {
+        if self.use_result_alias {
+            format!("{}<{}>", self.resolved_result_alias(), ok_type)
+        } else {
+            format!("Result<{}, EngineError>", ok_type)
+        }
+    }
+
+    // cb 参数的类型
+    fn callback_param_type(&self, cb_type: &str) -> String {
+        match self.callback_style {
+            CallbackStyle::Generic => "CB".to_string(),
+            CallbackStyle::BoxedDyn => format!("Box<dyn {}>", self.callback_bound(cb_type)),
+        }
+    }
+
+    // 泛型 CB 的 where 子句，紧跟在函数签名之后
+    fn callback_where_clause(&self, cb_type: &str) -> String {
+        match self.callback_style {
+            CallbackStyle::Generic => {
+                format!("\nwhere\n    CB: {},", self.callback_bound(cb_type))
+            }
+            CallbackStyle::BoxedDyn => String::new(),
+        }
+    }
+
+    // 将闭包传给下一层时，Box<dyn FnOnce> 风格需要先装箱
+    fn callback_arg(&self, name: &str) -> String {
+        match self.callback_style {
+            CallbackStyle::Generic => name.to_string(),
+            CallbackStyle::BoxedDyn => format!("Box::new({})", name),
+        }
+    }
+
+    // 日志宏名称，为空时使用默认值
+    fn resolved_trace_macro(&self) -> String {
+        let trace_macro = self.trace_macro.trim().trim_end_matches('!');
+        if trace_macro.is_empty() {
+            GenSpec::default().trace_macro
+        } else {
+            trace_macro.to_string()
+        }
+    }
+
+    // db_worker 日志宏名称，为空时使用默认值
+    fn resolved_db_log_macro(&self) -> String {
+        let db_log_macro = self.db_log_macro.trim().trim_end_matches('!');
+        if db_log_macro.is_empty() {
+            GenSpec::default().db_log_macro
+        } else {
+            db_log_macro.to_string()
+        }
+    }
+
+    // 日志标签前缀，为空时使用默认值
+    fn resolved_trace_tag_prefix(&self) -> String {
+        let prefix = self.trace_tag_prefix.trim();
+        if prefix.is_empty() {
+            GenSpec::default().trace_tag_prefix
+        } else {
+            prefix.to_string()
+        }
+    }
+
+    // 网络层和数据库层的会话类型名称，为空时使用默认值
+    fn resolved_conv_type_names(&self) -> (String, String) {
+        let defaults = GenSpec::default();
+        let resolve = |name: &str, default: String| {
+            let name = name.trim();
+            if name.is_empty() {
+                default
+            } else {
+                name.to_string()
+            }
+        };
+        (
+            resolve(&self.conv_type_name, defaults.conv_type_name),
+            resolve(&self.db_conv_type_name, defaults.db_conv_type_name),
+        )
+    }
+
+    // 生成函数签名前的可见性关键字，未选择时使用该输出框的默认值
+    fn visibility_keyword(&self, default: Visibility) -> &'static str {
+        self.visibility.unwrap_or(default).keyword()
+    }
+
+    // 整数参数在枚举表中有对应类型时返回枚举类型名
+    fn enum_type_for_param(&self, param_name: &str, param_type: &str) -> Option<String> {
+        if !matches!(
+            param_type,
+            "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize"
+        ) {
+            return None;
+        }
+        self.enum_param_table.split(',').find_map(|entry| {
+            let (name, enum_type) = entry.split_once('=')?;
+            let enum_type = enum_type.trim();
+            (name.trim() == param_name && !enum_type.is_empty()).then(|| enum_type.to_string())
+        })
+    }
+
+    // engine_sync 中把整数参数转换为枚举，例如 let status = MessageStatus::from(status);
+    fn generate_enum_param_conversions(&self) -> String {
+        split_top_level_params(&self.clean_params_with(&self.function_params, false))
+            .into_iter()
+            .filter_map(|param| {
+                let (name, param_type) = param.split_once(':')?;
+                let name = name.trim();
+                let enum_type = self.enum_type_for_param(name, param_type.trim())?;
+                Some(format!("    let {0} = {1}::from({0});\n", name, enum_type))
+            })
+            .collect()
+    }
+
+    fn resolved_cb_pool_fn(&self) -> String {
+        let cb_pool_fn = self.cb_pool_fn.trim();
+        if cb_pool_fn.is_empty() {
+            GenSpec::default().cb_pool_fn
+        } else {
+            cb_pool_fn.to_string()
+        }
+    }
+
+    // 流式响应和分批返回的数据库结果会多次回调，FnOnce 回调池只能回调一次，改用按 Fn 保存回调的 cb_pool
+    fn engine_sync_cb_pool_fn(&self) -> String {
+        if self.multi_shot_callback() && self.cb_pool_variant == CbPoolVariant::FnOnce {
+            "cb_pool".to_string()
+        } else {
+            self.resolved_cb_pool_fn()
+        }
+    }
+
+    fn resolved_result_alias(&self) -> String {
+        let result_alias = self.result_alias.trim();
+        if result_alias.is_empty() {
+            GenSpec::default().result_alias
+        } else {
+            result_alias.to_string()
+        }
+    }
+
+    pub fn resolved_engine_type(&self) -> String {
+        let engine_type = self.engine_type.trim();
+        if engine_type.is_empty() {
+            GenSpec::default().engine_type
+        } else {
+            engine_type.to_string()
+        }
+    }
+
+    // 参数校验失败时的错误，为空时使用默认值
+    fn resolved_guard_error(&self) -> String {
+        let guard_error = self.guard_error.trim();
+        if guard_error.is_empty() {
+            GenSpec::default().guard_error
+        } else {
+            guard_error.to_string()
+        }
+    }
+
+    // engine_sync 开头的参数校验：字符串不能为空，分页数量必须为正，偏移量不能为负
+    fn generate_param_guards(&self) -> String {
+        if !self.generate_guards {
+            return String::new();
+        }
+
+        let guard_error = self.resolved_guard_error();
+        split_top_level_params(&self.clean_params(&self.function_params))
+            .into_iter()
+            .filter_map(|param| {
+                let (name, param_type) = param.split_once(':')?;
+                let (name, param_type) = (name.trim(), param_type.trim());
+                let condition = match (name, param_type) {
+                    (_, "&str" | "String") => format!("{}.is_empty()", name),
+                    ("limit" | "count" | "page_size", "i32" | "i64" | "isize") => {
+                        format!("{} <= 0", name)
+                    }
+                    ("limit" | "count" | "page_size", "u32" | "u64" | "usize") => {
+                        format!("{} == 0", name)
+                    }
+                    ("offset", "i32" | "i64" | "isize") => format!("{} < 0", name),
+                    _ => return None,
+                };
+                Some(format!(
+                    "    if {} {{\n        return cb(Err(err!({})));\n    }}\n",
+                    condition, guard_error
+                ))
+            })
+            .collect()
+    }
+
+    // CancellationToken 的完整导入路径，为空时使用默认值
+    fn resolved_cancel_token_import(&self) -> String {
+        let import = self.cancel_token_import.trim().trim_end_matches(';');
+        if import.is_empty() {
+            GenSpec::default().cancel_token_import
+        } else {
+            import.to_string()
+        }
+    }
+
+    // 导入路径的最后一段作为参数类型名
+    fn cancel_token_type(&self) -> String {
+        let import = self.resolved_cancel_token_import();
+        import.rsplit("::").next().unwrap_or_default().to_string()
+    }
+
+    // Java 时间类型对应的 Rust 类型 (毫秒时间戳)，为空时使用默认值
+    pub fn resolved_timestamp_type(&self) -> String {
+        let timestamp_type = self.timestamp_type.trim();
+        if timestamp_type.is_empty() {
+            GenSpec::default().timestamp_type
+        } else {
+            timestamp_type.to_string()
+        }
+    }
+
+    // 根据参数类型规范化参数名称
+    fn normalize_param_name(&self, param_name: &str, param_type: &str) -> String {
+        // 如果类型是 ConversationType 或 DbConversationType，统一使用 conv_type
+        let (conv_type_name, db_conv_type_name) = self.resolved_conv_type_names();
+        if param_type == conv_type_name || param_type == db_conv_type_name {
+            "conv_type".to_string()
+        } else {
+            param_name.to_string()
+        }
+    }
+
+    // 规范化参数，确保格式为 "name: type"
+    fn normalize_params_for_request_builder(&self) -> String {
+        split_top_level_params(&self.clean_params(&self.function_params))
+            .into_iter()
+            .filter_map(|param| {
+                let trimmed = param.trim();
+                if trimmed.is_empty() {
+                    return None;
+                }
+
+                // 分割参数为名称和类型
+                let parts: Vec<&str> = trimmed.split(':').map(|s| s.trim()).collect();
+                if parts.len() != 2 {
+                    return Some(trimmed.to_string());
+                }
+
+                let param_name = parts[0];
+                let mut param_type = parts[1].trim_end_matches(',').trim();
+
+                // 如果类型是 String，转换为 &str（使用 String 所有权参数时保持不变）
+                if param_type == "String" && !self.prefer_owned_strings {
+                    param_type = "&str";
+                }
+
+                // 规范化参数名称
+                let normalized_name = self.normalize_param_name(param_name, param_type);
+
+                // 返回正确格式: name: type
+                Some(format!("{}: {}", normalized_name, param_type))
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn generate_request_struct(&self) -> String {
+        let cb_type = self.resolved_return_type();
+
+        let pb_request_name = format!("Pb{}", self.request_body_name);
+
+        // 根据开关状态决定是否生成额外的成员变量
+        let (extra_fields, extra_new_params, extra_field_inits) = if self.pass_params_to_request {
+            // 开关打开，生成额外的成员变量
+            (
+                self.generate_struct_fields(),
+                self.generate_new_params(),
+                self.generate_field_inits(),
+            )
+        } else {
+            // 开关关闭，不生成额外的成员变量
+            (String::new(), String::new(), String::new())
+        };
+
+        // 决定结构体成员和 new 方法的内容，不保存回调时没有 cb 成员
+        let (cb_field, cb_param) = if self.request_stores_callback {
+            ("\n    cb: CB,", "cb: CB")
+        } else {
+            ("", "")
+        };
+        let struct_fields = if extra_fields.is_empty() {
+            format!("    pb_req: {},{}", pb_request_name, cb_field)
+        } else {
+            format!(
+                "    pb_req: {},{}\n{}",
+                pb_request_name, cb_field, extra_fields
+            )
+        };
+
+        let new_params = join_args(&[
+            &format!("pb_req: {}", pb_request_name),
+            cb_param,
+            &extra_new_params,
+        ]);
+
+        // 成员都没有用到 CB 时（不保存回调的变体）需要 PhantomData 占位，否则会报未使用的类型参数
+        let (marker_field, marker_init) = phantom_marker(&struct_fields, "CB");
+        let struct_fields = struct_fields + &marker_field;
+
+        let field_init = format!(
+            "Self {{ {} }}",
+            join_args(&[
+                "pb_req",
+                self.request_callback_arg(),
+                &extra_field_inits,
+                &marker_init
+            ])
+        );
+
+        let cb_bound = self.callback_bound(&cb_type);
+
+        // 不传参数时 pb_req 可以直接用默认值构造，只是方便测试的构造函数，不属于 Request trait
+        let default_request = if self.generate_default_request && !self.pass_params_to_request {
+            format!(
+                r#"
+
+    pub(crate) fn default_request({}) -> Self {{
+        Self::new({})
+    }}"#,
+                cb_param,
+                join_args(&[
+                    &format!("{}::new()", pb_request_name),
+                    self.request_callback_arg()
+                ])
+            )
+        } else {
+            String::new()
+        };
+
+        // 参数较多时可以用 Builder 代替带一长串参数的 new
+        let constructor = if self.uses_request_builder() {
+            self.generate_request_builder_struct(&pb_request_name, &cb_type, &marker_init)
+        } else {
+            format!(
+                r#"impl<CB> {}<CB>
+where
+    CB: {},
+{{
+    pub(crate) fn new({}) -> Self {{
+        {}
+    }}{}
+}}"#,
+                self.request_body_name, cb_bound, new_params, field_init, default_request
+            )
+        };
+
+        // 流式响应会多次收到响应，deal_with_response 不能消耗 self
+        let receiver = if self.operation_type == Some(OperationType::NetworkStream) {
+            "&self"
+        } else {
+            "self: Box<Self>"
+        };
+
+        // 解析响应的占位代码，元组返回值按元素个数生成占位
+        let parse_stub = match tuple_element_types(&cb_type) {
+            Some(element_types) => format!("({})", vec!["..."; element_types.len()].join(", ")),
+            None => "...".to_string(),
+        };
+
+        // deal_with_response 中未使用的参数，消除警告时加 _ 前缀；不保存回调时要用 ctx 和 msg_uid 取回调
+        let (ctx_name, timestamp_name, msg_uid_name) =
+            match (self.suppress_warnings, self.request_stores_callback) {
+                (true, true) => ("_ctx", "_timestamp", "_msg_uid"),
+                (true, false) => ("ctx", "_timestamp", "msg_uid"),
+                (false, _) => ("ctx", "timestamp", "msg_uid"),
+            };
+        let take_callback = if self.request_stores_callback {
+            ""
+        } else {
+            r#"        // 回调没有保存在 Request 中，按 msg_uid 从回调池中取出
+        let Some(cb) = ctx.take_callback::<CB>(msg_uid) else {
+            return;
+        };
+"#
+        };
+        let cb_call = self.request_callback_call();
+
+        // 新版工具链可以在 trait 中直接写 async fn，此时不需要 async_trait 宏，但 Request trait 也要同步修改
+        let (async_trait_import, async_trait_attribute) = if self.native_async_trait {
+            (
+                "",
+                "// 注意：使用原生 async fn，Request trait 的定义也需要去掉 #[async_trait]\n",
+            )
+        } else {
+            ("use async_trait::async_trait;\n", "#[async_trait]\n")
+        };
+
+        // write_to_bytes 得到的是 Vec<u8>，使用 Bytes 时转换一次，不复制数据
+        let pb_buffer = self.pb_buffer.type_name();
+        let (bytes_import, pb_data_expr) = match self.pb_buffer {
+            PbBuffer::VecU8 => ("", "self.pb_req.write_to_bytes().unwrap_or_default()"),
+            PbBuffer::Bytes => (
+                "use bytes::Bytes;\n",
+                "Bytes::from(self.pb_req.write_to_bytes().unwrap_or_default())",
+            ),
+        };
+
+        let code = format!(
+            r#"use crate::engine_context::EngineContext;
+use crate::engine_def::{{EngineError}};
+use crate::rmtp::request::request_trait::Request;
+use crate::rmtp::rmtp_def::RmtpQos;
+{}{}use protobuf::Message;
+use rust_universal_logger::err;
+use std::sync::Arc;
+
+pub(crate) struct {}<CB>
+where
+    CB: {},
+{{
+{}
+}}
+
+{}
+
+{}impl<CB> Request for {}<CB>
+where
+    CB: {},
+{{
+    fn get_method(&self) -> String {{
+        "{}".to_string()
+    }}
+
+    fn get_qos(&self) -> RmtpQos {{
+        RmtpQos::QosAtLastOnce
+    }}
+
+    async fn deal_with_response(
+        {},
+        {}: &Arc<EngineContext>,
+        code: EngineError,
+        {}: i64,
+        {}: String,
+        pb_data: Option<{}>,
+    ) {{
+{}{}
+        let pb_data = match pb_data {{
+            Some(pb_data) => pb_data,
+            None => return {}(Err(err!(EngineError::NetDataParserFailed))),
+        }};
+
+        // if EngineError::Success == code {{
+        //     {}(Ok(()));
+        // }} else {{
+        //     {}(Err(code));
+        // }}
+        
+        // TODO: 解析响应数据
+        // let ret: {} = {};
+        // {}(Ok(ret));
+    }}
+
+    fn get_pb_data(&self) -> {} {{
+        {}
+    }}
+}}"#,
+            async_trait_import,
+            bytes_import,
+            self.request_body_name,
+            cb_bound,
+            struct_fields,
+            constructor,
+            async_trait_attribute,
+            self.request_body_name,
+            cb_bound,
+            self.rmtp_method.trim(),
+            receiver,
+            ctx_name,
+            timestamp_name,
+            msg_uid_name,
+            pb_buffer,
+            take_callback,
+            self.generate_response_code_check(),
+            cb_call,
+            cb_call,
+            cb_call,
+            cb_type,
+            parse_stub,
+            cb_call,
+            pb_buffer,
+            pb_data_expr
+        );
+        prune_unused_imports(&code)
+    }
+
+    // deal_with_response 开头对错误码的检查，错误码映射表不为空时生成 match
+    fn generate_response_code_check(&self) -> String {
+        let mappings: Vec<(&str, &str)> = self
+            .error_code_table
+            .split(',')
+            .filter_map(|entry| {
+                let (code, error) = entry.split_once("=>")?;
+                let (code, error) = (code.trim(), error.trim());
+                (!code.is_empty() && !error.is_empty()).then_some((code, error))
+            })
+            .collect();
+
+        let cb_call = self.request_callback_call();
+        if mappings.is_empty() {
+            return format!(
+                r#"        if EngineError::Success != code {{
+            {}(Err(code));
+            return;
+        }}
+"#,
+                cb_call
+            );
+        }
+
+        let arms = mappings
+            .iter()
+            .map(|(code, error)| {
+                format!(
+                    "            {} => return {}(Err({})),\n",
+                    code, cb_call, error
+                )
+            })
+            .collect::<String>();
+        format!(
+            r#"        match code {{
+            EngineError::Success => {{}}
+{}            _ => return {}(Err(code)),
+        }}
+"#,
+            arms, cb_call
+        )
+    }
+
+    // Request 保存回调时通过 self.cb 调用，否则调用 deal_with_response 中从回调池取出的 cb
+    fn request_callback_call(&self) -> &'static str {
+        if self.request_stores_callback {
+            "(self.cb)"
+        } else {
+            "cb"
+        }
+    }
+
+    // 构造 Request 时传入的回调，不保存回调时为空
+    fn request_callback_arg(&self) -> &'static str {
+        if self.request_stores_callback {
+            "cb"
+        } else {
+            ""
+        }
+    }
+
+    // request builder 调用 Request::new 时的额外实参，需与 generate_new_params 的签名一致；
+    // builder 中 String 参数是 &str，传给 new 时需要转换
+    fn extract_param_names_for_request_new(&self) -> String {
+        split_top_level_params(&self.clean_params(&self.function_params))
+            .into_iter()
+            .filter_map(|param| {
+                let (name, param_type) = param.trim().split_once(':')?;
+                let (name, param_type) = (name.trim(), param_type.trim());
+                if name.is_empty() {
+                    return None;
+                }
+
+                let normalized_name = self.normalize_param_name(name, param_type);
+                if param_type == "String" && !self.prefer_owned_strings {
+                    Some(format!("{}.to_string()", normalized_name))
+                } else {
+                    Some(normalized_name)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // Builder 模式只在参数传递到 Request 且确实有额外参数时生效
+    fn uses_request_builder(&self) -> bool {
+        self.use_builder_pattern
+            && self.pass_params_to_request
+            && !self.request_struct_field_list().is_empty()
+    }
+
+    // Request 结构体的额外成员 (规范化后的名称, 类型)，&str 存为 String
+    fn request_struct_field_list(&self) -> Vec<(String, String)> {
+        split_top_level_params(&self.clean_params(&self.function_params))
+            .into_iter()
+            .filter_map(|param| {
+                let (name, param_type) = param.trim().split_once(':')?;
+                let (name, param_type) = (name.trim(), param_type.trim());
+                if name.is_empty() || param_type.is_empty() {
+                    return None;
+                }
+
+                let field_type = if param_type == "&str" {
+                    "String"
+                } else {
+                    param_type
+                };
+                Some((
+                    self.normalize_param_name(name, param_type),
+                    field_type.to_string(),
+                ))
+            })
+            .collect()
+    }
+
+    fn generate_request_builder_struct(
+        &self,
+        pb_request_name: &str,
+        cb_type: &str,
+        marker_init: &str,
+    ) -> String {
+        let fields = self.request_struct_field_list();
+        let builder_name = format!("{}Builder", self.request_body_name);
+
+        // 必填参数是 new 的参数，build 时不会缺少字段；只有 Option 参数可以通过 with_ 方法选填，默认为 None
+        let builder_fields = fields
+            .iter()
+            .map(|(name, field_type)| format!("    {}: {},", name, field_type))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let new_params = fields
+            .iter()
+            .filter(|(_, field_type)| !is_option_type(field_type))
+            .map(|(name, field_type)| format!("{}: {}", name, field_type))
+            .collect::<Vec<_>>();
+
+        let new_inits = fields
+            .iter()
+            .map(|(name, field_type)| {
+                if is_option_type(field_type) {
+                    format!("{}: None", name)
+                } else {
+                    name.clone()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let with_methods = fields
+            .iter()
+            .filter(|(_, field_type)| is_option_type(field_type))
+            .map(|(name, field_type)| {
+                format!(
+                    r#"
+
+    pub(crate) fn with_{0}(mut self, {0}: {1}) -> Self {{
+        self.{0} = {0};
+        self
+    }}"#,
+                    name, field_type
+                )
+            })
+            .collect::<String>();
+
+        let build_inits = fields
+            .iter()
+            .map(|(name, _)| format!("            {0}: self.{0},", name))
+            .chain((!marker_init.is_empty()).then(|| format!("            {},", marker_init)))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        format!(
+            r#"pub(crate) struct {0} {{
+    pb_req: {1},
+{2}
+}}
+
+impl {0} {{
+    pub(crate) fn new({10}) -> Self {{
+        Self {{ {3} }}
+    }}{4}
+
+    pub(crate) fn build<CB>(self{8}) -> {5}<CB>
+    where
+        CB: {6},
+    {{
+        {5} {{
+            pb_req: self.pb_req,{9}
+{7}
+        }}
+    }}
+}}"#,
+            builder_name,
+            pb_request_name,
+            builder_fields,
+            join_args(&["pb_req", &new_inits.join(", ")]),
+            with_methods,
+            self.request_body_name,
+            self.callback_bound(cb_type),
+            build_inits,
+            if self.request_stores_callback {
+                ", cb: CB"
+            } else {
+                ""
+            },
+            if self.request_stores_callback {
+                "\n            cb,"
+            } else {
+                ""
+            },
+            join_args(&[
+                &format!("pb_req: {}", pb_request_name),
+                &new_params.join(", ")
+            ])
+        )
+    }
+
+    fn resolved_feature_gate(&self) -> Option<&str> {
+        let feature = self.feature_gate.trim().trim_matches('"');
+        (!feature.is_empty()).then_some(feature)
+    }
+
+    // 设置了 feature 时，在函数等单个条目前加 #[cfg(feature = "...")]
+    fn gate_item(&self, code: String) -> String {
+        match self.resolved_feature_gate() {
+            Some(feature) if !code.is_empty() => {
+                format!("#[cfg(feature = \"{}\")]\n{}", feature, code)
+            }
+            _ => code,
+        }
+    }
+
+    // request builder 和测试方法只在勾选后才加 feature 条件
+    fn gate_request_and_test_item(&self, code: String) -> String {
+        if self.feature_gate_request_and_test {
+            self.gate_item(code)
+        } else {
+            code
+        }
+    }
+
+    // 请求体结构是一个完整文件，用 #![cfg(...)] 作用于整个文件
+    fn gate_file(&self, code: String) -> String {
+        match self.resolved_feature_gate() {
+            Some(feature) if !code.is_empty() => {
+                format!("#![cfg(feature = \"{}\")]\n\n{}", feature, code)
+            }
+            _ => code,
+        }
+    }
+
+    // 回调返回值类型，为空时所有层统一使用配置的默认返回值类型 (默认为 ())
+    fn resolved_return_type(&self) -> String {
+        let return_type = self.callback_return_type.trim();
+        if !return_type.is_empty() {
+            return return_type.to_string();
+        }
+        let default_return_type = self.default_return_type.trim();
+        if default_return_type.is_empty() {
+            GenSpec::default().default_return_type
+        } else {
+            default_return_type.to_string()
+        }
+    }
+
+    // 只有返回值不是 () 时才需要把 Pb 类型转换为返回值类型
+    pub fn needs_from_conversion(&self) -> bool {
+        let return_type = self.callback_return_type.trim();
+        !self.request_body_name.is_empty() && !return_type.is_empty() && return_type != "()"
+    }
+
+    fn generate_from_conversion(&self) -> String {
+        let pb_request_name = format!("Pb{}", self.request_body_name);
+        let fields = self.pb_request_fields();
+
+        // 字段已知且返回值是结构体时，按字段生成赋值，无法映射的字段使用 todo!()
+        let return_type = self.callback_return_type.trim();
+        let is_struct = return_type.starts_with(|c: char| c.is_ascii_uppercase())
+            && return_type.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if is_struct && !fields.is_empty() {
+            let assignments: Vec<(String, Option<String>)> = fields
+                .iter()
+                .map(|(name, field_type)| (name.clone(), self.pb_field_getter(name, field_type)))
+                .collect();
+            let pb_mut = if assignments
+                .iter()
+                .any(|(_, getter)| getter.as_ref().is_some_and(|g| g.starts_with("pb.take_")))
+            {
+                "mut "
+            } else {
+                ""
+            };
+            let field_lines = assignments
+                .iter()
+                .map(|(name, getter)| {
+                    format!(
+                        "            {}: {},\n",
+                        name,
+                        getter.as_deref().unwrap_or("todo!()")
+                    )
+                })
+                .collect::<String>();
+
+            return format!(
+                r#"impl From<{0}> for {1} {{
+    fn from({2}pb: {0}) -> Self {{
+        Self {{
+{3}        }}
+    }}
+}}"#,
+                pb_request_name, return_type, pb_mut, field_lines
+            );
+        }
+
+        // 函数体只有 todo!()，消除警告时参数加 _ 前缀
+        let pb_name = if self.suppress_warnings { "_pb" } else { "pb" };
+
+        // 返回值不是结构体时只为每个字段生成一行映射提示
+        let field_hints = fields
+            .iter()
+            .map(|(name, field_type)| format!("        // {0}: pb.{0} ({1})\n", name, field_type))
+            .collect::<String>();
+
+        format!(
+            r#"impl From<{0}> for {1} {{
+    fn from({2}: {0}) -> Self {{
+{3}        todo!()
+    }}
+}}"#,
+            pb_request_name, self.callback_return_type, pb_name, field_hints
+        )
+    }
+
+    // 按 rust-protobuf 的访问器从 pb 取出字段：标量用 get_，String、bytes、集合和消息用 take_ 取走所有权，
+    // 重复的消息字段逐个 Into 转换；类型无法识别时返回 None
+    fn pb_field_getter(&self, name: &str, field_type: &str) -> Option<String> {
+        let is_copy = |rust_type: &str| {
+            matches!(
+                rust_type,
+                "bool" | "i32" | "i64" | "u32" | "u64" | "f32" | "f64"
+            ) || self.is_proto_enum(rust_type)
+        };
+        let is_message = |rust_type: &str| {
+            rust_type.starts_with(|c: char| c.is_ascii_uppercase())
+                && rust_type.chars().all(|c| c.is_alphanumeric() || c == '_')
+        };
+
+        if is_copy(field_type) {
+            Some(format!("pb.get_{}()", name))
+        } else if matches!(field_type, "String" | "Vec<u8>") || field_type.starts_with("HashMap<") {
+            Some(format!("pb.take_{}()", name))
+        } else if let Some(element) = field_type
+            .strip_prefix("Vec<")
+            .and_then(|rest| rest.strip_suffix('>'))
+        {
+            if is_copy(element) || element == "String" || element == "Vec<u8>" {
+                Some(format!("pb.take_{}().into_vec()", name))
+            } else if is_message(element) {
+                Some(format!(
+                    "pb.take_{}().into_iter().map(Into::into).collect()",
+                    name
+                ))
+            } else {
+                None
+            }
+        } else if is_message(field_type) {
+            Some(format!("pb.take_{}().into()", name))
+        } else {
+            None
+        }
+    }
+
+    // 加载的 proto 中声明了同名 enum
+    fn is_proto_enum(&self, rust_type: &str) -> bool {
+        let words: Vec<&str> = self
+            .proto_source
+            .split(|c: char| c.is_whitespace() || c == '{')
+            .filter(|word| !word.is_empty())
+            .collect();
+        words.windows(2).any(|pair| pair == ["enum", rust_type])
+    }
+
+    // 测试中对返回值的断言：集合类型断言非空，简单类型在填写了期望返回值时断言相等
+    // 数据库操作测试中的调用，mpsc 通道分批返回结果时检查收到的第一批；rx 已用于 oneshot 通道
+    fn test_db_call(&self, rust_function_name: &str, param_names: &str) -> String {
+        let call = format!(
+            "engine.{}({}){}",
+            rust_function_name,
+            param_names,
+            self.await_suffix()
+        );
+        if self.streams_db_results() {
+            format!(
+                "let mut results = {};\n        let ret = results.recv().await.unwrap();",
+                call
+            )
+        } else {
+            format!("let ret = {};", call)
+        }
+    }
+
+    fn generate_test_return_assertion(&self, indent: &str) -> String {
+        let return_type = self.resolved_return_type();
+        let return_type = return_type.trim();
+        let expected = self.expected_return.trim();
+
+        let is_collection = ["Vec<", "HashMap<", "HashSet<", "BTreeMap<", "BTreeSet<"]
+            .iter()
+            .any(|prefix| return_type.starts_with(prefix));
+        let is_simple = matches!(
+            return_type,
+            "bool"
+                | "i8"
+                | "i16"
+                | "i32"
+                | "i64"
+                | "isize"
+                | "u8"
+                | "u16"
+                | "u32"
+                | "u64"
+                | "usize"
+                | "f32"
+                | "f64"
+                | "char"
+                | "String"
+        );
+
+        if is_collection {
+            format!("{}assert!(!ret.unwrap().is_empty());\n", indent)
+        } else if is_simple && !expected.is_empty() {
+            format!("{}assert_eq!(ret.unwrap(), {});\n", indent, expected)
+        } else {
+            String::new()
+        }
+    }
+
+    // 测试中链式调用 engine 方法后的 .await，阻塞版本没有
+    fn test_call_await(&self) -> &'static str {
+        if self.async_mode {
+            "\n                .await"
+        } else {
+            ""
+        }
+    }
+
+    fn generate_test_method(&self, rust_function_name: &str) -> String {
+        let param_definitions = match self.shared_test_setup_name() {
+            Some(setup_name) => {
+                let names: Vec<String> = self
+                    .test_param_bindings()
+                    .into_iter()
+                    .map(|(name, _)| name)
+                    .collect();
+                if names.len() == 1 {
+                    format!("let {} = {}();", names[0], setup_name)
+                } else {
+                    format!("let ({}) = {}();", names.join(", "), setup_name)
+                }
+            }
+            None => self.generate_test_param_definitions(),
+        };
+        let param_names = self.extract_param_names_only();
+
+        match self.operation_type {
+            Some(OperationType::Database) => {
+                // 数据库操作测试：参考 integration_ultra_group.rs
+                let param_section = if !param_definitions.is_empty() {
+                    format!("{}\n        ", param_definitions)
+                } else {
+                    String::new()
+                };
+
+                format!(
+                    r#"#[test]
+fn {0}() {{
+    SHARED_RUNTIME.block_on(async {{
+        const ROOM_NAME: &str = "test_room";
+        let server_api = ServerApi::new();
+        if !server_api.is_chatroom_exist(ROOM_NAME).await {{
+            server_api.create_chatroom(ROOM_NAME).await;
+        }}
+{4}        let (tx, rx) = oneshot::channel();
+        {1}{2}
+
+        println!("{0}: {{:?}}", ret);
+        assert!(ret.is_ok());
+{3}        tx.send(()).unwrap();
+
+        match rx.await {{
+            Ok(_) => {{}}
+            Err(e) => {{
+                debug!("{0} err: {{:?}}", e);
+                assert!(false);
+            }}
+        }}
+    }});
+}}"#,
+                    rust_function_name,
+                    param_section,
+                    self.test_db_call(rust_function_name, &param_names),
+                    self.generate_test_return_assertion("        "),
+                    self.tester_setup(rust_function_name)
+                )
+            }
+            Some(
+                OperationType::Network
+                | OperationType::NetworkStream
+                | OperationType::NetworkThenDb,
+            ) => {
+                // 网络请求测试：参考 integration_black_list.rs
+                let param_section = if !param_definitions.is_empty() {
+                    format!("{}\n        ", param_definitions)
+                } else {
+                    String::new()
+                };
+
+                let return_assertion = self.generate_test_return_assertion("                    ");
+                let call_code = if param_names.is_empty() {
+                    format!(
+                        r#"{1}engine
+                .{0}(|ret| {{
+                    println!("{0}: {{:?}}", ret);
+                    assert!(ret.is_ok());
+{2}                    tx.send(()).unwrap();
+                }}){3};"#,
+                        rust_function_name,
+                        param_section,
+                        return_assertion,
+                        self.test_call_await()
+                    )
+                } else {
+                    format!(
+                        r#"{2}engine
+                .{0}({1}, |ret| {{
+                    println!("{0}: {{:?}}", ret);
+                    assert!(ret.is_ok());
+{3}                    tx.send(()).unwrap();
+                }}){4};"#,
+                        rust_function_name,
+                        param_names,
+                        param_section,
+                        return_assertion,
+                        self.test_call_await()
+                    )
+                };
+
+                format!(
+                    r#"#[test]
+fn {0}() {{
+    SHARED_RUNTIME.block_on(async {{
+        const ROOM_NAME: &str = "test_room";
+        let server_api = ServerApi::new();
+        if !server_api.is_chatroom_exist(ROOM_NAME).await {{
+            server_api.create_chatroom(ROOM_NAME).await;
+        }}
+{2}        let (tx, rx) = oneshot::channel();
+        {1}
+
+        match rx.await {{
+            Ok(_) => {{}}
+            Err(e) => {{
+                debug!("{0} err: {{:?}}", e);
+                assert!(false);
+            }}
+        }}
+    }});
+}}"#,
+                    rust_function_name,
+                    call_code,
+                    self.tester_setup(rust_function_name)
+                )
+            }
+            None => String::new(),
+        }
+    }
+
+    // 连接选中的测试账号；两个账号都连接时 engine 为 TESTER_A 的，engine_b 为 TESTER_B 的
+    // graceful_connect 时连接失败打印原因并跳过测试，不再 unwrap 直接 panic
+    fn tester_setup(&self, rust_function_name: &str) -> String {
+        let constants = self.tester.constants();
+        let mut setup: String = constants
+            .iter()
+            .map(|tester| {
+                if self.graceful_connect {
+                    format!(
+                        r#"        if let Err(e) = {0}.connect().await {{
+            eprintln!("跳过 {1}：{0} 无法连接服务器，请检查服务是否启动：{{:?}}", e);
+            return;
+        }}
+"#,
+                        tester, rust_function_name
+                    )
+                } else {
+                    format!("        {}.connect().await.unwrap();\n", tester)
+                }
+            })
+            .collect();
+        setup.push_str(&format!("        let engine = &{}.engine;\n", constants[0]));
+        if let Some(other) = constants.get(1) {
+            setup.push_str(&format!("        let engine_b = &{}.engine;\n", other));
+        }
+        setup
+    }
+
+    // 将测试方法包裹在带有所需 use 语句的 #[cfg(test)] mod tests 中，便于直接放入新文件
+    fn wrap_in_test_module(&self, test_code: &str) -> String {
+        if test_code.is_empty() {
+            return String::new();
+        }
+
+        format!(
+            r#"#[cfg(test)]
+mod tests {{
+    use {}::{{ServerApi, SHARED_RUNTIME, {}}};
+    use log::debug;
+    use tokio::sync::oneshot;
+
+{}
+}}"#,
+            self.resolved_test_support_path(),
+            self.tester.constants().join(", "),
+            indent_lines(test_code, 4)
+        )
+    }
+
+    fn generate_struct_fields(&self) -> String {
+        let cleaned_params = self.clean_params(&self.function_params);
+        if cleaned_params.is_empty() {
+            return String::new();
+        }
+
+        split_top_level_params(&cleaned_params)
+            .into_iter()
+            .filter_map(|param| {
+                let trimmed = param.trim();
+                if trimmed.is_empty() {
+                    return None;
+                }
+
+                let parts: Vec<&str> = trimmed.split(':').map(|s| s.trim()).collect();
+                if parts.len() != 2 {
+                    return None;
+                }
+
+                let param_name = parts[0];
+                let mut param_type = parts[1];
+
+                // 如果是 &str，转换为 String
+                if param_type == "&str" {
+                    param_type = "String";
+                }
+
+                // 规范化参数名称
+                let normalized_name = self.normalize_param_name(param_name, param_type);
+
+                let doc = if self.document_wire {
+                    format!(
+                        "    /// {}: {}\n",
+                        normalized_name,
+                        self.param_doc(param_name, &normalized_name)
+                    )
+                } else {
+                    String::new()
+                };
+                let attribute = self.field_attribute_template.trim();
+                if attribute.is_empty() {
+                    Some(format!("{}    {}: {},", doc, normalized_name, param_type))
+                } else {
+                    Some(format!(
+                        "{}    {}\n    {}: {},",
+                        doc,
+                        attribute.replace("{field}", &normalized_name),
+                        normalized_name,
+                        param_type
+                    ))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // 字段说明按参数名或字段名查找，没有填写时使用 Java 风格的参数名 (下划线命名转回驼峰)
+    fn param_doc(&self, param_name: &str, field_name: &str) -> String {
+        self.param_docs
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| {
+                let name = name.trim();
+                name == param_name || name == field_name
+            })
+            .map(|(_, description)| description.trim().to_string())
+            .filter(|description| !description.is_empty())
+            .unwrap_or_else(|| to_lower_camel_case(param_name))
+    }
+
+    fn generate_new_params(&self) -> String {
+        let cleaned_params = self.clean_params(&self.function_params);
+        if cleaned_params.is_empty() {
+            return String::new();
+        }
+
+        split_top_level_params(&cleaned_params)
+            .into_iter()
+            .filter_map(|param| {
+                let trimmed = param.trim();
+                if trimmed.is_empty() {
+                    return None;
+                }
+
+                // 分割参数为名称和类型
+                let parts: Vec<&str> = trimmed.split(':').map(|s| s.trim()).collect();
+                if parts.len() != 2 {
+                    return Some(trimmed.to_string());
+                }
+
+                let param_name = parts[0];
+                let param_type = parts[1];
+
+                // 规范化参数名称
+                let normalized_name = self.normalize_param_name(param_name, param_type);
+
+                Some(format!("{}: {}", normalized_name, param_type))
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn generate_field_inits(&self) -> String {
+        let cleaned_params = self.clean_params(&self.function_params);
+        if cleaned_params.is_empty() {
+            return String::new();
+        }
+
+        split_top_level_params(&cleaned_params)
+            .into_iter()
+            .filter_map(|param| {
+                let trimmed = param.trim();
+                if trimmed.is_empty() {
+                    return None;
+                }
+
+                let parts: Vec<&str> = trimmed.split(':').map(|s| s.trim()).collect();
+                if parts.len() != 2 {
+                    return None;
+                }
+
+                let param_name = parts[0];
+                let param_type = parts[1];
+
+                // 规范化参数名称
+                let normalized_name = self.normalize_param_name(param_name, param_type);
+
+                // 如果参数是 &str，需要转换为 String
+                if param_type == "&str" {
+                    Some(format!(
+                        "{}: {}.to_string()",
+                        normalized_name, normalized_name
+                    ))
+                } else {
+                    Some(normalized_name)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn extract_param_names(&self) -> String {
+        split_top_level_params(&self.clean_params(&self.function_params))
+            .into_iter()
+            .filter_map(|param| {
+                let trimmed = param.trim();
+                if trimmed.is_empty() {
+                    return None;
+                }
+
+                // 分割参数为名称和类型
+                let parts: Vec<&str> = trimmed.split(':').map(|s| s.trim()).collect();
+                if parts.len() != 2 {
+                    return trimmed
+                        .split(':')
+                        .next()
+                        .map(|name| name.trim().to_string());
+                }
+
+                let param_name = parts[0];
+                let param_type = parts[1].trim();
+
+                // 规范化参数名称
+                let normalized_name = self.normalize_param_name(param_name, param_type);
+
+                Some(normalized_name)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn clean_params(&self, params: &str) -> String {
+        self.clean_params_with(params, true)
+    }
+
+    // map_enums 为 false 时保留整数枚举参数的原始类型，只有 engine_sync 的签名需要
+    fn clean_params_with(&self, params: &str, map_enums: bool) -> String {
+        // 去除末尾的逗号、空格等
+        let cleaned = params.trim().trim_end_matches(',').trim().to_string();
+
+        // 去除回调参数，生成的签名会自己加上 cb: CB
+        let parts = split_top_level_params(&cleaned);
+        let filtered_parts: Vec<String> = parts
+            .into_iter()
+            .filter(|param| !is_callback_param(param))
+            .map(|param| {
+                // 使用 String 所有权参数时，&str 统一改为 String，后续不再生成 .to_string() 转换
+                match param.split_once(':') {
+                    Some((name, param_type))
+                        if self.prefer_owned_strings && param_type.trim() == "&str" =>
+                    {
+                        format!("{}: String", name)
+                    }
+                    // 整数枚举参数在 engine_sync 之后的各层都使用枚举类型
+                    Some((name, param_type)) if map_enums => {
+                        match self.enum_type_for_param(name.trim(), param_type.trim()) {
+                            Some(enum_type) => format!("{}: {}", name, enum_type),
+                            None => param.to_string(),
+                        }
+                    }
+                    _ => param.to_string(),
+                }
+            })
+            .collect();
+
+        filtered_parts.join(", ")
+    }
+
+    // 生成按引用调用时的参数列表：&str 参数在调用处已转为 String，需要传 &name，其余参数直接传
+    fn extract_param_names_for_call(&self) -> String {
+        split_top_level_params(&self.clean_params(&self.function_params))
+            .into_iter()
+            .filter_map(|param| {
+                let trimmed = param.trim();
+                if trimmed.is_empty() {
+                    return None;
+                }
+
+                let mut parts = trimmed.splitn(2, ':');
+                let name = parts.next()?.trim();
+                let param_type = parts.next().map(|t| t.trim()).unwrap_or_default();
+
+                if param_type == "&str" {
+                    Some(format!("&{}", name))
+                } else {
+                    Some(name.to_string())
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn add_ref_to_str_params(&self) -> String {
+        split_top_level_params(&self.clean_params(&self.function_params))
+            .into_iter()
+            .filter_map(|param| {
+                let trimmed = param.trim();
+                if trimmed.is_empty() {
+                    return None;
+                }
+
+                // 分割参数为名称和类型
+                let parts: Vec<&str> = trimmed.split(':').map(|s| s.trim()).collect();
+                if parts.len() != 2 {
+                    return Some(trimmed.to_string());
+                }
+
+                let param_name = parts[0];
+                let mut param_type = parts[1].trim();
+
+                // 如果类型是 String，转换为 &str（使用 String 所有权参数时保持不变）
+                if param_type == "String" && !self.prefer_owned_strings {
+                    param_type = "&str";
+                }
+
+                // 规范化参数名称
+                let normalized_name = self.normalize_param_name(param_name, param_type);
+
+                Some(format!("{}: {}", normalized_name, param_type))
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // -T 日志追加的参数，关闭记录或没有可记录的参数时为空
+    fn trace_params_args(&self) -> String {
+        if !self.log_trace_params {
+            return String::new();
+        }
+        let params = self.generate_trace_params();
+        if params.is_empty() {
+            String::new()
+        } else {
+            format!(", {}", params)
+        }
+    }
+
+    fn generate_trace_params(&self) -> String {
+        split_top_level_params(&self.clean_params(&self.function_params))
+            .into_iter()
+            .filter_map(|param| {
+                let trimmed = param.trim();
+                if trimmed.is_empty() {
+                    return None;
+                }
+                let (name, param_type) = trimmed.split_once(':').unwrap_or((trimmed, ""));
+                let name = name.trim();
+                if is_sensitive_param_name(name) {
+                    return None;
+                }
+                // 集合参数之后还要传给下一层，日志中只借用，避免被提前 move
+                if is_owned_collection_type(param_type.trim()) {
+                    Some(format!("\"{}\": &{}", name, name))
+                } else {
+                    Some(format!("\"{}\": {}", name, name))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn generate_str_to_string_conversions(&self) -> String {
+        let cleaned_params = self.clean_params(&self.function_params);
+        let conversions: Vec<String> = split_top_level_params(&cleaned_params)
+            .into_iter()
+            .filter_map(|param| {
+                let trimmed = param.trim();
+                if trimmed.is_empty() {
+                    return None;
+                }
+
+                // 检查参数类型是否为 &str
+                if trimmed.contains(": &str") {
+                    let param_name = trimmed.split(':').next()?.trim();
+                    Some(format!(
+                        "    let {} = {}.to_string();",
+                        param_name, param_name
+                    ))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        if conversions.is_empty() {
+            String::new()
+        } else {
+            conversions.join("\n") + "\n"
+        }
+    }
+
+    fn extract_param_names_only(&self) -> String {
+        split_top_level_params(&self.clean_params(&self.function_params))
+            .into_iter()
+            .filter_map(|param| {
+                let trimmed = param.trim();
+                if trimmed.is_empty() {
+                    return None;
+                }
+
+                // 分割参数为名称和类型
+                let parts: Vec<&str> = trimmed.split(':').map(|s| s.trim()).collect();
+                if parts.len() != 2 {
+                    return trimmed
+                        .split(':')
+                        .next()
+                        .map(|name| name.trim().to_string());
+                }
+
+                let param_name = parts[0];
+                let param_type = parts[1];
+
+                // 规范化参数名称
+                let normalized_name = self.normalize_param_name(param_name, param_type);
+                Some(normalized_name)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // 测试参数的名称和类型，和 generate_test_param_definitions 生成的变量一一对应
+    fn test_param_bindings(&self) -> Vec<(String, String)> {
+        split_top_level_params(&self.clean_params(&self.function_params))
+            .into_iter()
+            .filter_map(|param| {
+                let parts: Vec<&str> = param.trim().split(':').collect();
+                if parts.len() != 2 {
+                    return None;
+                }
+                Some((parts[0].trim().to_string(), parts[1].trim().to_string()))
+            })
+            .collect()
+    }
+
+    // 共用参数辅助函数按参数名命名，参数相同的函数生成同一个辅助函数
+    fn shared_test_setup_name(&self) -> Option<String> {
+        if !self.shared_test_setup || self.operation_type.is_none() {
+            return None;
+        }
+        let bindings = self.test_param_bindings();
+        if bindings.is_empty() {
+            return None;
+        }
+        // 名称中带上类型，同名不同类型的参数 (id: &str 和 id: i64) 不会生成重名的辅助函数
+        let parts: Vec<String> = bindings
+            .iter()
+            .map(|(name, param_type)| format!("{}_{}", name, type_identifier(param_type)))
+            .collect();
+        Some(format!("test_params_{}", parts.join("_")))
+    }
+
+    // 返回全部测试参数默认值的辅助函数，引用类型的默认值都是字面量，返回 'static 引用
+    fn generate_shared_test_setup(&self) -> String {
+        let Some(setup_name) = self.shared_test_setup_name() else {
+            return String::new();
+        };
+        let bindings = self.test_param_bindings();
+        let types: Vec<String> = bindings
+            .iter()
+            .map(|(_, param_type)| match param_type.strip_prefix('&') {
+                Some(rest) => format!("&'static {}", rest),
+                None => param_type.clone(),
+            })
+            .collect();
+        let names: Vec<&str> = bindings.iter().map(|(name, _)| name.as_str()).collect();
+        let (return_type, return_value) = if bindings.len() == 1 {
+            (types[0].clone(), names[0].to_string())
+        } else {
+            (
+                format!("({})", types.join(", ")),
+                format!("({})", names.join(", ")),
+            )
+        };
+
+        format!(
+            "#[cfg(test)]\nfn {}() -> {} {{\n    {}\n    {}\n}}",
+            setup_name,
+            return_type,
+            self.generate_test_param_definitions()
+                .replace("\n        ", "\n    "),
+            return_value
+        )
+    }
+
+    fn generate_test_param_definitions(&self) -> String {
+        let cleaned_params = self.clean_params(&self.function_params);
+        if cleaned_params.is_empty() {
+            return String::new();
+        }
+
+        let definitions: Vec<String> = split_top_level_params(&cleaned_params)
+            .into_iter()
+            .filter_map(|param| {
+                let trimmed = param.trim();
+                if trimmed.is_empty() {
+                    return None;
+                }
+
+                // 分割参数名和类型
+                let parts: Vec<&str> = trimmed.split(':').collect();
+                if parts.len() != 2 {
+                    return None;
+                }
+
+                let param_name = parts[0].trim();
+                let param_type = parts[1].trim();
+
+                // 根据类型生成默认值，分页等常见整数参数按名称使用更有用的取值
+                let mut default_value = self.generate_default_value_for_type(param_type);
+                if default_value == "0" {
+                    if let Some(value) = self.test_value_for_name(param_name) {
+                        default_value = value;
+                    }
+                }
+
+                Some(format!(
+                    "let {}: {} = {};",
+                    param_name, param_type, default_value
+                ))
+            })
+            .collect();
+
+        if definitions.is_empty() {
+            String::new()
+        } else {
+            definitions.join("\n        ")
+        }
+    }
+
+    // criterion 基准测试：和测试方法使用相同的默认参数，每次迭代计时一次完整调用；
+    // 需要在 Cargo.toml 中添加 criterion 开发依赖和 harness = false 的 [[bench]]
+    fn generate_bench_function(&self, rust_function_name: &str) -> String {
+        let param_definitions = self.generate_test_param_definitions();
+        let param_section = if param_definitions.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "{}\n",
+                indent_lines(&param_definitions.replace("\n        ", "\n"), 16)
+            )
+        };
+        let param_names = self.extract_param_names_only();
+
+        let call_code = match self.operation_type {
+            Some(OperationType::Database) => format!(
+                "let ret = engine.{}({}){};\n                black_box(ret);",
+                rust_function_name,
+                param_names,
+                self.await_suffix()
+            ),
+            Some(_) => format!(
+                r#"let (tx, rx) = oneshot::channel();
+                engine
+                    .{}({}|ret| {{
+                        let _ = tx.send(ret);
+                    }}){};
+                black_box(rx.await.unwrap());"#,
+                rust_function_name,
+                if param_names.is_empty() {
+                    String::new()
+                } else {
+                    format!("{}, ", param_names)
+                },
+                if self.async_mode {
+                    "\n                    .await"
+                } else {
+                    ""
+                }
+            ),
+            None => return String::new(),
+        };
+
+        format!(
+            r#"// Cargo.toml 需要添加: [[bench]] name = "{0}" harness = false
+fn bench_{0}(c: &mut Criterion) {{
+    SHARED_RUNTIME.block_on(async {{
+        TESTER_A.connect().await.unwrap();
+    }});
+    let engine = &TESTER_A.engine;
+
+    c.bench_function("{0}", |b| {{
+        b.iter(|| {{
+            SHARED_RUNTIME.block_on(async {{
+{1}                {2}
+            }})
+        }})
+    }});
+}}
+
+criterion_group!(benches, bench_{0});
+criterion_main!(benches);"#,
+            rust_function_name, param_section, call_code
+        )
+    }
+
+    // 在测试取值表中按参数名查找，表为空时使用默认表
+    fn test_value_for_name(&self, param_name: &str) -> Option<String> {
+        let table = if self.test_value_table.trim().is_empty() {
+            GenSpec::default().test_value_table
+        } else {
+            self.test_value_table.clone()
+        };
+
+        table.split(',').find_map(|entry| {
+            let (name, value) = entry.split_once('=')?;
+            let value = value.trim();
+            (name.trim() == param_name && !value.is_empty()).then(|| value.to_string())
+        })
+    }
+
+    fn generate_default_value_for_type(&self, param_type: &str) -> String {
+        match param_type {
+            "&str" => "\"test\"".to_string(),
+            "String" => "\"test\".to_string()".to_string(),
+            "i32" | "i64" | "u32" | "u64" | "i8" | "i16" | "u8" | "u16" | "usize" | "isize" => {
+                "0".to_string()
+            }
+            "f32" | "f64" => "0.0".to_string(),
+            "bool" => "false".to_string(),
+            "()" => "()".to_string(),
+            _ => {
+                // 对于复杂类型，尝试生成默认值
+                if let Some(element_types) = tuple_element_types(param_type) {
+                    // 元组逐个元素生成默认值，单元素元组需要保留末尾的逗号
+                    let values: Vec<String> = element_types
+                        .iter()
+                        .map(|element_type| self.generate_default_value_for_type(element_type))
+                        .collect();
+                    if values.len() == 1 {
+                        format!("({},)", values[0])
+                    } else {
+                        format!("({})", values.join(", "))
+                    }
+                } else if let Some(element_type) = param_type
+                    .strip_prefix("Vec<")
+                    .and_then(|rest| rest.strip_suffix('>'))
+                {
+                    // 空 vec 常常让测试直接通过，默认放一个元素
+                    if self.empty_test_vecs {
+                        "vec![]".to_string()
+                    } else {
+                        format!(
+                            "vec![{}]",
+                            self.generate_default_value_for_type(element_type)
+                        )
+                    }
+                } else if param_type.starts_with("Option<") {
+                    "None".to_string()
+                } else {
+                    // 对于其他类型，尝试使用 Default trait
+                    "Default::default()".to_string()
+                }
+            }
+        }
+    }
+
+    // 生成 ffi.rs 中的 C 接口函数：字符串参数用 *const c_char 传入，回调通过 C 函数指针通知调用方
+    fn generate_ffi_function(&self, rust_function_name: &str) -> String {
+        if self.operation_type.is_none() {
+            return String::new();
+        }
+
+        // FFI 调用 engine_sync，整数枚举参数保持原始整数类型
+        let cleaned_params = self.clean_params_with(&self.function_params, false);
+        let mut ffi_params = Vec::new();
+        let mut conversions = String::new();
+        let mut unconverted = Vec::new();
+        for param in split_top_level_params(&cleaned_params) {
+            let Some((name, param_type)) = param.split_once(':') else {
+                continue;
+            };
+            let (name, param_type) = (name.trim(), param_type.trim());
+            match param_type {
+                "&str" | "String" => {
+                    ffi_params.push(format!("{}: *const c_char", name));
+                    // 空指针不能交给 CStr::from_ptr，直接回调参数错误
+                    conversions.push_str(&format!(
+                        "    if {0}.is_null() {{\n        callback(context, {1}.code());\n        return;\n    }}\n    let {0} = unsafe {{ CStr::from_ptr({0}) }}.to_string_lossy().into_owned();\n",
+                        name,
+                        self.resolved_guard_error()
+                    ));
+                }
+                "bool" | "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64"
+                | "usize" | "f32" | "f64" => ffi_params.push(param.to_string()),
+                _ => {
+                    ffi_params.push(param.to_string());
+                    unconverted.push(name);
+                }
+            }
+        }
+
+        let todo = if unconverted.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "// TODO: 以下参数不是 C 兼容类型，需要手动转换: {}\n",
+                unconverted.join(", ")
+            )
+        };
+
+        // 裸指针不是 Send，转成 usize 后才能放进回调闭包
+        let callback = self.callback_arg(
+            r#"move |ret| {
+        let code = match ret {
+            Ok(_) => 0,
+            Err(e) => e.code(),
+        };
+        callback(context as *const c_void, code);
+    }"#,
+        );
+
+        format!(
+            r#"// 需要引入: use std::ffi::{{c_char, c_void, CStr}};
+{}{}#[no_mangle]
+pub extern "C" fn {}(
+    engine: *const EngineSync,{}
+    context: *const c_void,
+    callback: extern "C" fn(context: *const c_void, code: i32),
+) {{
+    let Some(engine) = (unsafe {{ engine.as_ref() }}) else {{
+        return;
+    }};
+{}    let context = context as usize;
+    engine.{}({});
+}}"#,
+            ERROR_CODE_NOTE,
+            todo,
+            rust_function_name,
+            params_line(&ffi_params.join(", ")),
+            conversions,
+            rust_function_name,
+            join_args(&[&self.extract_param_names_for_call(), &callback])
+        )
+    }
+
+    // 上层绑定的包装函数，调用 engine_sync 并把结果转成错误码回调给上层
+    fn generate_binding_function(&self, rust_function_name: &str) -> String {
+        if self.operation_type.is_none() {
+            return String::new();
+        }
+        match self.binding_target {
+            BindingTarget::None => String::new(),
+            BindingTarget::Jni => self.generate_jni_binding(rust_function_name),
+            BindingTarget::FlutterFfi => self.generate_flutter_binding(rust_function_name),
+        }
+    }
+
+    fn generate_jni_binding(&self, rust_function_name: &str) -> String {
+        let cleaned_params = self.clean_params_with(&self.function_params, false);
+        let mut jni_params = Vec::new();
+        let mut conversions = String::new();
+        let mut unconverted = Vec::new();
+        for param in split_top_level_params(&cleaned_params) {
+            let Some((name, param_type)) = param.split_once(':') else {
+                continue;
+            };
+            let (name, param_type) = (name.trim(), param_type.trim());
+            let jni_type = match param_type {
+                "&str" | "String" => {
+                    conversions.push_str(&format!(
+                        "    let {0}: String = env.get_string(&{0}).map(Into::into).unwrap_or_default();\n",
+                        name
+                    ));
+                    "JString"
+                }
+                "bool" => {
+                    conversions.push_str(&format!("    let {0} = {0} != 0;\n", name));
+                    "jboolean"
+                }
+                "i32" => "jint",
+                "i64" => "jlong",
+                "f32" => "jfloat",
+                "f64" => "jdouble",
+                _ => {
+                    unconverted.push(name);
+                    "JObject"
+                }
+            };
+            jni_params.push(format!("{}: {}", name, jni_type));
+        }
+
+        let todo = if unconverted.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "// TODO: 以下参数需要从 Java 对象手动转换: {}\n",
+                unconverted.join(", ")
+            )
+        };
+
+        let callback = self.callback_arg(
+            r#"move |ret| {
+        let code = match ret {
+            Ok(_) => 0,
+            Err(e) => e.code(),
+        };
+        if let Ok(mut env) = jvm.attach_current_thread() {
+            let _ = env.call_method(&callback, "onResult", "(I)V", &[JValue::Int(code)]);
+        }
+    }"#,
+        );
+
+        format!(
+            r#"// 需要引入: use jni::objects::{{JClass, JObject, JString, JValue}}; use jni::sys::*; use jni::JNIEnv;
+// TODO: {} 按实际的 Java 包名和类名修改
+{}{}#[no_mangle]
+pub extern "system" fn Java_{}_{}(
+    mut env: JNIEnv,
+    _class: JClass,
+    engine: jlong,{}
+    callback: JObject,
+) {{
+    let Some(engine) = (unsafe {{ (engine as *const EngineSync).as_ref() }}) else {{
+        return;
+    }};
+{}    let Ok(jvm) = env.get_java_vm() else {{
+        return;
+    }};
+    let Ok(callback) = env.new_global_ref(callback) else {{
+        return;
+    }};
+    engine.{}({});
+}}"#,
+            JNI_CLASS_PATH,
+            ERROR_CODE_NOTE,
+            todo,
+            JNI_CLASS_PATH,
+            self.function_name.replace('_', "_1"),
+            params_line(&jni_params.join(", ")),
+            conversions,
+            rust_function_name,
+            join_args(&[&self.extract_param_names_for_call(), &callback])
+        )
+    }
+
+    fn generate_flutter_binding(&self, rust_function_name: &str) -> String {
+        // flutter_rust_bridge 的参数需要是所有权类型，&str 改为 String
+        let params = split_top_level_params(&self.clean_params_with(&self.function_params, false))
+            .into_iter()
+            .map(|param| match param.split_once(':') {
+                Some((name, param_type)) if param_type.trim() == "&str" => {
+                    format!("{}: String", name.trim())
+                }
+                _ => param.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let callback = self.callback_arg(
+            r#"move |ret| {
+        let code = match ret {
+            Ok(_) => 0,
+            Err(e) => e.code(),
+        };
+        let _ = sink.add(code);
+    }"#,
+        );
+
+        format!(
+            r#"// 需要引入: use crate::frb_generated::StreamSink;
+// flutter_rust_bridge 会为 api 模块中的 pub fn 生成 Dart 绑定，结果通过 sink 以错误码返回
+{}pub fn {}({}) {{
+    // TODO: 获取全局的 EngineSync 实例
+    let engine = engine_sync();
+    engine.{}({});
+}}"#,
+            ERROR_CODE_NOTE,
+            rust_function_name,
+            join_args(&[&params, "sink: StreamSink<i32>"]),
+            rust_function_name,
+            join_args(&[&self.extract_param_names_for_call(), &callback])
+        )
+    }
+
+    // 生成 A 函数 - db_agent.rs 中的函数
+    fn generate_db_agent_function(&self, rust_function_name: &str) -> String {
+        let return_type = self.resolved_return_type();
+
+        let params_with_ref = self.add_ref_to_str_params();
+        let param_names_for_call = self.extract_param_names_for_db_worker_call();
+
+        // 生成 &str 参数和会话类型参数的转换代码
+        let str_conversions = self.generate_str_to_string_conversions_for_db_agent()
+            + &self.generate_conv_type_conversion_for_db_agent();
+
+        // mpsc 通道把发送端交给 db_worker，每查到一批结果发送一次；任务放到运行时中执行后直接返回接收端
+        let (import, return_type, channel, call, execute) = match self.channel_kind {
+            ChannelKind::Oneshot => (
+                "",
+                self.result_type(&return_type),
+                "let (resp_tx, resp_rx) = oneshot::channel();".to_string(),
+                format!(
+                    "let result = db_worker.{}({})\n            .await;\n        let _ = resp_tx.send(result);",
+                    rust_function_name, param_names_for_call
+                ),
+                format!(
+                    "// 4. 发任务给 db 模块执行\n    {}",
+                    self.blocking_call("self.runtime", "self.execute(task, resp_rx)")
+                ),
+            ),
+            ChannelKind::Mpsc => (
+                MPSC_IMPORT_NOTE,
+                format!("mpsc::Receiver<{}>", self.result_type(&return_type)),
+                format!(
+                    "// 通道满时 db_worker 等待调用方取走结果\n    {}",
+                    db_stream_channel("resp_tx", "resp_rx")
+                ),
+                format!(
+                    "db_worker.{}({})\n            .await;",
+                    rust_function_name,
+                    join_args(&[&param_names_for_call, "resp_tx"])
+                ),
+                "// 4. 发任务给 db 模块执行，调用方从 resp_rx 中逐条接收结果，db_worker 发送完毕后通道关闭\n    self.runtime.spawn(task);\n    resp_rx".to_string(),
+            ),
+        };
+
+        format!(
+            r#"{}{}{}fn {}(
+    &self,{}
+) -> {} {{
+    // 1. 基础参数转化（需要将数据转为 db 模块的类型）
+{}
+    // 2. 创建通道和 db_worker
+    {}
+    let db_worker_clone = self.db_worker.clone();
+
+    // 3. 创建 task，调用 db_worker 对应方法。
+    // task 只负责调用简单的方法，复杂逻辑挪到 db 模块内
+    let task = Box::pin(async move {{
+        let db_worker = db_worker_clone.read().await;
+        {}
+    }});
+
+    {}
+}}"#,
+            import,
+            self.visibility_keyword(Visibility::Pub),
+            self.async_keyword(),
+            rust_function_name,
+            params_line(&params_with_ref),
+            return_type,
+            str_conversions,
+            channel,
+            call,
+            execute
+        )
+    }
+
+    // 生成 B 函数 - db_worker.rs 中的函数
+    fn generate_db_worker_function(&self, rust_function_name: &str) -> String {
+        let return_type = self.resolved_return_type();
+
+        let params_with_ref = self.add_ref_to_str_params();
+        let param_names = self.extract_param_names();
+        let db_log_macro = self.resolved_db_log_macro();
+        // 日志标签和 engine_async 使用相同的前缀：<前缀>-<函数名>-T / -R
+        let trace_tag = format!(
+            "{}-{}",
+            self.resolved_trace_tag_prefix(),
+            rust_function_name
+        );
+
+        if self.channel_kind == ChannelKind::Mpsc {
+            return format!(
+                r#"// 需要引入: use tokio::sync::mpsc;
+// DbError 需要能转换为 EngineError (impl From<DbError> for EngineError)
+{}async fn {}(
+    &self,{}
+    resp_tx: mpsc::Sender<{}>,
+) {{
+    {}!("{}-T");
+    let method_name = "{}";
+    let db_lock = self.db_sqlite_lock.read().await;
+    let Some(db) = db_lock.as_ref() else {{
+        let error = self.callback_error(method_name, DbError::NotOpen);
+        let _ = resp_tx.send(Err(error.into())).await;
+        return;
+    }};
+    // 每收到一批结果转发一次，发送失败说明调用方已释放接收端，停止转发
+    let mut rows = db.{}({});
+    let mut count = 0;
+    while let Some(ret) = rows.recv().await {{
+        count += 1;
+        let ret = self.callback(method_name, ret).map_err(Into::into);
+        if resp_tx.send(ret).await.is_err() {{
+            break;
+        }}
+    }}
+    {}!("{}-R", "count", count);
+}}"#,
+                self.visibility_keyword(Visibility::Pub),
+                rust_function_name,
+                params_line(&params_with_ref),
+                self.result_type(&return_type),
+                db_log_macro,
+                trace_tag,
+                rust_function_name,
+                rust_function_name,
+                param_names,
+                db_log_macro,
+                trace_tag
+            );
+        }
+
+        format!(
+            r#"{}async fn {}(
+    &self,{}
+) -> Result<{}, DbError> {{
+    {}!("{}-T");
+    let method_name = "{}";
+    let db_lock = self.db_sqlite_lock.read().await;
+    let db = db_lock
+        .as_ref()
+        .ok_or_else(|| self.callback_error(method_name, DbError::NotOpen))?;
+    let ret = db.{}({})
+        .await
+        .unwrap_or_else(|join_error| Err(DbErrorInfo::from_join_error(join_error)));
+    let str = match &ret {{
+        Ok(_) => "".to_string(),
+        Err(e) => e.to_string(),
+    }};
+    {}!("{}-R", "result", str);
+    self.callback(method_name, ret)
+}}"#,
+            self.visibility_keyword(Visibility::Pub),
+            rust_function_name,
+            params_line(&params_with_ref),
+            return_type,
+            db_log_macro,
+            trace_tag,
+            rust_function_name,
+            rust_function_name,
+            param_names,
+            db_log_macro,
+            trace_tag
+        )
+    }
+
+    // 生成 C 函数 - db_sqlite.rs 中的函数
+    fn generate_db_sqlite_function(&self, rust_function_name: &str) -> String {
+        let return_type = self.resolved_return_type();
+
+        let params_with_ref = self.add_ref_to_str_params();
+
+        // 生成 &str 参数的转换代码（在函数体内）
+        let str_conversions = self.generate_str_conversions_in_function_body();
+
+        if self.channel_kind == ChannelKind::Mpsc {
+            return format!(
+                r#"// 需要引入: use tokio::sync::mpsc;
+{}fn {}(
+    &self,{}
+) -> mpsc::Receiver<Result<{}, DbErrorInfo>> {{
+    let db_lock_clone = self.db_lock.clone();
+{}
+    {}
+    spawn_blocking(move || {{
+        let db = match db_lock_clone.read() {{
+            Ok(db) => db,
+            Err(error) => {{
+                let _ = row_tx.blocking_send(Err(DbErrorInfo::from_lock(error)));
+                return;
+            }}
+        }};
+        // TODO: 分批查询，每批结果用 row_tx.blocking_send(Ok(rows)) 发送，返回 Err 说明接收端已释放，停止查询
+    }});
+    row_rx
+}}"#,
+                self.visibility_keyword(Visibility::Pub),
+                rust_function_name,
+                params_line(&params_with_ref),
+                return_type,
+                str_conversions,
+                db_stream_channel("row_tx", "row_rx")
+            );
+        }
+
+        format!(
+            r#"{}fn {}(
+    &self,{}
+) -> JoinHandle<Result<{}, DbErrorInfo>> {{
+    let db_lock_clone = self.db_lock.clone();
+{}
+    spawn_blocking(move || {{
+        let db = db_lock_clone
+                .read()
+                .map_err(|error| DbErrorInfo::from_lock(error))?;
+            let mut transaction_err_opt = None;
+            let transaction_ret = db.run_transaction(|_| {{
+
+                if let Err(exp) = ret {{
+                    transaction_err_opt = Some(DbErrorInfo::from(exp));
+                    return false;
+                }}
+
+                return true; //返回 false 回滚整个事务
+            }});
+            if let Some(error) = transaction_err_opt {{
+                return Err(error);
+            }}
+            if let Err(exp) = transaction_ret {{
+                return Err(DbErrorInfo::from(exp));
+            }}
+            Ok(())
+    }})
+}}"#,
+            self.visibility_keyword(Visibility::Pub),
+            rust_function_name,
+            params_line(&params_with_ref),
+            return_type,
+            str_conversions
+        )
+    }
+
+    // 辅助函数：生成 db_agent 中 &str 参数的转换代码
+    fn generate_str_to_string_conversions_for_db_agent(&self) -> String {
+        let cleaned_params = self.clean_params(&self.function_params);
+        let conversions: Vec<String> = split_top_level_params(&cleaned_params)
+            .into_iter()
+            .filter_map(|param| {
+                let trimmed = param.trim();
+                if trimmed.is_empty() {
+                    return None;
+                }
+
+                if trimmed.contains(": &str") {
+                    let param_name = trimmed.split(':').next()?.trim();
+                    Some(format!(
+                        "    let {} = {}.to_string();",
+                        param_name, param_name
+                    ))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        if conversions.is_empty() {
+            String::new()
+        } else {
+            conversions.join("\n") + "\n"
+        }
+    }
+
+    // 辅助函数：生成 db_agent 中会话类型的转换代码，将网络层类型转为 db 模块的类型
+    fn generate_conv_type_conversion_for_db_agent(&self) -> String {
+        let (conv_type_name, db_conv_type_name) = self.resolved_conv_type_names();
+        let has_conv_type = split_top_level_params(&self.clean_params(&self.function_params))
+            .into_iter()
+            .filter_map(|param| param.split_once(':'))
+            .any(|(_, param_type)| param_type.trim() == conv_type_name);
+
+        if has_conv_type {
+            format!(
+                "    let conv_type = {}::from(conv_type);\n",
+                db_conv_type_name
+            )
+        } else {
+            String::new()
+        }
+    }
+
+    // 辅助函数：生成调用 db_worker 时的参数列表
+    fn extract_param_names_for_db_worker_call(&self) -> String {
+        split_top_level_params(&self.clean_params(&self.function_params))
+            .into_iter()
+            .filter_map(|param| {
+                let trimmed = param.trim();
+                if trimmed.is_empty() {
+                    return None;
+                }
+
+                let (param_name, param_type) = trimmed.split_once(':').unwrap_or((trimmed, ""));
+                let param_name = self.normalize_param_name(param_name.trim(), param_type.trim());
+
+                // 如果参数类型是 &str，在调用时需要使用 .as_str()
+                if trimmed.contains(": &str") {
+                    Some(format!("{}.as_str()", param_name))
+                } else {
+                    Some(param_name.to_string())
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // 辅助函数：生成 db_sqlite 中 &str 参数的转换代码（在 spawn_blocking 外部）
+    fn generate_str_conversions_in_function_body(&self) -> String {
+        let cleaned_params = self.clean_params(&self.function_params);
+        let conversions: Vec<String> = split_top_level_params(&cleaned_params)
+            .into_iter()
+            .filter_map(|param| {
+                let trimmed = param.trim();
+                if trimmed.is_empty() {
+                    return None;
+                }
+
+                if trimmed.contains(": &str") {
+                    let param_name = trimmed.split(':').next()?.trim();
+                    Some(format!(
+                        "    let {} = {}.to_string();",
+                        param_name, param_name
+                    ))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        if conversions.is_empty() {
+            String::new()
+        } else {
+            conversions.join("\n") + "\n"
+        }
+    }
+}
+
+// 下划线命名转回 Java 风格的驼峰命名：group_id -> groupId
+fn to_lower_camel_case(name: &str) -> String {
+    let mut result = String::new();
+    for (index, word) in name.split('_').filter(|word| !word.is_empty()).enumerate() {
+        let mut chars = word.chars();
+        if let (true, Some(first)) = (index > 0, chars.next()) {
+            result.extend(first.to_uppercase());
+            result.push_str(chars.as_str());
+        } else {
+            result.push_str(word);
+        }
+    }
+    result
+}
+
+// Java 驼峰命名 (getGroupInfo) 和类型名 (GetGroupReq) 都用这一个函数转换为下划线命名
+// 连续的大写字母视为一个缩写词：SetHTTPConfig -> set_http_config
+pub fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut result = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let prev = i.checked_sub(1).map(|p| chars[p]);
+            let next = chars.get(i + 1);
+            // 小写/数字后的大写开始新单词；缩写词中只有后面跟小写的最后一个大写字母开始新单词
+            let starts_word = match prev {
+                Some(p) if p.is_lowercase() || p.is_ascii_digit() => true,
+                Some(p) if p.is_uppercase() => next.is_some_and(|n| n.is_lowercase()),
+                _ => false,
+            };
+            if starts_word && !result.ends_with('_') {
+                result.push('_');
+            }
+            result.extend(c.to_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+// 从 CSV/TSV 导入函数：每行 functionName, params, returnType, operationType，后两列可以省略，
+// 省略或为空时沿用 base 的设置。含逗号的参数列表用双引号括起来，第一行是表头时跳过，# 开头的行是注释。
+// 返回解析成功的函数和每个出错行的说明 (行号从 1 开始)
+pub fn parse_spec_table(source: &str, base: &GenSpec) -> (Vec<GenSpec>, Vec<String>) {
+    let delimiter = match source.lines().find(|line| !line.trim().is_empty()) {
+        Some(line) if line.contains('\t') => '\t',
+        _ => ',',
+    };
+    let mut specs = Vec::new();
+    let mut errors = Vec::new();
+    let mut seen_row = false;
+    for (index, line) in source.lines().enumerate() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let cells = match split_delimited_row(line, delimiter) {
+            Ok(cells) => cells,
+            Err(reason) => {
+                errors.push(format!("第 {} 行：{}", index + 1, reason));
+                continue;
+            }
+        };
+        let is_header = !seen_row
+            && matches!(
+                cells[0].to_lowercase().as_str(),
+                "functionname" | "function_name"
+            );
+        seen_row = true;
+        if is_header {
+            continue;
+        }
+        match spec_from_cells(&cells, base) {
+            Ok(spec) => specs.push(spec),
+            Err(reason) => errors.push(format!("第 {} 行：{}", index + 1, reason)),
+        }
+    }
+    (specs, errors)
+}
+
+fn spec_from_cells(cells: &[String], base: &GenSpec) -> Result<GenSpec, String> {
+    if cells.len() > 4 {
+        return Err(format!(
+            "最多 4 列 (functionName, params, returnType, operationType)，实际 {} 列",
+            cells.len()
+        ));
+    }
+    let cell = |index: usize| cells.get(index).map(String::as_str).unwrap_or_default();
+    if cell(0).is_empty() {
+        return Err("函数名不能为空".to_string());
+    }
+    let operation_type = match cell(3) {
+        "" => base.operation_type.clone(),
+        name => Some(
+            OperationType::from_name(name).ok_or_else(|| format!("未知的操作类型：{}", name))?,
+        ),
+    };
+    Ok(GenSpec {
+        function_name: cell(0).to_string(),
+        function_params: cell(1).to_string(),
+        callback_return_type: match cell(2) {
+            "" => base.callback_return_type.clone(),
+            return_type => return_type.to_string(),
+        },
+        operation_type,
+        ..base.clone()
+    })
+}
+
+// 按分隔符拆分一行，双引号中的分隔符不拆分，"" 表示一个双引号；每个单元格去掉首尾空白
+fn split_delimited_row(line: &str, delimiter: char) -> Result<Vec<String>, String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c == delimiter && !in_quotes => {
+                cells.push(cell.trim().to_string());
+                cell.clear();
+            }
+            c => cell.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("双引号没有闭合".to_string());
+    }
+    cells.push(cell.trim().to_string());
+    Ok(cells)
+}
+
+// 生成代码的后处理：去掉每行末尾的空白，连续多个空行合并为一个，保留末尾的换行
+pub fn normalize_whitespace(code: &str) -> String {
+    let mut result = String::with_capacity(code.len());
+    let mut previous_blank = false;
+    for line in code.lines() {
+        let line = line.trim_end();
+        if line.is_empty() && previous_blank {
+            continue;
+        }
+        previous_blank = line.is_empty();
+        result.push_str(line);
+        result.push('\n');
+    }
+    if !code.ends_with('\n') {
+        result.pop();
+    }
+    result
+}
+
+// 用 vars 替换模板中的 {key} 占位符；不是已知变量的花括号 (例如代码块) 原样保留
+pub fn render_template(tmpl: &str, vars: &HashMap<&str, String>) -> String {
+    let mut result = String::new();
+    let mut rest = tmpl;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let placeholder = after
+            .find('}')
+            .and_then(|end| vars.get(&after[..end]).map(|value| (end, value)));
+        match placeholder {
+            Some((end, value)) => {
+                result.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                result.push('{');
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+// .proto 消息中的一个字段
+pub struct ProtoField {
+    pub name: String,
+    pub proto_type: String,
+    pub repeated: bool,
+}
+
+impl ProtoField {
+    pub fn rust_type(&self) -> String {
+        let element = if let Some(inner) = self
+            .proto_type
+            .strip_prefix("map<")
+            .and_then(|rest| rest.strip_suffix('>'))
+        {
+            let (key, value) = inner.split_once(',').unwrap_or((inner, ""));
+            format!(
+                "HashMap<{}, {}>",
+                proto_type_to_rust(key.trim()),
+                proto_type_to_rust(value.trim())
+            )
+        } else {
+            proto_type_to_rust(&self.proto_type)
+        };
+
+        if self.repeated {
+            format!("Vec<{}>", element)
+        } else {
+            element
+        }
+    }
+}
+
+// 解析请求体字段，每行 field_name: rust_type，空行和缺少类型的行忽略
+fn parse_request_fields(source: &str) -> Vec<(String, String)> {
+    source
+        .lines()
+        .filter_map(|line| {
+            let (name, field_type) = line.split_once(':')?;
+            let (name, field_type) = (name.trim(), field_type.trim().trim_end_matches(','));
+            if name.is_empty() || field_type.is_empty() {
+                return None;
+            }
+            Some((name.to_string(), field_type.to_string()))
+        })
+        .collect()
+}
+
+// 标量类型按 protobuf 规则映射，消息和枚举类型取最后一段名称
+fn proto_type_to_rust(proto_type: &str) -> String {
+    let rust_type = match proto_type {
+        "string" => "String",
+        "bytes" => "Vec<u8>",
+        "bool" => "bool",
+        "int32" | "sint32" | "sfixed32" => "i32",
+        "int64" | "sint64" | "sfixed64" => "i64",
+        "uint32" | "fixed32" => "u32",
+        "uint64" | "fixed64" => "u64",
+        "float" => "f32",
+        "double" => "f64",
+        _ => proto_type.rsplit('.').next().unwrap_or(proto_type),
+    };
+    rust_type.to_string()
+}
+
+// 简单的 .proto 解析：找到指定 message，读取其直接字段（包括 oneof 中的字段），
+// 忽略嵌套的 message/enum 定义以及 option、reserved 等语句
+fn parse_proto_message(src: &str, msg: &str) -> Vec<ProtoField> {
+    let src = src
+        .lines()
+        .map(|line| line.split("//").next().unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut fields = Vec::new();
+    // 当前所在的块，保存块头，例如 "message Foo"、"oneof bar"
+    let mut blocks: Vec<String> = Vec::new();
+    let mut target_depth = None;
+    let mut statement = String::new();
+
+    for c in src.chars() {
+        match c {
+            '{' => {
+                let header = std::mem::take(&mut statement).trim().to_string();
+                let words: Vec<&str> = header.split_whitespace().collect();
+                if target_depth.is_none() && words == ["message", msg] {
+                    target_depth = Some(blocks.len() + 1);
+                }
+                blocks.push(header);
+            }
+            '}' => {
+                statement.clear();
+                if target_depth == Some(blocks.len()) {
+                    break;
+                }
+                blocks.pop();
+            }
+            ';' => {
+                let statement = std::mem::take(&mut statement);
+                let Some(depth) = target_depth else {
+                    continue;
+                };
+                let in_target = blocks.len() == depth
+                    || (blocks.len() == depth + 1
+                        && blocks.last().is_some_and(|b| b.starts_with("oneof ")));
+                if in_target {
+                    fields.extend(parse_proto_field(&statement));
+                }
+            }
+            _ => statement.push(c),
+        }
+    }
+
+    fields
+}
+
+// 解析单个字段语句，例如 "repeated string user_ids = 2"
+fn parse_proto_field(statement: &str) -> Option<ProtoField> {
+    let (declaration, _) = statement.split_once('=')?;
+    let mut tokens: Vec<&str> = declaration.split_whitespace().collect();
+    let name = tokens.pop()?;
+
+    let repeated = tokens.first() == Some(&"repeated");
+    match tokens.first() {
+        Some(&"option") | None => return None,
+        Some(&"repeated") | Some(&"optional") | Some(&"required") => {
+            tokens.remove(0);
+        }
+        _ => {}
+    }
+
+    // map<string, int32> 会被空白拆开，这里重新拼接
+    let proto_type = tokens.join(" ").replace(", ", ",").replace(',', ", ");
+    if proto_type.is_empty() {
+        return None;
+    }
+
+    Some(ProtoField {
+        name: name.to_string(),
+        proto_type,
+        repeated,
+    })
+}
+
+// 给每个非空行增加缩进
+fn indent_lines(code: &str, spaces: usize) -> String {
+    let indent = " ".repeat(spaces);
+    code.lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", indent, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// 用逗号拼接参数列表，跳过空的部分，避免没有参数时生成悬空的逗号
+fn join_args(args: &[&str]) -> String {
+    args.iter()
+        .map(|arg| arg.trim())
+        .filter(|arg| !arg.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// 多行签名中的参数行，没有参数时整行省略
+fn params_line(params: &str) -> String {
+    if params.trim().is_empty() {
+        String::new()
+    } else {
+        format!("\n    {},", params)
+    }
+}
+
+// 只通过方法调用使用的 trait，导入名不会出现在代码中，按方法名判断是否需要导入
+const TRAIT_IMPORT_METHODS: &[(&str, &[&str])] = &[(
+    "Message",
+    &["write_to_bytes", "parse_from_bytes", "merge_from_bytes"],
+)];
+
+// 整理开头的 use 语句：去掉代码中没有用到的导入，合并重复项并排序，避免 unused_imports 警告
+fn prune_unused_imports(code: &str) -> String {
+    let use_count = code
+        .lines()
+        .take_while(|line| line.starts_with("use ") || line.trim().is_empty())
+        .count();
+    let body = code.lines().skip(use_count).collect::<Vec<_>>().join("\n");
+
+    let mut uses: Vec<String> = code
+        .lines()
+        .take(use_count)
+        .filter_map(|line| {
+            let path = line
+                .trim()
+                .strip_prefix("use ")?
+                .trim_end_matches(';')
+                .trim();
+            let Some((prefix, group)) = path.split_once("::{") else {
+                return is_import_used(&body, path).then(|| format!("use {};", path));
+            };
+            let items: Vec<&str> = group
+                .trim_end_matches('}')
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty() && is_import_used(&body, item))
+                .collect();
+            match items.as_slice() {
+                [] => None,
+                [item] => Some(format!("use {}::{};", prefix, item)),
+                _ => Some(format!("use {}::{{{}}};", prefix, items.join(", "))),
+            }
+        })
+        .collect();
+    uses.sort();
+    uses.dedup();
+
+    if uses.is_empty() {
+        body
+    } else {
+        format!("{}\n\n{}", uses.join("\n"), body)
+    }
+}
+
+// 导入路径的最后一段（或 as 之后的别名）是否在代码中作为标识符出现
+fn is_import_used(body: &str, path: &str) -> bool {
+    let name = match path.split_once(" as ") {
+        Some((_, alias)) => alias.trim(),
+        None => path.rsplit("::").next().unwrap_or(path).trim(),
+    };
+    if name == "*" || name == "self" {
+        return true;
+    }
+    let used_by_method = TRAIT_IMPORT_METHODS.iter().any(|(trait_name, methods)| {
+        *trait_name == name && methods.iter().any(|m| body.contains(m))
+    });
+    used_by_method || contains_identifier(body, name)
+}
+
+fn contains_identifier(body: &str, name: &str) -> bool {
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+    body.match_indices(name).any(|(index, _)| {
+        let before = body[..index].chars().next_back();
+        let after = body[index + name.len()..].chars().next();
+        !before.is_some_and(is_ident_char) && !after.is_some_and(is_ident_char)
+    })
+}
+
+// 把类型写成标识符的一部分：&str -> ref_str，Vec<&[u8]> -> vec_ref_slice_u8
+fn type_identifier(type_name: &str) -> String {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for ch in type_name.chars() {
+        if ch.is_alphanumeric() || ch == '_' {
+            word.extend(ch.to_lowercase());
+            continue;
+        }
+        if !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        match ch {
+            '&' => words.push("ref".to_string()),
+            '[' => words.push("slice".to_string()),
+            '(' => words.push("tuple".to_string()),
+            _ => {}
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words.join("_")
+}
+
+// Option<T> 类型的参数可以不传，其余参数都是必填的
+fn is_option_type(type_name: &str) -> bool {
+    type_name.trim().starts_with("Option<")
+}
+
+// 结构体成员中没有用到类型参数时，返回 PhantomData 占位成员和它的初始化
+fn phantom_marker(struct_fields: &str, type_param: &str) -> (String, String) {
+    if contains_identifier(struct_fields, type_param) {
+        (String::new(), String::new())
+    } else {
+        (
+            format!("\n    _marker: std::marker::PhantomData<{}>,", type_param),
+            "_marker: std::marker::PhantomData".to_string(),
+        )
+    }
+}
+
+// mpsc 通道的默认容量，db_agent 和 db_sqlite 中的通道满时发送方等待
+const DB_STREAM_CAPACITY: usize = 32;
+
+const MPSC_IMPORT_NOTE: &str = "// 需要引入: use tokio::sync::mpsc;\n";
+
+// db_agent 和 db_sqlite 中创建 mpsc 通道的语句，容量都来自 DB_STREAM_CAPACITY
+fn db_stream_channel(tx: &str, rx: &str) -> String {
+    format!(
+        "const DB_STREAM_CAPACITY: usize = {};\n    let ({}, {}) = mpsc::channel(DB_STREAM_CAPACITY);",
+        DB_STREAM_CAPACITY, tx, rx
+    )
+}
+
+// FFI 和上层绑定把错误转成整数错误码，EngineError 是带数据的枚举时不能直接 as i32
+const ERROR_CODE_NOTE: &str =
+    "// EngineError 需要提供 fn code(&self) -> i32，把错误转换为上层使用的错误码\n";
+
+// 元组类型 (A, B) 的元素类型，单元类型 () 和非元组类型返回 None
+fn tuple_element_types(type_name: &str) -> Option<Vec<&str>> {
+    let inner = type_name.trim().strip_prefix('(')?.strip_suffix(')')?;
+    let element_types = split_top_level_params(inner);
+    if element_types.is_empty() {
+        None
+    } else {
+        Some(element_types)
+    }
+}
+
+// 按顶层逗号分割参数，忽略泛型尖括号、元组括号和数组方括号内的逗号
+pub fn split_top_level_params(params: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    let mut prev = ' ';
+    for (i, c) in params.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            // 闭包返回值的 -> 不是泛型的结束
+            '>' if prev == '-' => {}
+            '>' | ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                result.push(params[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+        prev = c;
+    }
+    result.push(params[start..].trim());
+
+    result.into_iter().filter(|p| !p.is_empty()).collect()
+}
+
+// 回调参数：名称为 cb/callback，或类型中出现 Fn/FnOnce/FnMut/CB/xxxCallback，不论在参数列表的哪个位置
+fn is_callback_param(param: &str) -> bool {
+    let (name, param_type) = param.split_once(':').unwrap_or((param, ""));
+    if matches!(name.trim(), "cb" | "callback") {
+        return true;
+    }
+    param_type
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .any(|token| {
+            matches!(token, "Fn" | "FnOnce" | "FnMut" | "CB") || token.ends_with("Callback")
+        })
+}
+
+// 拥有所有权的集合类型，按值使用一次后就会被 move
+fn is_owned_collection_type(param_type: &str) -> bool {
+    [
+        "Vec<",
+        "VecDeque<",
+        "HashMap<",
+        "HashSet<",
+        "BTreeMap<",
+        "BTreeSet<",
+    ]
+    .iter()
+    .any(|prefix| param_type.starts_with(prefix))
+}
+
+// 名称像凭据的参数不写进 trace 日志
+fn is_sensitive_param_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    [
+        "password",
+        "passwd",
+        "token",
+        "secret",
+        "credential",
+        "private_key",
+    ]
+    .iter()
+    .any(|hint| name.contains(hint))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generator_with_params(params: &str) -> GenSpec {
+        GenSpec {
+            function_params: params.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn prefer_owned_strings_keeps_string_params() {
+        let mut generator = generator_with_params("target_id: &str, user_id: String");
+        generator.prefer_owned_strings = true;
+        assert_eq!(
+            generator.add_ref_to_str_params(),
+            "target_id: String, user_id: String"
+        );
+        assert_eq!(generator.generate_str_to_string_conversions(), "");
+        assert_eq!(
+            generator.extract_param_names_for_call(),
+            "target_id, user_id"
+        );
+    }
+
+    #[test]
+    fn db_agent_converts_conversation_type_for_db_layer() {
+        let generator = generator_with_params("type: ConversationType, target_id: &str");
+        let code = generator.generate_db_agent_function("get_conversation");
+        assert!(code.contains("    let conv_type = DbConversationType::from(conv_type);\n"));
+        assert!(code.contains("conv_type: ConversationType"));
+        assert!(code.contains("db_worker.get_conversation(conv_type, target_id.as_str())"));
+    }
+
+    #[test]
+    fn boxed_dyn_callback_style_drops_generic_and_where_clause() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.callback_style = CallbackStyle::BoxedDyn;
+        generator.callback_return_type = "bool".to_string();
+
+        let code = generator.generate_module_function("get_info");
+        assert!(code.starts_with("pub(crate) async fn get_info(\n"));
+        assert!(code.contains("cb: Box<dyn FnOnce(Result<bool, EngineError>) + Send + 'static>,"));
+        assert!(!code.contains("where"));
+    }
+
+    #[test]
+    fn builder_pattern_replaces_long_request_new() {
+        let mut generator = generator_with_params("target_id: &str, limit: i32");
+        generator.request_body_name = "GetGroupReq".to_string();
+        generator.pass_params_to_request = true;
+        generator.use_builder_pattern = true;
+
+        let request_struct = generator.generate_request_struct();
+        assert!(request_struct.contains("pub(crate) struct GetGroupReqBuilder {"));
+        // 必填参数在 Builder::new 中传入，build 时不会用默认值补上缺少的字段
+        assert!(request_struct.contains(
+            "pub(crate) fn new(pb_req: PbGetGroupReq, target_id: String, limit: i32) -> Self {"
+        ));
+        assert!(!request_struct.contains("fn with_"));
+        assert!(!request_struct.contains(".limit.unwrap_or_default()"));
+        assert!(request_struct.contains("            limit: self.limit,\n"));
+        assert!(!request_struct.contains("pub(crate) fn new(pb_req: PbGetGroupReq, cb: CB"));
+        syn::parse_file(&request_struct).unwrap();
+
+        let request_builder = generator.generate_request_builder_function("get_group");
+        assert!(request_builder.contains(
+            "GetGroupReqBuilder::new(pb_req, target_id.to_string(), limit)\n        .build(cb);"
+        ));
+
+        // Option 参数可以不传，通过 with_ 方法设置
+        generator.function_params = "target_id: &str, limit: Option<i32>".to_string();
+        let request_struct = generator.generate_request_struct();
+        assert!(request_struct.contains(
+            "        Self { pb_req, target_id, limit: None }\n    }\n\n    pub(crate) fn with_limit(mut self, limit: Option<i32>) -> Self {\n        self.limit = limit;"
+        ));
+        syn::parse_file(&request_struct).unwrap();
+        let request_builder = generator.generate_request_builder_function("get_group");
+        assert!(request_builder.contains(
+            "GetGroupReqBuilder::new(pb_req, target_id.to_string())\n        .with_limit(limit)\n        .build(cb);"
+        ));
+    }
+
+    #[test]
+    fn paging_params_get_useful_test_values() {
+        let mut generator = generator_with_params("limit: i32, offset: i32, flag: i32, name: &str");
+        assert_eq!(
+            generator.generate_test_param_definitions(),
+            "let limit: i32 = 20;\n        let offset: i32 = 0;\n        let flag: i32 = 0;\n        let name: &str = \"test\";"
+        );
+
+        generator.test_value_table = "limit=50, flag=1".to_string();
+        assert!(generator.generate_test_param_definitions().starts_with(
+            "let limit: i32 = 50;\n        let offset: i32 = 0;\n        let flag: i32 = 1;"
+        ));
+    }
+
+    #[test]
+    fn request_builder_forwards_params_to_request_new() {
+        let mut generator =
+            generator_with_params("target_id: &str, type: ConversationType, name: String");
+        generator.request_body_name = "GetGroupReq".to_string();
+
+        let code = generator.generate_request_builder_function("get_group");
+        assert!(code.contains("let req = GetGroupReq::new(pb_req, cb);"));
+
+        generator.pass_params_to_request = true;
+        let code = generator.generate_request_builder_function("get_group");
+        assert!(code.contains(
+            "let req = GetGroupReq::new(pb_req, cb, target_id, conv_type, name.to_string());"
+        ));
+        assert!(generator
+            .generate_request_struct()
+            .contains("fn new(pb_req: PbGetGroupReq, cb: CB, target_id: &str, conv_type: ConversationType, name: String)"));
+    }
+
+    #[test]
+    fn cancellable_async_passes_token_and_completes_with_cancelled() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.supports_cancel = true;
+
+        let code = generator.generate_engine_async_function("get_info");
+        assert!(code.starts_with("// 需要引入: use tokio_util::sync::CancellationToken;\n"));
+        assert!(code.contains("target_id: &str, cancel_token: CancellationToken, cb: CB)"));
+        assert!(code
+            .contains("bugtags::get_info(&self.ctx, target_id, cancel_token.clone(), request_cb)"));
+        assert!(code.contains("cb(Err(EngineError::Cancelled));"));
+
+        // 模块收到 token 后和发送请求一起 select!，取消后不再等待请求
+        let module = generator.generate_module_function("get_info");
+        assert!(module.contains("    target_id: &str,\n    cancel_token: CancellationToken,\n"));
+        assert!(module.contains(
+            "    tokio::select! {\n        _ = ctx.send_query(query) => {}\n        // engine_async 已经在取消时回调了错误，这里只停止发送\n        _ = cancel_token.cancelled() => {}\n    }\n}"
+        ));
+        syn::parse_file(&module).unwrap();
+
+        // 取消时回调的错误可以配置，为空时使用默认值
+        generator.cancel_error = "EngineError::UserCancelled".to_string();
+        let code = generator.generate_engine_async_function("get_info");
+        assert!(code.contains("cb(Err(EngineError::UserCancelled));"));
+        generator.cancel_error = " ".to_string();
+        let code = generator.generate_engine_async_function("get_info");
+        assert!(code.contains("cb(Err(EngineError::Cancelled));"));
+    }
+
+    #[test]
+    fn field_attribute_template_is_applied_above_each_field() {
+        let mut generator = generator_with_params("target_id: &str, limit: i32");
+        assert_eq!(
+            generator.generate_struct_fields(),
+            "    target_id: String,\n    limit: i32,"
+        );
+
+        generator.field_attribute_template = "#[serde(rename = \"{field}\")]".to_string();
+        assert_eq!(
+            generator.generate_struct_fields(),
+            "    #[serde(rename = \"target_id\")]\n    target_id: String,\n    #[serde(rename = \"limit\")]\n    limit: i32,"
+        );
+    }
+
+    #[test]
+    fn vec_test_params_get_one_element() {
+        let mut generator = generator_with_params("channel_ids: Vec<String>, flags: Vec<bool>");
+        assert_eq!(
+            generator.generate_test_param_definitions(),
+            "let channel_ids: Vec<String> = vec![\"test\".to_string()];\n        let flags: Vec<bool> = vec![false];"
+        );
+
+        generator.empty_test_vecs = true;
+        assert_eq!(
+            generator.generate_test_param_definitions(),
+            "let channel_ids: Vec<String> = vec![];\n        let flags: Vec<bool> = vec![];"
+        );
+    }
+
+    #[test]
+    fn generated_code_parses_for_all_templates() {
+        for operation_type in OperationType::ALL {
+            let mut generator =
+                generator_with_params("target_id: &str, conv_type: ConversationType, limit: i32");
+            generator.function_name = "getGroupInfo".to_string();
+            generator.callback_return_type = "bool".to_string();
+            generator.request_body_name = "GetGroupInfoReq".to_string();
+            generator.operation_type = Some(operation_type.clone());
+            generator.pass_params_to_request = true;
+
+            let outputs = [
+                generator.generate_engine_sync_function("get_group_info"),
+                generator.generate_engine_async_function("get_group_info"),
+                generator.generate_module_function("get_group_info"),
+                generator.generate_request_builder_function("get_group_info"),
+                generator.generate_request_struct(),
+                generator.generate_from_conversion(),
+                generator.generate_test_method("get_group_info"),
+                generator.generate_db_agent_function("get_group_info"),
+                generator.generate_db_worker_function("get_group_info"),
+                generator.generate_db_sqlite_function("get_group_info"),
+            ];
+            for code in outputs {
+                if let Err(e) = syn::parse_file(&code) {
+                    panic!("{:?}: {}\n{}", operation_type, e, code);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generic_heavy_return_type_survives_every_template() {
+        let return_type = "HashMap<String, Vec<i32>>";
+        for operation_type in OperationType::ALL {
+            let mut generator =
+                generator_with_params("filters: HashMap<String, Vec<i32>>, target_id: &str");
+            generator.callback_return_type = return_type.to_string();
+            generator.request_body_name = "GetGroupInfoReq".to_string();
+            generator.operation_type = Some(operation_type.clone());
+
+            let outputs = [
+                generator.generate_engine_sync_function("get_group_info"),
+                generator.generate_engine_async_function("get_group_info"),
+                generator.generate_module_function("get_group_info"),
+                generator.generate_request_builder_function("get_group_info"),
+            ];
+            for code in outputs.iter().filter(|code| !code.is_empty()) {
+                if let Err(e) = syn::parse_file(code) {
+                    panic!("{:?}: {}\n{}", operation_type, e, code);
+                }
+                assert!(!code.contains("{{") && !code.contains("}}"), "{}", code);
+            }
+            assert!(outputs[1].contains("Result<HashMap<String, Vec<i32>>, EngineError>"));
+            assert!(outputs[2].contains("filters: HashMap<String, Vec<i32>>,"));
+        }
+    }
+
+    #[test]
+    fn proto_message_fields_are_parsed() {
+        let proto = r#"
+syntax = "proto3";
+option java_package = "com.example";
+
+message GetGroupReq {
+    string group_id = 1; // 群组 id
+    repeated string user_ids = 2;
+    map<string, int32> counts = 3;
+    message Inner {
+        int64 ignored = 1;
+    }
+    oneof filter {
+        int64 start_time = 4;
+    }
+    reserved 5;
+}
+
+message Other {
+    string other = 1;
+}
+"#;
+        let fields = parse_proto_message(proto, "GetGroupReq");
+        let summary: Vec<(String, String)> = fields
+            .iter()
+            .map(|field| (field.name.clone(), field.rust_type()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("group_id".to_string(), "String".to_string()),
+                ("user_ids".to_string(), "Vec<String>".to_string()),
+                ("counts".to_string(), "HashMap<String, i32>".to_string()),
+                ("start_time".to_string(), "i64".to_string()),
+            ]
+        );
+
+        let mut generator = generator_with_params("group_id: String, user_ids: Vec<String>");
+        generator.request_body_name = "GetGroupReq".to_string();
+        generator.proto_source = proto.to_string();
+        let code = generator.generate_request_builder_function("get_group");
+        assert!(code.contains(
+            "let mut pb_req = PbGetGroupReq::new();\n    pb_req.set_group_id(group_id.to_string());\n    pb_req.set_user_ids(user_ids.into());\n    // pb_req.set_counts(...);"
+        ));
+    }
+
+    #[test]
+    fn network_stream_uses_fn_callback_and_borrows_self() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.operation_type = Some(OperationType::NetworkStream);
+        generator.request_body_name = "SubscribeReq".to_string();
+
+        let module = generator.generate_module_function("subscribe");
+        assert!(module.contains("CB: Fn(Result<(), EngineError>) + Send + Sync + 'static,"));
+        assert!(!module.contains("FnOnce"));
+
+        let request_struct = generator.generate_request_struct();
+        assert!(request_struct.contains("async fn deal_with_response(\n        &self,"));
+        assert!(!request_struct.contains("FnOnce"));
+        assert!(generator
+            .generate_request_builder_function("subscribe")
+            .contains("SubscribeReq::new(pb_req, cb)"));
+    }
+
+    #[test]
+    fn feature_gate_prefixes_functions_and_optionally_request() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.feature_gate = "experimental".to_string();
+
+        let code = generator.gate_item(generator.generate_module_function("get_info"));
+        assert!(
+            code.starts_with("#[cfg(feature = \"experimental\")]\npub(crate) async fn get_info")
+        );
+        assert_eq!(
+            generator.gate_request_and_test_item("fn test() {}".to_string()),
+            "fn test() {}"
+        );
+
+        generator.feature_gate_request_and_test = true;
+        generator.request_body_name = "GetInfoReq".to_string();
+        assert_eq!(
+            generator.gate_request_and_test_item("fn test() {}".to_string()),
+            "#[cfg(feature = \"experimental\")]\nfn test() {}"
+        );
+        assert!(syn::parse_file(&generator.gate_file(generator.generate_request_struct())).is_ok());
+    }
+
+    #[test]
+    fn database_engine_and_db_agent_share_default_return_type() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.operation_type = Some(OperationType::Database);
+
+        assert!(generator
+            .generate_engine_sync_function("get_info")
+            .contains("CB: FnOnce(Result<(), EngineError>) + Send + 'static,"));
+        assert!(generator
+            .generate_engine_async_function("get_info")
+            .contains("-> Result<(), EngineError>"));
+        assert!(generator
+            .generate_db_agent_function("get_info")
+            .contains("Result<(), EngineError>"));
+        assert!(generator
+            .generate_db_worker_function("get_info")
+            .contains("-> Result<(), DbError>"));
+
+        // 默认类型可配置，engine 和 db 各层同时变化
+        generator.default_return_type = "bool".to_string();
+        assert!(generator
+            .generate_module_function("get_info")
+            .contains("-> Result<bool, EngineError>"));
+        assert!(generator
+            .generate_db_agent_function("get_info")
+            .contains("-> Result<bool, EngineError>"));
+        assert!(generator
+            .generate_db_sqlite_function("get_info")
+            .contains("JoinHandle<Result<bool, DbErrorInfo>>"));
+        generator.default_return_type.clear();
+
+        generator.operation_type = Some(OperationType::Network);
+        assert!(generator
+            .generate_engine_sync_function("get_info")
+            .contains("CB: FnOnce(Result<(), EngineError>) + Send + 'static,"));
+    }
+
+    #[test]
+    fn db_worker_uses_configured_log_macro_for_start_and_result() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.db_log_macro = "log_db_d!".to_string();
+
+        let worker = generator.generate_db_worker_function("get_info");
+        assert!(worker.contains("log_db_d!(\"P-get_info-T\");"));
+        assert!(worker.contains("log_db_d!(\"P-get_info-R\", \"result\", str);"));
+
+        generator.db_log_macro.clear();
+        generator.trace_tag_prefix = "DB".to_string();
+        let worker = generator.generate_db_worker_function("get_info");
+        assert!(worker.contains("log_db_i!(\"DB-get_info-T\");"));
+        assert!(worker.contains(
+            "    let str = match &ret {\n        Ok(_) => \"\".to_string(),\n        Err(e) => e.to_string(),\n    };\n    log_db_i!(\"DB-get_info-R\", \"result\", str);\n    self.callback(method_name, ret)\n}"
+        ));
+    }
+
+    #[test]
+    fn zero_param_network_function_has_no_dangling_commas() {
+        let mut generator = generator_with_params("");
+        generator.operation_type = Some(OperationType::Network);
+        generator.request_body_name = "DisconnectRequest".to_string();
+
+        let engine_sync = generator.generate_engine_sync_function("disconnect");
+        assert!(engine_sync.contains("pub fn disconnect<CB>(&self, cb: CB)"));
+        assert!(engine_sync.contains("engine.disconnect(callback).await;"));
+
+        let engine_async = generator.generate_engine_async_function("disconnect");
+        assert!(engine_async.contains("pub async fn disconnect<CB>(&self, cb: CB)"));
+        assert!(engine_async.contains("bugtags::disconnect(&self.ctx, cb).await;"));
+
+        let module = generator.generate_module_function("disconnect");
+        assert!(module.contains("ctx: &Arc<EngineContext>,\n    cb: CB,"));
+        assert!(module.contains(".build_disconnect_request(cb);"));
+
+        let request_builder = generator.generate_request_builder_function("disconnect");
+        assert!(request_builder.contains("&self,\n    cb: CB,"));
+
+        generator.supports_cancel = true;
+        let cancellable = generator.generate_engine_async_function("disconnect");
+        assert!(cancellable
+            .contains("bugtags::disconnect(&self.ctx, cancel_token.clone(), request_cb)"));
+
+        for code in [
+            engine_sync,
+            engine_async,
+            module,
+            request_builder,
+            cancellable,
+        ] {
+            assert!(!code.contains(", ,"));
+            assert!(!code.contains("(, "));
+            assert!(!code.contains(",\n    ,"));
+            syn::parse_file(&code).unwrap();
+        }
+    }
+
+    #[test]
+    fn ffi_function_marshals_strings_and_calls_sync_engine() {
+        let mut generator = generator_with_params("target_id: &str, count: i32");
+        generator.operation_type = Some(OperationType::Network);
+
+        let ffi = generator.generate_ffi_function("get_info");
+        assert!(ffi.contains("#[no_mangle]\npub extern \"C\" fn get_info("));
+        assert!(ffi.contains("    target_id: *const c_char, count: i32,"));
+        assert!(ffi.contains(
+            "    if target_id.is_null() {\n        callback(context, EngineError::InvalidParam.code());\n        return;\n    }\n    let target_id = unsafe { CStr::from_ptr(target_id) }.to_string_lossy().into_owned();"
+        ));
+        assert!(ffi.contains("engine.get_info(&target_id, count, move |ret| {"));
+        assert!(ffi.contains("            Err(e) => e.code(),\n"));
+        assert!(!ffi.contains("as i32"));
+        assert!(!ffi.contains("TODO"));
+        syn::parse_file(&ffi).unwrap();
+
+        let ffi = generator_with_params("info: GroupInfo").generate_ffi_function("set_info");
+        assert!(ffi.contains("// TODO: 以下参数不是 C 兼容类型，需要手动转换: info\n"));
+    }
+
+    #[test]
+    fn database_engine_async_can_log_result_with_helper() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.operation_type = Some(OperationType::Database);
+        assert!(generator
+            .generate_engine_async_function("get_info")
+            .contains("let str = match &ret {"));
+
+        generator.use_log_result_helper = true;
+        let code = generator.generate_engine_async_function("get_info");
+        assert!(code.contains("    self.ctx.logger().log_result(&ret);\n    ret\n}"));
+        assert!(!code.contains("match &ret"));
+        syn::parse_file(&code).unwrap();
+    }
+
+    #[test]
+    fn tuple_callback_return_types_get_defaults_and_parse_stub() {
+        let mut generator = generator_with_params("target_id: &str");
+        assert_eq!(
+            generator.generate_default_value_for_type("(i32, String)"),
+            "(0, \"test\".to_string())"
+        );
+        assert_eq!(
+            generator.generate_default_value_for_type("(Vec<(i32, bool)>, Option<String>)"),
+            "(vec![(0, false)], None)"
+        );
+        assert_eq!(generator.generate_default_value_for_type("(u8,)"), "(0,)");
+
+        generator.callback_return_type = "(i32, String)".to_string();
+        generator.request_body_name = "GetInfoRequest".to_string();
+        let request_struct = generator.generate_request_struct();
+        assert!(request_struct.contains("// let ret: (i32, String) = (..., ...);"));
+        assert!(request_struct.contains("CB: FnOnce(Result<(i32, String), EngineError>)"));
+        assert!(generator
+            .generate_engine_async_function("get_info")
+            .contains("Ok(_) => \"\".to_string()"));
+    }
+
+    #[test]
+    fn engine_sync_guards_validate_strings_and_paging_params() {
+        let mut generator =
+            generator_with_params("target_id: &str, count: i32, offset: i64, page_size: u32");
+        let without_guards = generator.generate_engine_sync_function("get_info");
+        assert!(!without_guards.contains("return cb("));
+
+        generator.generate_guards = true;
+        generator.guard_error = "EngineError::InvalidArgument".to_string();
+        let code = generator.generate_engine_sync_function("get_info");
+        assert!(code.contains(
+            "{\n    if target_id.is_empty() {\n        return cb(Err(err!(EngineError::InvalidArgument)));\n    }\n"
+        ));
+        assert!(code.contains("    if count <= 0 {\n"));
+        assert!(code.contains("    if offset < 0 {\n"));
+        assert!(code.contains("    if page_size == 0 {\n"));
+        assert!(code.contains("    }\n    let engine = self.engine.clone();"));
+    }
+
+    #[test]
+    fn test_method_asserts_on_known_return_types() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.operation_type = Some(OperationType::Database);
+        generator.callback_return_type = "bool".to_string();
+        assert!(!generator
+            .generate_test_method("get_info")
+            .contains("assert_eq!"));
+
+        generator.expected_return = "true".to_string();
+        assert!(generator.generate_test_method("get_info").contains(
+            "        assert!(ret.is_ok());\n        assert_eq!(ret.unwrap(), true);\n        tx.send"
+        ));
+
+        generator.operation_type = Some(OperationType::Network);
+        generator.callback_return_type = "Vec<GroupInfo>".to_string();
+        assert!(generator.generate_test_method("get_info").contains(
+            "                    assert!(!ret.unwrap().is_empty());\n                    tx.send"
+        ));
+    }
+
+    #[test]
+    fn unused_imports_are_pruned_sorted_and_deduplicated() {
+        let code = "use std::sync::Arc;\nuse rust_universal_logger::err;\nuse crate::engine_def::{EngineError, EngineResult};\nuse protobuf::Message;\nuse std::sync::Arc;\n\nfn f(ctx: Arc<u8>) -> EngineError {\n    pb.write_to_bytes();\n}";
+        assert_eq!(
+            prune_unused_imports(code),
+            "use crate::engine_def::EngineError;\nuse protobuf::Message;\nuse std::sync::Arc;\n\nfn f(ctx: Arc<u8>) -> EngineError {\n    pb.write_to_bytes();\n}"
+        );
+
+        // 生成的请求体结构目前用到了全部导入
+        let generator = GenSpec {
+            request_body_name: "GetInfoRequest".to_string(),
+            ..Default::default()
+        };
+        let request_struct = generator.generate_request_struct();
+        assert!(request_struct.starts_with("use async_trait::async_trait;\n"));
+        assert!(request_struct.contains("use crate::engine_def::EngineError;\n"));
+        assert!(request_struct.contains("use rust_universal_logger::err;\n"));
+        syn::parse_file(&request_struct).unwrap();
+    }
+
+    #[test]
+    fn visibility_overrides_every_section_default() {
+        let mut generator = generator_with_params("target_id: &str");
+        assert!(generator
+            .generate_engine_sync_function("get_info")
+            .starts_with("pub fn get_info<CB>("));
+        assert!(generator
+            .generate_module_function("get_info")
+            .starts_with("pub(crate) async fn get_info<CB>("));
+
+        generator.visibility = Some(Visibility::Private);
+        assert!(generator
+            .generate_engine_sync_function("get_info")
+            .starts_with("fn get_info<CB>("));
+        assert!(generator
+            .generate_module_function("get_info")
+            .starts_with("async fn get_info<CB>("));
+
+        generator.visibility = Some(Visibility::PubCrate);
+        assert!(generator
+            .generate_engine_sync_function("get_info")
+            .starts_with("pub(crate) fn get_info<CB>("));
+    }
+
+    #[test]
+    fn network_then_db_module_saves_the_response_before_calling_back() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.operation_type = Some(OperationType::NetworkThenDb);
+        generator.callback_return_type = "GroupInfo".to_string();
+        generator.request_body_name = "GetGroupInfoRequest".to_string();
+
+        let module = generator.generate_module_function("get_group_info");
+        assert!(module.contains("let on_response = move |ret: Result<GroupInfo, EngineError>| {"));
+        assert!(module.contains(
+            "let saved = db_ctx.db_agent().save_get_group_info(value.clone()).await;\n                    saved.map(|_| value)"
+        ));
+        assert!(module.contains(".build_get_group_info_request(target_id, on_response);"));
+
+        // 模块调用的 save_xxx 由 db 输出框生成，参数为请求的结果
+        generator.function_name = "getGroupInfo".to_string();
+        let db_agent = generate_db_agent(&generator);
+        assert!(db_agent.starts_with(
+            "pub async fn save_get_group_info(\n    &self,\n    value: GroupInfo,\n) -> Result<(), EngineError> {"
+        ));
+        assert!(db_agent.contains("db_worker.save_get_group_info(value)"));
+        assert!(generate_db_worker(&generator).contains("let ret = db.save_get_group_info(value)"));
+        assert!(generate_db_sqlite(&generator).starts_with("pub fn save_get_group_info("));
+        syn::parse_file(&db_agent).unwrap();
+
+        // 可取消时和普通网络请求一样接收 engine_async 传入的 token
+        generator.supports_cancel = true;
+        let module = generator.generate_module_function("get_group_info");
+        assert!(module
+            .contains("    target_id: &str,\n    cancel_token: CancellationToken,\n    cb: CB,"));
+        assert!(module.contains("        _ = cancel_token.cancelled() => {}\n"));
+        syn::parse_file(&module).unwrap();
+        generator.supports_cancel = false;
+
+        // engine 层和请求构建与普通网络请求一致
+        assert!(generator
+            .generate_engine_async_function("get_group_info")
+            .contains("bugtags::get_group_info(&self.ctx, target_id, cb).await;"));
+        assert!(!generator
+            .generate_request_builder_function("get_group_info")
+            .is_empty());
+    }
+
+    #[test]
+    fn default_request_constructor_only_without_passed_params() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.request_body_name = "GetGroupInfoRequest".to_string();
+        generator.generate_default_request = true;
+
+        let request_struct = generator.generate_request_struct();
+        assert!(request_struct.contains(
+            "    pub(crate) fn default_request(cb: CB) -> Self {\n        Self::new(PbGetGroupInfoRequest::new(), cb)\n    }\n}"
+        ));
+        syn::parse_file(&request_struct).unwrap();
+
+        generator.pass_params_to_request = true;
+        assert!(!generator
+            .generate_request_struct()
+            .contains("fn default_request"));
+    }
+
+    #[test]
+    fn native_async_trait_drops_the_macro_and_its_import() {
+        let generator = GenSpec {
+            request_body_name: "GetInfoRequest".to_string(),
+            native_async_trait: true,
+            ..Default::default()
+        };
+        let request_struct = generator.generate_request_struct();
+        assert!(!request_struct.contains("use async_trait::async_trait;"));
+        assert!(!request_struct.contains("\n#[async_trait]\n"));
+        assert!(
+            request_struct.contains("去掉 #[async_trait]\nimpl<CB> Request for GetInfoRequest<CB>")
+        );
+        assert!(request_struct.contains("    async fn deal_with_response("));
+        syn::parse_file(&request_struct).unwrap();
+    }
+
+    #[test]
+    fn enum_backed_int_params_are_converted_in_engine_sync() {
+        let mut generator = generator_with_params("target_id: &str, status: i32, count: i32");
+        generator.enum_param_table = "status=MessageStatus, flag=Flag".to_string();
+
+        let engine_sync = generator.generate_engine_sync_function("set_status");
+        assert!(engine_sync.contains("status: i32, count: i32"));
+        assert!(engine_sync.contains("    let status = MessageStatus::from(status);\n"));
+
+        // engine_sync 之后的各层直接使用枚举类型
+        assert!(generator
+            .generate_engine_async_function("set_status")
+            .contains("status: MessageStatus, count: i32"));
+        assert!(generator
+            .generate_module_function("set_status")
+            .contains("status: MessageStatus, count: i32,"));
+    }
+
+    #[test]
+    fn binding_stubs_wrap_engine_sync_for_the_selected_target() {
+        let mut generator = generator_with_params("target_id: &str, count: i32, flag: bool");
+        generator.function_name = "getInfo".to_string();
+        assert_eq!(generator.generate_binding_function("get_info"), "");
+
+        generator.binding_target = BindingTarget::Jni;
+        let jni = generator.generate_binding_function("get_info");
+        assert!(jni.contains(
+            "pub extern \"system\" fn Java_com_example_sdk_NativeEngine_getInfo(\n    mut env: JNIEnv,\n    _class: JClass,\n    engine: jlong,\n    target_id: JString, count: jint, flag: jboolean,\n    callback: JObject,\n)"
+        ));
+        assert!(jni.contains("    let flag = flag != 0;\n"));
+        assert!(jni.contains("    engine.get_info(&target_id, count, flag, move |ret| {"));
+        assert!(jni.contains("            Err(e) => e.code(),\n"));
+        assert!(!jni.contains("as i32"));
+        syn::parse_file(&jni).unwrap();
+
+        generator.binding_target = BindingTarget::FlutterFfi;
+        let flutter = generator.generate_binding_function("get_info");
+        assert!(flutter.contains(
+            "pub fn get_info(target_id: String, count: i32, flag: bool, sink: StreamSink<i32>) {"
+        ));
+        assert!(flutter.contains("            Err(e) => e.code(),\n"));
+        assert!(!flutter.contains("as i32"));
+        syn::parse_file(&flutter).unwrap();
+    }
+
+    #[test]
+    fn vec_param_is_borrowed_for_tracing_and_moved_to_the_worker() {
+        let mut generator = generator_with_params("user_ids: Vec<String>, count: i32");
+        generator.operation_type = Some(OperationType::Database);
+
+        assert_eq!(
+            generator.generate_trace_params(),
+            "\"user_ids\": &user_ids, \"count\": count"
+        );
+        let db_agent = generator.generate_db_agent_function("remove_users");
+        assert!(db_agent.contains("db_worker.remove_users(user_ids, count)"));
+        assert_eq!(db_agent.matches("user_ids").count(), 2);
+    }
+
+    #[test]
+    fn result_alias_replaces_spelled_out_result_types() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.callback_return_type = "GroupInfo".to_string();
+        generator.use_result_alias = true;
+
+        let engine_sync = generator.generate_engine_sync_function("get_info");
+        assert!(engine_sync.contains("CB: FnOnce(EngineResult<GroupInfo>) + Send + 'static,"));
+        let engine_async = generator.generate_engine_async_function("get_info");
+        assert!(engine_async.contains("let cb = move |ret: EngineResult<GroupInfo>| {"));
+        assert!(!engine_async.contains("EngineError>"));
+
+        generator.operation_type = Some(OperationType::Database);
+        generator.result_alias = "SdkResult".to_string();
+        assert!(generator
+            .generate_module_function("get_info")
+            .contains(") -> SdkResult<GroupInfo> {"));
+    }
+
+    #[test]
+    fn wire_comment_documents_method_and_params() {
+        let mut generator = generator_with_params("target_id: &str, count: i32");
+        generator.request_body_name = "GetInfoRequest".to_string();
+        generator.rmtp_method = "qryInfo".to_string();
+        assert!(!generator
+            .generate_engine_async_function("get_info")
+            .contains("// Wire:"));
+        assert!(generator.generate_request_struct().contains(
+            "    fn get_method(&self) -> String {\n        \"qryInfo\".to_string()\n    }"
+        ));
+
+        generator.document_wire = true;
+        assert!(generator
+            .generate_engine_async_function("get_info")
+            .starts_with("// Wire: method=qryInfo, params=[target_id: &str, count: i32]\npub async fn get_info<CB>("));
+        generator.supports_cancel = true;
+        assert!(generator
+            .generate_engine_async_function("get_info")
+            .starts_with("// Wire: method=qryInfo,"));
+    }
+
+    #[test]
+    fn trace_logs_non_sensitive_params_unless_disabled() {
+        let generator =
+            generator_with_params("target_id: &str, access_token: &str, user_ids: Vec<String>");
+        let code = generator.generate_engine_async_function("get_info");
+        assert!(code.contains(
+            "trace_i_json!(self.ctx.logger(), \"P-get_info-T\", trace_id, \"target_id\": target_id, \"user_ids\": &user_ids);"
+        ));
+        assert!(!code.contains("\"access_token\""));
+
+        let generator = GenSpec {
+            log_trace_params: false,
+            operation_type: Some(OperationType::Database),
+            ..generator
+        };
+        assert!(generator
+            .generate_engine_async_function("get_info")
+            .contains("trace_i_json!(self.ctx.logger(), \"P-get_info-T\", trace_id);"));
+    }
+
+    #[test]
+    fn request_fields_drive_setters_independently_of_params() {
+        let mut generator = generator_with_params("target_id: &str, ids: Vec<String>, flag: bool");
+        generator.request_body_name = "GetInfoReq".to_string();
+        generator.request_fields = "group_id: String\n\nids: Vec<String>\nflag: bool\n".to_string();
+
+        let builder = generator.generate_request_builder_function("get_info");
+        assert!(builder.contains(
+            "let mut pb_req = PbGetInfoReq::new();\n    // pb_req.set_group_id(...);\n    pb_req.set_ids(ids.into());\n    pb_req.set_flag(flag);"
+        ));
+
+        generator.callback_return_type = "GroupInfo".to_string();
+        let from = generator.generate_from_conversion();
+        assert!(from.contains(
+            "    fn from(mut pb: PbGetInfoReq) -> Self {\n        Self {\n            group_id: pb.take_group_id(),\n            ids: pb.take_ids().into_vec(),\n            flag: pb.get_flag(),\n        }\n"
+        ));
+    }
+
+    #[test]
+    fn from_conversion_maps_proto_fields_by_type() {
+        let proto = r#"
+enum Role { ROLE_NONE = 0; }
+message Member { string id = 1; }
+message GetGroupRsp {
+    uint32 count = 1;
+    Role role = 2;
+    repeated Member members = 3;
+    Member owner = 4;
+    map<string, int32> extras = 5;
+}
+"#;
+        let mut generator = generator_with_params("");
+        generator.request_body_name = "GetGroupRsp".to_string();
+        generator.callback_return_type = "GroupInfo".to_string();
+        generator.proto_source = proto.to_string();
+        generator.request_fields = "count: u32\nrole: Role\nmembers: Vec<Member>\nowner: Member\nextras: HashMap<String, i32>\nnote: Option<String>".to_string();
+
+        let from = generator.generate_from_conversion();
+        assert!(from.contains(
+            "            count: pb.get_count(),\n            role: pb.get_role(),\n            members: pb.take_members().into_iter().map(Into::into).collect(),\n            owner: pb.take_owner().into(),\n            extras: pb.take_extras(),\n            note: todo!(),\n"
+        ));
+        syn::parse_file(&from).unwrap();
+
+        generator.request_fields.clear();
+        assert_eq!(
+            generator.generate_from_conversion(),
+            from.replace("            note: todo!(),\n", "")
+        );
+    }
+
+    #[test]
+    fn error_code_table_generates_match_in_deal_with_response() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.request_body_name = "GetInfoReq".to_string();
+        assert!(generator.generate_request_struct().contains(
+            "    ) {\n        if EngineError::Success != code {\n            (self.cb)(Err(code));\n            return;\n        }\n\n        let pb_data"
+        ));
+
+        generator.error_code_table =
+            "EngineError::NotFound => EngineError::GroupNotExist, bad entry,".to_string();
+        let code = generator.generate_request_struct();
+        assert!(code.contains(
+            "        match code {\n            EngineError::Success => {}\n            EngineError::NotFound => return (self.cb)(Err(EngineError::GroupNotExist)),\n            _ => return (self.cb)(Err(code)),\n        }\n\n        let pb_data"
+        ));
+        syn::parse_file(&code).unwrap();
+    }
+
+    #[test]
+    fn sync_mode_generates_blocking_module_and_db_functions() {
+        let generator = GenSpec {
+            async_mode: false,
+            supports_cancel: true,
+            ..generator_with_params("target_id: &str")
+        };
+        let module = generator.generate_module_function("get_info");
+        assert!(module.starts_with("pub(crate) fn get_info<CB>("));
+        assert!(module.contains("    ctx.runtime().block_on(ctx.send_query(query));\n"));
+        assert!(!module.contains("cancel_token"));
+        let engine = generator.generate_engine_async_function("get_info");
+        assert!(engine.starts_with("pub fn get_info<CB>("));
+        assert!(!engine.contains(".await") && !engine.contains("tokio::select!"));
+        syn::parse_file(&module).unwrap();
+        syn::parse_file(&engine).unwrap();
+
+        let generator = GenSpec {
+            operation_type: Some(OperationType::Database),
+            ..generator
+        };
+        let module = generator.generate_module_function("get_info");
+        assert!(module.contains("    ctx.db_agent()\n        .get_info(target_id)\n}"));
+        let db_agent = generator.generate_db_agent_function("get_info");
+        assert!(db_agent.starts_with("pub fn get_info("));
+        assert!(db_agent.contains("    self.runtime.block_on(self.execute(task, resp_rx))\n}"));
+        syn::parse_file(&db_agent).unwrap();
+    }
+
+    #[test]
+    fn sync_mode_engine_sync_calls_the_engine_directly() {
+        let mut generator = GenSpec {
+            async_mode: false,
+            ..generator_with_params("target_id: &str")
+        };
+        let code = generator.generate_engine_sync_function("get_info");
+        assert!(!code.contains("self.post(async move"));
+        assert!(!code.contains("let engine = self.engine.clone();"));
+        assert!(code.contains("    self.engine.get_info(&target_id, callback);\n}"));
+        syn::parse_file(&code).unwrap();
+
+        generator.operation_type = Some(OperationType::Database);
+        let code = generator.generate_engine_sync_function("get_info");
+        assert!(!code.contains("self.post(async move"));
+        assert!(code.contains("    let ret = self.engine.get_info(&target_id);\n    cb(ret);\n}"));
+        syn::parse_file(&code).unwrap();
+    }
+
+    #[test]
+    fn cb_pool_fn_and_variant_are_configurable() {
+        let generator = GenSpec {
+            cb_pool_fn: "spawn_cb".to_string(),
+            cb_pool_variant: CbPoolVariant::Fn,
+            ..generator_with_params("target_id: &str")
+        };
+        let engine_sync = generator.generate_engine_sync_function("get_info");
+        assert!(engine_sync.contains("    let callback = self.spawn_cb(cb);\n"));
+        assert!(
+            engine_sync.contains("    CB: Fn(Result<(), EngineError>) + Send + Sync + 'static,")
+        );
+
+        let generator = GenSpec {
+            cb_pool_fn: " ".to_string(),
+            cb_pool_variant: CbPoolVariant::FnOnce,
+            operation_type: Some(OperationType::Database),
+            ..generator
+        };
+        let engine_sync = generator.generate_engine_sync_function("get_info");
+        assert!(engine_sync.contains("    let cb = self.cb_pool_once(cb);\n"));
+        assert!(engine_sync.contains("    CB: FnOnce(Result<(), EngineError>) + Send + 'static,"));
+
+        // 流式响应的 Fn 回调不能放进只回调一次的回调池
+        let generator = GenSpec {
+            operation_type: Some(OperationType::NetworkStream),
+            ..generator
+        };
+        let engine_sync = generator.generate_engine_sync_function("get_info");
+        assert!(engine_sync.contains("    let callback = self.cb_pool(cb);\n"));
+        assert!(
+            engine_sync.contains("    CB: Fn(Result<(), EngineError>) + Send + Sync + 'static,")
+        );
+        let generator = GenSpec {
+            cb_pool_fn: "spawn_cb".to_string(),
+            cb_pool_variant: CbPoolVariant::Fn,
+            ..generator
+        };
+        assert!(generator
+            .generate_engine_sync_function("get_info")
+            .contains("    let callback = self.spawn_cb(cb);\n"));
+    }
+
+    #[test]
+    fn network_call_is_wrapped_in_configured_timeout() {
+        let mut generator = GenSpec {
+            timeout_ms: Some(5000),
+            timeout_error: "EngineError::RequestTimeout".to_string(),
+            ..generator_with_params("target_id: &str")
+        };
+        let code = generator.generate_engine_async_function("get_info");
+        assert!(code.contains(
+            "    let request = bugtags::get_info(&self.ctx, target_id, request_cb);\n    if tokio::time::timeout(std::time::Duration::from_millis(5000), request)\n"
+        ));
+        assert!(code.contains("cb(Err(EngineError::RequestTimeout));"));
+        syn::parse_file(&code).unwrap();
+
+        // 可取消时超时成为 select! 的一个分支
+        generator.supports_cancel = true;
+        let code = generator.generate_engine_async_function("get_info");
+        assert!(code.contains("// 取消或超时时回调被取走"));
+        assert!(code.contains(
+            "        _ = tokio::time::sleep(std::time::Duration::from_millis(5000)) => {\n"
+        ));
+        syn::parse_file(&code).unwrap();
+
+        // 数据库操作和阻塞版本不加超时
+        generator.supports_cancel = false;
+        generator.async_mode = false;
+        assert!(!generator
+            .generate_engine_async_function("get_info")
+            .contains("timeout"));
+        generator.async_mode = true;
+        generator.operation_type = Some(OperationType::Database);
+        assert!(!generator
+            .generate_engine_async_function("get_info")
+            .contains("timeout"));
+    }
+
+    #[test]
+    fn test_method_connects_the_selected_testers() {
+        let generator = generator_with_params("target_id: &str");
+        let default_test = generator.generate_test_method("get_info");
+        assert!(default_test.contains(
+            "        TESTER_A.connect().await.unwrap();\n        let engine = &TESTER_A.engine;\n"
+        ));
+        assert!(!default_test.contains("TESTER_B"));
+
+        let generator = GenSpec {
+            tester: TesterChoice::B,
+            operation_type: Some(OperationType::Database),
+            ..generator
+        };
+        let test = generator.generate_test_method("get_info");
+        assert!(test.contains(
+            "        TESTER_B.connect().await.unwrap();\n        let engine = &TESTER_B.engine;\n"
+        ));
+        assert!(!test.contains("TESTER_A"));
+
+        let generator = GenSpec {
+            tester: TesterChoice::AandB,
+            operation_type: Some(OperationType::Network),
+            ..generator
+        };
+        let test = generator.generate_test_method("get_info");
+        assert!(test.contains(
+            "        TESTER_A.connect().await.unwrap();\n        TESTER_B.connect().await.unwrap();\n        let engine = &TESTER_A.engine;\n        let engine_b = &TESTER_B.engine;\n"
+        ));
+        let module = generator.wrap_in_test_module(&test);
+        assert!(
+            module.contains("use crate::common::{ServerApi, SHARED_RUNTIME, TESTER_A, TESTER_B};")
+        );
+        syn::parse_file(&module).unwrap();
+
+        // 测试支持代码所在的模块路径可以配置
+        let generator = GenSpec {
+            test_support_path: "crate::test_utils::".to_string(),
+            ..generator
+        };
+        assert!(generator
+            .wrap_in_test_module(&test)
+            .contains("use crate::test_utils::{ServerApi, SHARED_RUNTIME, TESTER_A, TESTER_B};"));
+    }
+
+    #[test]
+    fn fallible_builder_returns_result_and_module_completes_cb_on_error() {
+        let generator = GenSpec {
+            request_body_name: "SetRoleReq".to_string(),
+            request_fields: "target_id: String\nrole: Role".to_string(),
+            fallible_builder: true,
+            ..generator_with_params("target_id: &str, role: i32")
+        };
+        let builder = generator.generate_request_builder_function("set_role");
+        assert!(builder.contains(") -> Result<RmtpQuery, EngineError>\n"));
+        assert!(builder.contains(
+            "    pb_req.set_target_id(target_id.to_string());\n    pb_req.set_role(role.try_into().map_err(|_| EngineError::InvalidParam)?);"
+        ));
+        assert!(builder.contains("    Ok(self.build_query("));
+        syn::parse_file(&builder).unwrap();
+
+        let module = generator.generate_module_function("set_role");
+        assert!(module.contains(".build_set_role_request(target_id, role, request_cb)\n    {\n        Ok(query) => query,"));
+        assert!(module.contains("                cb(Err(e));\n            }\n            return;"));
+        syn::parse_file(&module).unwrap();
+
+        let generator = GenSpec {
+            operation_type: Some(OperationType::NetworkThenDb),
+            ..generator
+        };
+        let module = generator.generate_module_function("set_role");
+        assert!(module
+            .contains("    let on_response = Arc::new(std::sync::Mutex::new(Some(on_response)));"));
+        syn::parse_file(&module).unwrap();
+    }
+
+    #[test]
+    fn snake_case_handles_non_ascii_letters_without_panicking() {
+        assert_eq!(to_snake_case("getÉtatInfo"), "get_état_info");
+        // İ 的小写是两个字符，全部保留
+        assert_eq!(to_snake_case("getİD"), "get_i\u{307}d");
+        // 非字母字符原样保留
+        assert_eq!(to_snake_case("get数据Info"), "get数据info");
+        assert_eq!(to_snake_case("ΣύνοψηList"), "σύνοψη_list");
+    }
+
+    #[test]
+    fn templates_substitute_known_placeholders_only() {
+        let generator = GenSpec {
+            function_name: "getInfo".to_string(),
+            callback_return_type: "bool".to_string(),
+            ..generator_with_params("target_id: &str, count: i32")
+        };
+        let code = render_template(
+            "pub fn {name}(&self, {params}) -> {return} {\n    bugtags::{name}({args}, {unknown})\n}",
+            &generator.template_vars(),
+        );
+        assert_eq!(
+            code,
+            "pub fn get_info(&self, target_id: &str, count: i32) -> bool {\n    bugtags::get_info(target_id, count, {unknown})\n}"
+        );
+        assert_eq!(
+            render_template("{ {name", &generator.template_vars()),
+            "{ {name"
+        );
+    }
+
+    #[test]
+    fn engine_impl_wrapper_indents_methods_and_falls_back_to_default_type() {
+        let generator = GenSpec {
+            function_name: "getInfo".to_string(),
+            wrap_engine_impl: true,
+            engine_type: " ".to_string(),
+            ..generator_with_params("target_id: &str")
+        };
+        let code = wrap_in_engine_impl(&generator, &generate_engine_sync(&generator));
+        assert!(code.starts_with(
+            "impl Engine {\n    pub fn get_info<CB>(&self, target_id: &str, cb: CB)\n"
+        ));
+        assert!(code.ends_with("\n    }\n}"));
+
+        let generator = GenSpec {
+            wrap_engine_impl: false,
+            ..generator
+        };
+        assert_eq!(
+            wrap_in_engine_impl(&generator, "pub fn a() {}"),
+            "pub fn a() {}"
+        );
+    }
+
+    #[test]
+    fn shared_test_setup_is_emitted_once_per_param_shape() {
+        let get_info = GenSpec {
+            function_name: "getInfo".to_string(),
+            shared_test_setup: true,
+            ..generator_with_params("target_id: &str, count: i32")
+        };
+        let clear_info = GenSpec {
+            function_name: "clearInfo".to_string(),
+            ..get_info.clone()
+        };
+        let code = generate_test_methods(&[get_info.clone(), clear_info]);
+
+        assert_eq!(
+            code.matches("fn test_params_target_id_ref_str_count_i32()")
+                .count(),
+            1
+        );
+        assert!(code.starts_with(
+            "#[cfg(test)]\nfn test_params_target_id_ref_str_count_i32() -> (&'static str, i32) {\n    let target_id: &str = \"test\";\n    let count: i32 = 20;\n    (target_id, count)\n}"
+        ));
+        assert_eq!(
+            code.matches("let (target_id, count) = test_params_target_id_ref_str_count_i32();")
+                .count(),
+            2
+        );
+        assert!(code.contains("fn get_info()") && code.contains("fn clear_info()"));
+
+        // 参数名相同但类型不同时各自生成辅助函数，不会出现重名函数
+        let by_str = GenSpec {
+            function_name: "getById".to_string(),
+            ..generator_with_params("id: &str")
+        };
+        let by_int = GenSpec {
+            function_name: "getByIndex".to_string(),
+            ..generator_with_params("id: i64")
+        };
+        let code = generate_test_methods(&[
+            GenSpec {
+                shared_test_setup: true,
+                ..by_str
+            },
+            GenSpec {
+                shared_test_setup: true,
+                ..by_int
+            },
+        ]);
+        assert_eq!(code.matches("fn test_params_id_ref_str()").count(), 1);
+        assert_eq!(code.matches("fn test_params_id_i64()").count(), 1);
+        assert_eq!(type_identifier("Vec<&[u8]>"), "vec_ref_slice_u8");
+
+        let single = GenSpec {
+            shared_test_setup: false,
+            ..get_info
+        };
+        assert!(!generate_test_method(&single).contains("test_params_"));
+    }
+
+    #[test]
+    fn callback_params_are_stripped_wherever_they_appear() {
+        let generator = generator_with_params("a: i32, cb: CB, b: String");
+        assert_eq!(
+            generator.clean_params(&generator.function_params),
+            "a: i32, b: String"
+        );
+
+        let generator = generator_with_params(
+            "callback: Box<dyn FnOnce(Result<bool, EngineError>) -> bool + Send>, target_id: &str",
+        );
+        assert_eq!(
+            generator.clean_params(&generator.function_params),
+            "target_id: &str"
+        );
+
+        let generator =
+            generator_with_params("on_done: ResultCallback, handler: impl Fn(i32), count: i32");
+        assert_eq!(
+            generator.clean_params(&generator.function_params),
+            "count: i32"
+        );
+    }
+
+    #[test]
+    fn whitespace_is_normalized_without_touching_indentation() {
+        assert_eq!(
+            normalize_whitespace("fn a() {  \n    let x = 1;\t\n\n\n\n}\n\n\n\nfn b() {}\n"),
+            "fn a() {\n    let x = 1;\n\n}\n\nfn b() {}\n"
+        );
+        assert_eq!(normalize_whitespace("fn a() {}  "), "fn a() {}");
+        assert_eq!(normalize_whitespace(""), "");
+    }
+
+    #[test]
+    fn request_struct_fields_are_documented_when_docs_are_on() {
+        let mut generator = GenSpec {
+            request_body_name: "GetInfoRequest".to_string(),
+            pass_params_to_request: true,
+            param_docs: "target_id: 目标 ID\ncount:".to_string(),
+            ..generator_with_params("target_id: &str, page_count: i32, count: i32")
+        };
+        assert!(!generator.generate_request_struct().contains("///"));
+
+        generator.document_wire = true;
+        let code = generator.generate_request_struct();
+        assert!(code.contains("    /// target_id: 目标 ID\n    target_id: String,"));
+        assert!(code.contains("    /// page_count: pageCount\n    page_count: i32,"));
+        assert!(code.contains("    /// count: count\n    count: i32,"));
+    }
+
+    #[test]
+    fn db_agent_channel_kind_switches_between_oneshot_and_mpsc() {
+        let mut generator = GenSpec {
+            operation_type: Some(OperationType::Database),
+            callback_return_type: "Vec<Message>".to_string(),
+            ..generator_with_params("target_id: &str")
+        };
+        let db_agent = generator.generate_db_agent_function("get_messages");
+        assert!(db_agent.contains(") -> Result<Vec<Message>, EngineError> {"));
+        assert!(db_agent.contains("let (resp_tx, resp_rx) = oneshot::channel();"));
+        assert!(db_agent.contains("let _ = resp_tx.send(result);"));
+        assert!(db_agent.contains("    self.execute(task, resp_rx).await\n}"));
+
+        generator.channel_kind = ChannelKind::Mpsc;
+        let db_agent = generator.generate_db_agent_function("get_messages");
+        assert!(db_agent.starts_with("// 需要引入: use tokio::sync::mpsc;\n"));
+        assert!(db_agent.contains(") -> mpsc::Receiver<Result<Vec<Message>, EngineError>> {"));
+        assert!(db_agent.contains(
+            "    const DB_STREAM_CAPACITY: usize = 32;\n    let (resp_tx, resp_rx) = mpsc::channel(DB_STREAM_CAPACITY);"
+        ));
+        // 发送端交给 db_worker 逐批发送，不再只发送一次结果
+        assert!(db_agent
+            .contains("db_worker.get_messages(target_id.as_str(), resp_tx)\n            .await;"));
+        assert!(!db_agent.contains("resp_tx.send"));
+        assert!(db_agent.ends_with("    self.runtime.spawn(task);\n    resp_rx\n}"));
+        syn::parse_file(&db_agent).unwrap();
+
+        let db_worker = generator.generate_db_worker_function("get_messages");
+        assert!(db_worker.contains(
+            "    target_id: &str,\n    resp_tx: mpsc::Sender<Result<Vec<Message>, EngineError>>,\n) {"
+        ));
+        assert!(db_worker.contains("    let mut rows = db.get_messages(target_id);\n"));
+        assert!(db_worker
+            .contains("        if resp_tx.send(ret).await.is_err() {\n            break;\n"));
+        syn::parse_file(&db_worker).unwrap();
+
+        let db_sqlite = generator.generate_db_sqlite_function("get_messages");
+        assert!(db_sqlite.contains(") -> mpsc::Receiver<Result<Vec<Message>, DbErrorInfo>> {"));
+        assert!(db_sqlite.contains(
+            "    const DB_STREAM_CAPACITY: usize = 32;\n    let (row_tx, row_rx) = mpsc::channel(DB_STREAM_CAPACITY);"
+        ));
+        assert!(db_sqlite.ends_with("    row_rx\n}"));
+        syn::parse_file(&db_sqlite).unwrap();
+    }
+
+    #[test]
+    fn graceful_connect_skips_the_test_when_the_server_is_unreachable() {
+        let mut generator = GenSpec {
+            graceful_connect: true,
+            tester: TesterChoice::AandB,
+            ..generator_with_params("target_id: &str")
+        };
+        for operation_type in [OperationType::Network, OperationType::Database] {
+            generator.operation_type = Some(operation_type);
+            let test_code = generator.generate_test_method("get_info");
+            assert!(!test_code.contains(".connect().await.unwrap()"));
+            for tester in ["TESTER_A", "TESTER_B"] {
+                assert!(test_code.contains(&format!(
+                    "        if let Err(e) = {0}.connect().await {{\n            eprintln!(\"跳过 get_info：{0} 无法连接服务器，请检查服务是否启动：{{:?}}\", e);\n            return;\n        }}\n",
+                    tester
+                )));
+            }
+        }
+    }
+
+    #[test]
+    fn spec_table_rows_become_specs_and_bad_rows_report_line_numbers() {
+        let base = GenSpec {
+            callback_return_type: "bool".to_string(),
+            ..GenSpec::default()
+        };
+        let csv = "functionName, params, returnType, operationType\n\
+getInfo, \"target_id: &str, count: i32\", Vec<String>, db\n\
+\n\
+# 注释行\n\
+clearInfo\n\
+, a: i32\n\
+setInfo, \"a: i32, ok\n\
+pushInfo, , , push\n\
+quoteInfo, \"tag: \"\"x\"\"\", , 网络请求 (流式响应), extra\n";
+        let (specs, errors) = parse_spec_table(csv, &base);
+
+        assert_eq!(specs.len(), 2);
+        assert_eq!(specs[0].function_name, "getInfo");
+        assert_eq!(specs[0].function_params, "target_id: &str, count: i32");
+        assert_eq!(specs[0].callback_return_type, "Vec<String>");
+        assert_eq!(specs[0].operation_type, Some(OperationType::Database));
+        assert_eq!(specs[1].function_name, "clearInfo");
+        assert_eq!(specs[1].callback_return_type, "bool");
+        assert_eq!(specs[1].operation_type, Some(OperationType::Network));
+        assert_eq!(
+            errors,
+            vec![
+                "第 6 行：函数名不能为空".to_string(),
+                "第 7 行：双引号没有闭合".to_string(),
+                "第 8 行：未知的操作类型：push".to_string(),
+                "第 9 行：最多 4 列 (functionName, params, returnType, operationType)，实际 5 列"
+                    .to_string(),
+            ]
+        );
+
+        let (specs, errors) =
+            parse_spec_table("getInfo\ta: i32, b: String\t\t网络请求 (流式响应)\n", &base);
+        assert!(errors.is_empty());
+        assert_eq!(specs[0].function_params, "a: i32, b: String");
+        assert_eq!(specs[0].operation_type, Some(OperationType::NetworkStream));
+    }
+
+    #[test]
+    fn unused_callback_generic_gets_a_phantom_marker() {
+        // 不保存回调的成员列表：CB 没有被任何成员用到
+        let (field, init) = phantom_marker("    pb_req: PbFoo,\n    count: u32,", "CB");
+        assert_eq!(field, "\n    _marker: std::marker::PhantomData<CB>,");
+        assert_eq!(init, "_marker: std::marker::PhantomData");
+
+        // CBox 之类只是包含 CB 的标识符，不算用到
+        let (field, _) = phantom_marker("    pb_req: PbFoo,\n    cbox: CBox,", "CB");
+        assert!(field.contains("PhantomData<CB>"));
+
+        // 默认保存回调，不需要占位
+        assert_eq!(
+            phantom_marker("    pb_req: PbFoo,\n    cb: CB,", "CB").0,
+            ""
+        );
+        let code = generator_with_params("count: u32").generate_request_struct();
+        assert!(!code.contains("PhantomData"));
+        assert!(code.contains("Self { pb_req, cb }"));
+
+        // 回调放在回调池中时 Request 不保存回调，CB 只能靠占位成员使用
+        let mut generator = generator_with_params("count: u32");
+        generator.request_body_name = "GetInfoRequest".to_string();
+        generator.request_stores_callback = false;
+        let code = generator.generate_request_struct();
+        assert!(code.contains(
+            "    pb_req: PbGetInfoRequest,\n    _marker: std::marker::PhantomData<CB>,\n}"
+        ));
+        assert!(!code.contains("cb: CB"));
+        assert!(code.contains(
+            "pub(crate) fn new(pb_req: PbGetInfoRequest) -> Self {\n        Self { pb_req, _marker: std::marker::PhantomData }"
+        ));
+        assert!(code.contains("let Some(cb) = ctx.take_callback::<CB>(msg_uid) else {"));
+        assert!(!code.contains("self.cb"));
+        syn::parse_file(&code).unwrap();
+
+        generator.use_builder_pattern = true;
+        generator.pass_params_to_request = true;
+        let code = generator.generate_request_struct();
+        assert!(code.contains("pub(crate) fn build<CB>(self) -> GetInfoRequest<CB>"));
+        assert!(code.contains("            _marker: std::marker::PhantomData,\n"));
+        syn::parse_file(&code).unwrap();
+
+        let build = generator.generate_request_builder_function("get_info");
+        assert!(code.contains("_marker") && build.contains(".build();"));
+        assert!(build.contains("    self.insert_callback(query.msg_uid(), cb);\n    query\n}"));
+    }
+
+    #[test]
+    fn request_struct_uses_the_chosen_pb_buffer_type() {
+        let mut generator = generator_with_params("count: u32");
+        let code = generator.generate_request_struct();
+        assert!(code.contains("pb_data: Option<Vec<u8>>,"));
+        assert!(code.contains("fn get_pb_data(&self) -> Vec<u8> {"));
+        assert!(!code.contains("Bytes"));
+
+        generator.pb_buffer = PbBuffer::Bytes;
+        let code = generator.generate_request_struct();
+        assert!(code.contains("use bytes::Bytes;\n"));
+        assert!(code.contains("pb_data: Option<Bytes>,"));
+        assert!(code.contains(
+            "    fn get_pb_data(&self) -> Bytes {\n        Bytes::from(self.pb_req.write_to_bytes().unwrap_or_default())\n    }"
+        ));
+    }
+
+    #[test]
+    fn warning_free_request_template_drops_unused_bindings() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.request_body_name = "GetInfoRequest".to_string();
+        generator.rmtp_method = "info.get".to_string();
+        assert!(generator.suppress_warnings);
+
+        // 没有加载 proto 时不会设置任何字段，pb_req 不需要 mut
+        let builder = generator.generate_request_builder_function("get_info");
+        assert!(builder.contains("    let pb_req = PbGetInfoRequest::new();\n"));
+        syn::parse_file(&builder).unwrap();
+
+        let request = generator.generate_request_struct();
+        assert!(request.contains(
+            "        _ctx: &Arc<EngineContext>,\n        code: EngineError,\n        _timestamp: i64,\n        _msg_uid: String,\n"
+        ));
+        syn::parse_file(&request).unwrap();
+
+        // 关闭后保留原来的写法
+        generator.suppress_warnings = false;
+        assert!(generator
+            .generate_request_builder_function("get_info")
+            .contains("    let mut pb_req = PbGetInfoRequest::new();\n"));
+        let request = generator.generate_request_struct();
+        assert!(request.contains("        ctx: &Arc<EngineContext>,\n"));
+        assert!(request.contains("        timestamp: i64,\n        msg_uid: String,\n"));
+        syn::parse_file(&request).unwrap();
+    }
+
+    #[test]
+    fn mpsc_db_results_keep_every_layer_in_agreement() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.operation_type = Some(OperationType::Database);
+        generator.callback_return_type = "Vec<Message>".to_string();
+        generator.channel_kind = ChannelKind::Mpsc;
+        let receiver = "mpsc::Receiver<Result<Vec<Message>, EngineError>>";
+
+        // db_agent、module 和 engine_async 返回同一个接收端类型
+        let db_agent = generator.generate_db_agent_function("get_messages");
+        let module = generator.generate_module_function("get_messages");
+        let engine_async = generator.generate_engine_async_function("get_messages");
+        for code in [&db_agent, &module, &engine_async] {
+            assert!(code.starts_with("// 需要引入: use tokio::sync::mpsc;\n"));
+            assert!(code.contains(&format!(") -> {} {{", receiver)), "{}", code);
+            syn::parse_file(code).unwrap();
+        }
+        assert!(module.contains("        .get_messages(target_id)\n        .await\n}"));
+        assert!(engine_async.contains("    bugtags::get_messages(&self.ctx, target_id).await\n}"));
+
+        // engine_sync 每收到一批结果回调一次，回调需要能多次调用
+        let engine_sync = generator.generate_engine_sync_function("get_messages");
+        assert!(engine_sync.contains("    let cb = self.cb_pool(cb);\n"));
+        assert!(engine_sync
+            .contains("    CB: Fn(Result<Vec<Message>, EngineError>) + Send + Sync + 'static,"));
+        assert!(engine_sync.contains(
+            "        let mut rx = engine.get_messages(&target_id).await;\n        while let Some(ret) = rx.recv().await {\n            cb(ret);\n        }\n"
+        ));
+        syn::parse_file(&engine_sync).unwrap();
+
+        let test = generator.generate_test_method("get_messages");
+        assert!(test.contains(
+            "let mut results = engine.get_messages(target_id).await;\n        let ret = results.recv().await.unwrap();"
+        ));
+        syn::parse_file(&test).unwrap();
+
+        // 阻塞版本在 engine_sync 中用 blocking_recv 接收
+        generator.async_mode = false;
+        assert!(generator
+            .generate_engine_sync_function("get_messages")
+            .contains("    while let Some(ret) = rx.blocking_recv() {\n"));
+
+        // oneshot 通道保持原来的单个结果
+        generator.async_mode = true;
+        generator.channel_kind = ChannelKind::Oneshot;
+        let module = generator.generate_module_function("get_messages");
+        assert!(module.contains(") -> Result<Vec<Message>, EngineError> {"));
+        assert!(!module.contains("mpsc"));
+    }
+
+    #[test]
+    fn call_params_pass_str_by_reference_and_owned_bare() {
+        let generator =
+            generator_with_params("target_id: &str, channel_ids: Vec<String>, count: i32");
+        assert_eq!(
+            generator.extract_param_names_for_call(),
+            "&target_id, channel_ids, count"
+        );
+    }
+}
@@ -1,380 +1,2701 @@
 use arboard::Clipboard;
+use auto_universal_sdk::{
+    generate_bench, generate_binding, generate_db_agent, generate_db_sqlite, generate_db_worker,
+    generate_engine_async, generate_engine_sync, generate_ffi, generate_from_conversion,
+    generate_module, generate_request_builder, generate_request_struct, generate_test_method,
+    generate_test_methods, normalize_whitespace, parse_spec_table, render_template,
+    split_top_level_params, to_snake_case, wrap_in_engine_impl, BindingTarget, CallbackStyle,
+    CbPoolVariant, ChannelKind, GenSpec, OperationType, PbBuffer, TesterChoice, Visibility,
+};
+use iced::advanced::text::highlighter;
 use iced::widget::{
     button, checkbox, column, container, pick_list, row, scrollable, text, text_editor, text_input,
+    Column, Row,
 };
-use iced::{Element, Font, Length, Settings, Theme};
+use iced::{keyboard, window, Element, Font, Length, Settings, Subscription, Task, Theme};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const STATUS_LOG_LIMIT: usize = 100;
+
+// 界面缩放比例的范围和每次点击 A- / A+ 的步长
+const UI_SCALE_RANGE: (f32, f32) = (0.75, 2.0);
+const UI_SCALE_STEP: f32 = 0.1;
+
+// 窗口的逻辑宽度 (除去界面缩放) 不小于该值时输入和输出左右分栏，否则上下排列
+const SPLIT_LAYOUT_MIN_WIDTH: f32 = 1000.0;
+const INPUT_COLUMN_WIDTH: f32 = 480.0;
+
+// 第一次点击清空后，需要在这段时间内再次点击才会真正清空
+const CLEAR_CONFIRM_WINDOW: Duration = Duration::from_secs(3);
+
+// 生成代码块的开始和结束标记 (后面带上函数名)，写入已有文件时只替换两行标记之间的内容
+const CODEGEN_HEADER_PREFIX: &str = "// @generated by auto_universal_sdk";
+const CODEGEN_END_MARKER_PREFIX: &str = "// codegen:do-not-edit end";
 
 fn main() -> iced::Result {
+    // --headless 时不启动界面，直接把生成的代码输出到标准输出，便于脚本批量生成
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--headless") {
+        std::process::exit(run_headless(&args));
+    }
+
     iced::application(
         "Rust 代码生成器",
-        CodeGenerator::update,
+        CodeGenerator::handle,
         CodeGenerator::view,
     )
+    .subscription(CodeGenerator::subscription)
     .settings(Settings {
         default_font: Font::with_name("PingFang SC"),
         ..Default::default()
     })
-    .run()
+    .scale_factor(|generator: &CodeGenerator| f64::from(generator.ui_scale))
+    // 关闭窗口时先保存配置再退出，见 Message::CloseRequested
+    .exit_on_close_request(false)
+    .run_with(|| (CodeGenerator::new(), Task::none()))
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum OperationType {
-    Database,
-    Network,
+// 无界面模式的参数：指定了 --fn / --params / --op 等时按参数生成，否则从标准输入读取 JSON 格式的 GenSpec
+#[derive(Debug, Default)]
+struct HeadlessArgs {
+    spec: Option<GenSpec>,
+    generate_db_functions: bool,
+    generate_ffi: bool,
+    generate_bench: bool,
 }
 
-impl std::fmt::Display for OperationType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            OperationType::Database => write!(f, "数据库操作"),
-            OperationType::Network => write!(f, "网络请求"),
+fn parse_headless_args(args: &[String]) -> Result<HeadlessArgs, String> {
+    let mut parsed = HeadlessArgs::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--headless" => {}
+            "--db" => parsed.generate_db_functions = true,
+            "--ffi" => parsed.generate_ffi = true,
+            "--bench" => parsed.generate_bench = true,
+            "--fn" | "--params" | "--return" | "--request" | "--op" => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| format!("参数 {} 缺少取值", arg))?
+                    .clone();
+                let spec = parsed.spec.get_or_insert_with(GenSpec::default);
+                match arg.as_str() {
+                    "--fn" => spec.function_name = value,
+                    "--params" => spec.function_params = value,
+                    "--return" => spec.callback_return_type = value,
+                    "--request" => spec.request_body_name = value,
+                    _ => {
+                        spec.operation_type = Some(
+                            OperationType::from_name(&value)
+                                .ok_or_else(|| format!("未知的操作类型：{}", value))?,
+                        )
+                    }
+                }
+            }
+            _ => return Err(format!("未知参数：{}", arg)),
+        }
+    }
+    Ok(parsed)
+}
+
+// 返回进程退出码：参数或输入有误时为 2
+fn run_headless(args: &[String]) -> i32 {
+    let parsed = match parse_headless_args(args) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("错误：{}", e);
+            return 2;
+        }
+    };
+    let spec = match parsed.spec {
+        Some(spec) => spec,
+        None => {
+            let mut input = String::new();
+            if let Err(e) = std::io::Read::read_to_string(&mut std::io::stdin(), &mut input) {
+                eprintln!("错误：读取标准输入失败：{}", e);
+                return 2;
+            }
+            match serde_json::from_str(&input) {
+                Ok(spec) => spec,
+                Err(e) => {
+                    eprintln!("错误：标准输入不是有效的 GenSpec JSON：{}", e);
+                    return 2;
+                }
+            }
         }
+    };
+    if spec.function_name.is_empty() {
+        eprintln!("错误：函数名称不能为空！");
+        return 2;
     }
+
+    let generator = CodeGenerator {
+        spec,
+        generate_db_functions: parsed.generate_db_functions,
+        generate_ffi: parsed.generate_ffi,
+        generate_bench: parsed.generate_bench,
+        ..Default::default()
+    };
+    print!("{}", generator.headless_output());
+    0
 }
 
-impl OperationType {
-    const ALL: [OperationType; 2] = [OperationType::Database, OperationType::Network];
+// 持久化的用户配置，保存在系统配置目录下的 auto_universal_sdk/config.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct Config {
+    trace_macro: String,
+    trace_tag_prefix: String,
+    // db_worker 中使用的日志宏
+    db_log_macro: String,
+    conv_type_name: String,
+    db_conv_type_name: String,
+    timestamp_type: String,
+    // 测试方法中整数参数按名称使用的取值，格式为 "name=value, ..."
+    test_value_table: String,
+    // 实际是枚举的整数参数，格式为 "name=EnumType, ..."，engine_sync 中转换为枚举
+    enum_param_table: String,
+    // deal_with_response 中需要重新映射的错误码，格式为 "code => error, ..."
+    error_code_table: String,
+    cancel_token_import: String,
+    // 测试模块中 ServerApi、SHARED_RUNTIME 和测试账号所在的模块路径
+    test_support_path: String,
+    timeout_error: String,
+    // engine_async 被取消时回调的错误
+    cancel_error: String,
+    // 参数校验失败时回调的错误
+    guard_error: String,
+    // engine 方法包裹的 impl 块类型名
+    engine_type: String,
+    // Result<T, EngineError> 的类型别名
+    result_alias: String,
+    // 未填写回调返回值类型时使用的类型，engine、module、request 和 db 各层共用
+    default_return_type: String,
+    // engine_sync 中包装回调的函数名，以及回调池按 FnOnce 还是 Fn 保存回调
+    cb_pool_fn: String,
+    cb_pool_variant: CbPoolVariant,
+    // Request 结构体每个额外成员上方的属性模板，{field} 替换为成员名，为空时不生成
+    field_attribute_template: String,
+    // 输出框的显示顺序
+    section_order: Vec<SectionId>,
+    // 用户自定义的各输出框模板，没有或为空时使用内置模板
+    section_templates: HashMap<SectionId, String>,
+    // 参数类型自动补全使用的已知类型，逗号分隔
+    known_types: String,
+    // 上次使用的 Cargo feature 名称
+    feature_gate: String,
+    // 界面缩放比例，范围见 UI_SCALE_RANGE
+    ui_scale: f32,
+    // 输出框的语法高亮，代码很长时可以关闭
+    syntax_highlighting: bool,
+    // 生成函数的可见性，None 表示按各输出框的默认值
+    visibility: Option<Visibility>,
+    // 累计生成的函数数量，按操作类型统计
+    generation_totals: HashMap<OperationType, u64>,
 }
 
-struct CodeGenerator {
+impl Default for Config {
+    fn default() -> Self {
+        let spec = GenSpec::default();
+        Self {
+            trace_macro: spec.trace_macro,
+            trace_tag_prefix: spec.trace_tag_prefix,
+            db_log_macro: spec.db_log_macro,
+            conv_type_name: spec.conv_type_name,
+            db_conv_type_name: spec.db_conv_type_name,
+            timestamp_type: spec.timestamp_type,
+            test_value_table: spec.test_value_table,
+            enum_param_table: spec.enum_param_table,
+            error_code_table: spec.error_code_table,
+            cancel_token_import: spec.cancel_token_import,
+            test_support_path: spec.test_support_path,
+            timeout_error: spec.timeout_error,
+            cancel_error: spec.cancel_error,
+            guard_error: spec.guard_error,
+            engine_type: spec.engine_type,
+            result_alias: spec.result_alias,
+            default_return_type: spec.default_return_type,
+            cb_pool_fn: spec.cb_pool_fn,
+            cb_pool_variant: spec.cb_pool_variant,
+            field_attribute_template: spec.field_attribute_template,
+            section_order: SectionId::ALL.to_vec(),
+            section_templates: HashMap::new(),
+            known_types: DEFAULT_KNOWN_TYPES.to_string(),
+            feature_gate: spec.feature_gate,
+            ui_scale: 1.0,
+            syntax_highlighting: true,
+            visibility: spec.visibility,
+            generation_totals: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    const FILE_NAME: &'static str = "config.json";
+
+    // 配置文件不存在或格式错误时使用默认配置
+    fn load(data_dir: Option<&Path>) -> Self {
+        read_app_json(data_dir, Self::FILE_NAME).unwrap_or_default()
+    }
+
+    fn save(&self, data_dir: Option<&Path>) -> std::io::Result<()> {
+        write_app_json(data_dir, Self::FILE_NAME, self)
+    }
+}
+
+// 上次生成的代码缓存，重新打开应用后可以直接恢复
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LastGenerated {
+    sections: HashMap<SectionId, String>,
+}
+
+impl LastGenerated {
+    const FILE_NAME: &'static str = "last_generated.json";
+
+    fn load(data_dir: Option<&Path>) -> Option<Self> {
+        read_app_json(data_dir, Self::FILE_NAME)
+    }
+
+    fn save(&self, data_dir: Option<&Path>) -> std::io::Result<()> {
+        write_app_json(data_dir, Self::FILE_NAME, self)
+    }
+}
+
+// 导出给别人复现生成结果的全部输入，不包含生成的代码
+// 新增字段时保持 serde(default)，旧版本导出的 JSON 缺少的字段使用默认值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct InputsSnapshot {
+    version: u32,
     project_path: String,
     function_name: String,
     function_params: String,
+    input_language: InputLanguage,
     callback_return_type: String,
+    expected_return: String,
+    java_callback_type: String,
     request_body_name: String,
     request_file_name: String,
+    // Request::get_method 返回的协议方法名
+    rmtp_method: String,
+    // 网络请求的 engine_async 上方生成 // Wire: 注释，请求结构体的字段上方生成 /// 文档
+    document_wire: bool,
+    request_fields: String,
+    param_docs: String,
     operation_type: Option<OperationType>,
+    // 生成函数的可见性，None 表示按各输出框的默认值
+    visibility: Option<Visibility>,
+    callback_style: CallbackStyle,
+    pass_params_to_request: bool,
+    // 不传参数时额外生成 default_request(cb) 便于测试构造请求
+    generate_default_request: bool,
+    // 关闭时 Request 不保存回调，发送前把回调放入回调池，收到响应时按 msg_uid 取出
+    request_stores_callback: bool,
+    use_builder_pattern: bool,
+    fallible_builder: bool,
+    // Request 实现使用原生 trait async fn，不再依赖 async_trait 宏
+    native_async_trait: bool,
+    pb_buffer: PbBuffer,
+    // 关闭时 module、db_agent 和 engine 生成阻塞版本，供不使用 async 的调用方
+    async_mode: bool,
+    generate_db_functions: bool,
+    generate_ffi: bool,
+    generate_bench: bool,
+    binding_target: BindingTarget,
+    wrap_test_module: bool,
+    shared_test_setup: bool,
+    tester: TesterChoice,
+    graceful_connect: bool,
+    channel_kind: ChannelKind,
+    prefer_owned_strings: bool,
+    suppress_warnings: bool,
+    supports_cancel: bool,
+    timeout_ms: Option<u32>,
+    empty_test_vecs: bool,
+    validate_syntax: bool,
+    keep_nested_type_paths: bool,
+    use_log_result_helper: bool,
+    use_tracing_instrument: bool,
+    log_trace_params: bool,
+    feature_gate_request_and_test: bool,
+    trace_macro: String,
+    trace_tag_prefix: String,
+    db_log_macro: String,
+    conv_type_name: String,
+    db_conv_type_name: String,
+    timestamp_type: String,
+    test_value_table: String,
+    enum_param_table: String,
+    error_code_table: String,
+    cancel_token_import: String,
+    test_support_path: String,
+    timeout_error: String,
+    cancel_error: String,
+    generate_guards: bool,
+    guard_error: String,
+    wrap_engine_impl: bool,
+    engine_type: String,
+    use_result_alias: bool,
+    result_alias: String,
+    default_return_type: String,
+    cb_pool_fn: String,
+    cb_pool_variant: CbPoolVariant,
+    field_attribute_template: String,
+    feature_gate: String,
+    proto_message_name: String,
+}
+
+impl InputsSnapshot {
+    const VERSION: u32 = 1;
+}
+
+impl Default for InputsSnapshot {
+    fn default() -> Self {
+        CodeGenerator::default().inputs_snapshot()
+    }
+}
+
+// 应用数据目录：系统配置目录下的 auto_universal_sdk
+fn app_data_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("auto_universal_sdk"))
+}
+
+// 数据目录为 None 时 (单元测试中默认如此) 不读写任何文件
+fn read_app_json<T: serde::de::DeserializeOwned>(
+    data_dir: Option<&Path>,
+    file_name: &str,
+) -> Option<T> {
+    let content = std::fs::read_to_string(data_dir?.join(file_name)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_app_json<T: Serialize>(
+    data_dir: Option<&Path>,
+    file_name: &str,
+    value: &T,
+) -> std::io::Result<()> {
+    let Some(dir) = data_dir else {
+        return Ok(());
+    };
+    std::fs::create_dir_all(dir)?;
+    let content = serde_json::to_string_pretty(value).map_err(std::io::Error::other)?;
+    std::fs::write(dir.join(file_name), content)
+}
+
+// 按操作类型记住的开关状态，切换操作类型时恢复
+#[derive(Debug, Clone, Copy)]
+struct OperationToggles {
+    generate_db_functions: bool,
     pass_params_to_request: bool,
+}
+
+// Tab / Shift+Tab 依次切换的输入框，跳过输出编辑器
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FocusField {
+    ProjectPath,
+    FunctionName,
+    FunctionParams,
+    CallbackReturnType,
+    RequestBody,
+}
+
+impl FocusField {
+    const ALL: [FocusField; 5] = [
+        FocusField::ProjectPath,
+        FocusField::FunctionName,
+        FocusField::FunctionParams,
+        FocusField::CallbackReturnType,
+        FocusField::RequestBody,
+    ];
+
+    fn id(self) -> text_input::Id {
+        text_input::Id::new(match self {
+            FocusField::ProjectPath => "project_path",
+            FocusField::FunctionName => "function_name",
+            FocusField::FunctionParams => "function_params",
+            FocusField::CallbackReturnType => "callback_return_type",
+            FocusField::RequestBody => "request_body",
+        })
+    }
+
+    // 在输入框中编辑时记下当前焦点，Tab 从这里继续
+    fn edited_by(message: &Message) -> Option<FocusField> {
+        match message {
+            Message::ProjectPathChanged(_) => Some(FocusField::ProjectPath),
+            Message::FunctionNameChanged(_) => Some(FocusField::FunctionName),
+            Message::FunctionParamsChanged(_) => Some(FocusField::FunctionParams),
+            Message::CallbackReturnTypeChanged(_) => Some(FocusField::CallbackReturnType),
+            Message::RequestBodyNameChanged(_) => Some(FocusField::RequestBody),
+            _ => None,
+        }
+    }
+
+    // 循环切换：没有焦点时正向从第一个开始，反向从最后一个开始
+    fn step(current: Option<FocusField>, forward: bool) -> FocusField {
+        let len = Self::ALL.len();
+        let index = match current {
+            Some(field) => {
+                let index = Self::ALL.iter().position(|&f| f == field).unwrap_or(0);
+                if forward {
+                    (index + 1) % len
+                } else {
+                    (index + len - 1) % len
+                }
+            }
+            None if forward => 0,
+            None => len - 1,
+        };
+        Self::ALL[index]
+    }
+}
+
+// 参数输入框中的语言：Java 会自动识别并转换，Kotlin 总是转换，Rust 原样使用
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum InputLanguage {
+    Java,
+    Kotlin,
+    Rust,
+}
+
+impl std::fmt::Display for InputLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputLanguage::Java => write!(f, "Java"),
+            InputLanguage::Kotlin => write!(f, "Kotlin"),
+            InputLanguage::Rust => write!(f, "Rust"),
+        }
+    }
+}
+
+impl InputLanguage {
+    const ALL: [InputLanguage; 3] = [
+        InputLanguage::Java,
+        InputLanguage::Kotlin,
+        InputLanguage::Rust,
+    ];
+}
+
+// 参数类型自动补全的默认已知类型，用户可以在界面上追加
+const DEFAULT_KNOWN_TYPES: &str =
+    "ConversationType, MessageId, FriendInfo, GroupInfo, UserInfo, Message, Conversation";
+
+// 输出框语法高亮的 token 种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RustToken {
+    Keyword,
+    Type,
+    String,
+    Number,
+    Comment,
+    Macro,
+}
+
+const RUST_KEYWORDS: [&str; 39] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "yield",
+];
+
+const RUST_PRIMITIVE_TYPES: [&str; 17] = [
+    "bool", "char", "str", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64",
+    "u128", "usize", "f32", "f64",
+];
+
+// 按行高亮 Rust 代码，只有块注释会跨行，记录每行开头是否在块注释中；
+// settings 为是否启用，关闭时不输出任何高亮
+struct RustHighlighter {
+    enabled: bool,
+    line_states: Vec<bool>,
+    current_line: usize,
+}
+
+impl highlighter::Highlighter for RustHighlighter {
+    type Settings = bool;
+    type Highlight = RustToken;
+    type Iterator<'a> = std::vec::IntoIter<(std::ops::Range<usize>, RustToken)>;
+
+    fn new(enabled: &bool) -> Self {
+        Self {
+            enabled: *enabled,
+            line_states: vec![false],
+            current_line: 0,
+        }
+    }
+
+    fn update(&mut self, enabled: &bool) {
+        self.enabled = *enabled;
+        self.change_line(0);
+    }
+
+    fn change_line(&mut self, line: usize) {
+        self.current_line = line.min(self.line_states.len() - 1);
+        self.line_states.truncate(self.current_line + 1);
+    }
+
+    fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
+        let in_block_comment = self.line_states[self.current_line];
+        let (tokens, ends_in_block_comment) = if self.enabled {
+            tokenize_rust_line(line, in_block_comment)
+        } else {
+            (Vec::new(), false)
+        };
+        self.current_line += 1;
+        self.line_states.truncate(self.current_line);
+        self.line_states.push(ends_in_block_comment);
+        tokens.into_iter()
+    }
+
+    fn current_line(&self) -> usize {
+        self.current_line
+    }
+}
+
+// 深色主题用较亮的颜色，浅色主题用较暗的颜色
+fn rust_token_format(token: &RustToken, theme: &Theme) -> highlighter::Format<Font> {
+    let dark = theme.extended_palette().is_dark;
+    let (r, g, b) = match (token, dark) {
+        (RustToken::Keyword, true) => (0.80, 0.47, 0.87),
+        (RustToken::Keyword, false) => (0.60, 0.15, 0.65),
+        (RustToken::Type, true) => (0.35, 0.75, 0.85),
+        (RustToken::Type, false) => (0.05, 0.45, 0.55),
+        (RustToken::String, true) => (0.60, 0.80, 0.45),
+        (RustToken::String, false) => (0.25, 0.50, 0.10),
+        (RustToken::Number, true) => (0.85, 0.65, 0.40),
+        (RustToken::Number, false) => (0.65, 0.35, 0.05),
+        (RustToken::Comment, true) => (0.50, 0.55, 0.55),
+        (RustToken::Comment, false) => (0.45, 0.50, 0.50),
+        (RustToken::Macro, true) => (0.40, 0.60, 0.95),
+        (RustToken::Macro, false) => (0.10, 0.30, 0.75),
+    };
+    highlighter::Format {
+        color: Some(iced::Color::from_rgb(r, g, b)),
+        font: None,
+    }
+}
+
+// 把一行代码切分为高亮区间 (字节范围)，返回区间和行尾是否仍在块注释中。
+// 多行的字符串只高亮第一行，生成的代码中很少出现
+fn tokenize_rust_line(
+    line: &str,
+    in_block_comment: bool,
+) -> (Vec<(std::ops::Range<usize>, RustToken)>, bool) {
+    let chars: Vec<(usize, char)> = line.char_indices().collect();
+    let byte_at = |index: usize| chars.get(index).map_or(line.len(), |&(byte, _)| byte);
+    let char_at = |index: usize| chars.get(index).map(|&(_, c)| c);
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    if in_block_comment {
+        match line.find("*/") {
+            Some(end) => {
+                tokens.push((0..end + 2, RustToken::Comment));
+                i = chars
+                    .iter()
+                    .position(|&(byte, _)| byte >= end + 2)
+                    .unwrap_or(chars.len());
+            }
+            None => {
+                tokens.push((0..line.len(), RustToken::Comment));
+                return (tokens, true);
+            }
+        }
+    }
+
+    while i < chars.len() {
+        let c = chars[i].1;
+        let start = i;
+        if c == '/' && char_at(i + 1) == Some('/') {
+            tokens.push((byte_at(i)..line.len(), RustToken::Comment));
+            break;
+        } else if c == '/' && char_at(i + 1) == Some('*') {
+            match line[byte_at(i) + 2..].find("*/") {
+                Some(offset) => {
+                    let end = byte_at(i) + 2 + offset + 2;
+                    tokens.push((byte_at(i)..end, RustToken::Comment));
+                    i = chars
+                        .iter()
+                        .position(|&(byte, _)| byte >= end)
+                        .unwrap_or(chars.len());
+                }
+                None => {
+                    tokens.push((byte_at(i)..line.len(), RustToken::Comment));
+                    return (tokens, true);
+                }
+            }
+            continue;
+        } else if c == '"'
+            || (c == 'r' && matches!(char_at(i + 1), Some('"' | '#')) && {
+                let hashes = chars[i + 1..]
+                    .iter()
+                    .take_while(|&&(_, c)| c == '#')
+                    .count();
+                char_at(i + 1 + hashes) == Some('"')
+            })
+        {
+            // 普通字符串按反斜杠转义，原始字符串以相同个数的 # 结束
+            let (hashes, mut j) = if c == 'r' {
+                let hashes = chars[i + 1..]
+                    .iter()
+                    .take_while(|&&(_, c)| c == '#')
+                    .count();
+                (Some(hashes), i + hashes + 2)
+            } else {
+                (None, i + 1)
+            };
+            while j < chars.len() {
+                match (chars[j].1, hashes) {
+                    ('\\', None) => j += 2,
+                    ('"', None) => {
+                        j += 1;
+                        break;
+                    }
+                    ('"', Some(hashes))
+                        if chars[j + 1..]
+                            .iter()
+                            .take(hashes)
+                            .filter(|&&(_, c)| c == '#')
+                            .count()
+                            == hashes =>
+                    {
+                        j += 1 + hashes;
+                        break;
+                    }
+                    _ => j += 1,
+                }
+            }
+            i = j.min(chars.len());
+            tokens.push((byte_at(start)..byte_at(i), RustToken::String));
+        } else if c == '\'' {
+            // 'a' 和 '\n' 是字符，'static 这样的生命周期不高亮
+            let end = if char_at(i + 1) == Some('\\') {
+                (i + 2..chars.len()).find(|&j| chars[j].1 == '\'')
+            } else {
+                (char_at(i + 2) == Some('\'')).then_some(i + 2)
+            };
+            match end {
+                Some(end) => {
+                    i = end + 1;
+                    tokens.push((byte_at(start)..byte_at(i), RustToken::String));
+                }
+                None => i += 1,
+            }
+        } else if c.is_ascii_digit() {
+            while i < chars.len()
+                && (chars[i].1.is_alphanumeric() || matches!(chars[i].1, '_' | '.'))
+            {
+                // 0..10 这样的区间不属于数字
+                if chars[i].1 == '.' && char_at(i + 1) == Some('.') {
+                    break;
+                }
+                i += 1;
+            }
+            tokens.push((byte_at(start)..byte_at(i), RustToken::Number));
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].1.is_alphanumeric() || chars[i].1 == '_') {
+                i += 1;
+            }
+            let word = &line[byte_at(start)..byte_at(i)];
+            let token = if char_at(i) == Some('!') && char_at(i + 1) != Some('=') {
+                i += 1;
+                Some(RustToken::Macro)
+            } else if RUST_KEYWORDS.contains(&word) {
+                Some(RustToken::Keyword)
+            } else if RUST_PRIMITIVE_TYPES.contains(&word) || c.is_uppercase() {
+                Some(RustToken::Type)
+            } else {
+                None
+            };
+            if let Some(token) = token {
+                tokens.push((byte_at(start)..byte_at(i), token));
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    (tokens, false)
+}
+
+// 自动补全最多显示的候选类型数
+const TYPE_SUGGESTION_LIMIT: usize = 6;
+
+// 正在输入类型的输入框：参数输入框，或参数表格中某一行的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TypeCompletionTarget {
+    Params,
+    Row(usize),
+}
+
+// 参数表格中的一行，和 function_params 中的一个 "名称: 类型" 对应
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ParamRow {
+    name: String,
+    param_type: String,
+}
+
+// 输出框标识，按界面上的显示顺序排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum SectionId {
+    EngineSync,
+    EngineAsync,
+    Module,
+    RequestBuilder,
+    RequestStruct,
+    FromConversion,
+    TestMethod,
+    DbAgent,
+    DbWorker,
+    DbSqlite,
+    Ffi,
+    Binding,
+    Bench,
+}
+
+impl std::fmt::Display for SectionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.title())
+    }
+}
+
+impl SectionId {
+    const ALL: [SectionId; 13] = [
+        SectionId::EngineSync,
+        SectionId::EngineAsync,
+        SectionId::Module,
+        SectionId::RequestBuilder,
+        SectionId::RequestStruct,
+        SectionId::FromConversion,
+        SectionId::TestMethod,
+        SectionId::DbAgent,
+        SectionId::DbWorker,
+        SectionId::DbSqlite,
+        SectionId::Ffi,
+        SectionId::Binding,
+        SectionId::Bench,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            SectionId::EngineSync => "engine_sync.rs",
+            SectionId::EngineAsync => "engine_async.rs",
+            SectionId::Module => "module 文件",
+            SectionId::RequestBuilder => "request_builder 文件",
+            SectionId::RequestStruct => "请求体结构",
+            SectionId::FromConversion => "From 转换",
+            SectionId::TestMethod => "测试方法",
+            SectionId::DbAgent => "db_agent.rs (A函数)",
+            SectionId::DbWorker => "db_worker.rs (B函数)",
+            SectionId::DbSqlite => "db_sqlite.rs (C函数)",
+            SectionId::Ffi => "ffi.rs (C 接口)",
+            SectionId::Binding => "绑定层 (JNI / Flutter)",
+            SectionId::Bench => "基准测试 (criterion)",
+        }
+    }
+
+    // 用库中对应的生成函数生成该输出框的代码
+    fn generate(self, spec: &GenSpec) -> String {
+        match self {
+            SectionId::EngineSync => generate_engine_sync(spec),
+            SectionId::EngineAsync => generate_engine_async(spec),
+            SectionId::Module => generate_module(spec),
+            SectionId::RequestBuilder => generate_request_builder(spec),
+            SectionId::RequestStruct => generate_request_struct(spec),
+            SectionId::FromConversion => generate_from_conversion(spec),
+            SectionId::TestMethod => generate_test_method(spec),
+            SectionId::DbAgent => generate_db_agent(spec),
+            SectionId::DbWorker => generate_db_worker(spec),
+            SectionId::DbSqlite => generate_db_sqlite(spec),
+            SectionId::Ffi => generate_ffi(spec),
+            SectionId::Binding => generate_binding(spec),
+            SectionId::Bench => generate_bench(spec),
+        }
+    }
+
+    fn copy_message(self) -> Message {
+        match self {
+            SectionId::EngineSync => Message::CopyEngineSyncToClipboard,
+            SectionId::EngineAsync => Message::CopyEngineAsyncToClipboard,
+            SectionId::Module => Message::CopyModuleToClipboard,
+            SectionId::RequestBuilder => Message::CopyRequestBuilderToClipboard,
+            SectionId::RequestStruct => Message::CopyRequestStructToClipboard,
+            SectionId::FromConversion => Message::CopyFromConversionToClipboard,
+            SectionId::TestMethod => Message::CopyTestMethodToClipboard,
+            SectionId::DbAgent => Message::CopyDbAgentToClipboard,
+            SectionId::DbWorker => Message::CopyDbWorkerToClipboard,
+            SectionId::DbSqlite => Message::CopyDbSqliteToClipboard,
+            SectionId::Ffi => Message::CopyFfiToClipboard,
+            SectionId::Binding => Message::CopyBindingToClipboard,
+            SectionId::Bench => Message::CopyBenchToClipboard,
+        }
+    }
+
+    fn action_message(self) -> fn(text_editor::Action) -> Message {
+        match self {
+            SectionId::EngineSync => Message::EngineSyncAction,
+            SectionId::EngineAsync => Message::EngineAsyncAction,
+            SectionId::Module => Message::ModuleAction,
+            SectionId::RequestBuilder => Message::RequestBuilderAction,
+            SectionId::RequestStruct => Message::RequestStructAction,
+            SectionId::FromConversion => Message::FromConversionAction,
+            SectionId::TestMethod => Message::TestMethodAction,
+            SectionId::DbAgent => Message::DbAgentAction,
+            SectionId::DbWorker => Message::DbWorkerAction,
+            SectionId::DbSqlite => Message::DbSqliteAction,
+            SectionId::Ffi => Message::FfiAction,
+            SectionId::Binding => Message::BindingAction,
+            SectionId::Bench => Message::BenchAction,
+        }
+    }
+}
+
+struct CodeGenerator {
+    project_path: String,
+    // 生成代码用到的全部输入，生成逻辑在 lib.rs 中
+    spec: GenSpec,
+    // 自动转换前用户输入的原始参数（可能是 Java 风格）
+    raw_function_params: String,
+    // 参数输入框显示原始输入还是转换后的 Rust 参数
+    show_raw_params: bool,
+    input_language: InputLanguage,
+    // 参数表格，编辑后同步拼接到 function_params
+    params: Vec<ParamRow>,
+    java_callback_type: String,
+    request_file_name: String,
+    request_fields_editor: text_editor::Content,
+    // 请求字段说明面板，内容同步到 spec.param_docs
+    show_param_docs: bool,
+    param_docs_editor: text_editor::Content,
     generate_db_functions: bool,
+    generate_ffi: bool,
+    generate_bench: bool,
+    validate_syntax: bool,
+    // 复制时在开头加上 // File: 目标文件路径
+    annotate_copies: bool,
+    // 复制时用 @generated 头和结束标记包住代码，便于之后写入时按标记替换
+    codegen_markers: bool,
+    section_order: Vec<SectionId>,
+    ui_scale: f32,
+    syntax_highlighting: bool,
+    // 已加载的 .proto 文件路径，文件内容保存在 spec.proto_source 中
+    proto_path: Option<PathBuf>,
     engine_sync_content: text_editor::Content,
     engine_async_content: text_editor::Content,
     module_content: text_editor::Content,
     request_builder_content: text_editor::Content,
     request_struct_content: text_editor::Content,
+    from_conversion_content: text_editor::Content,
     test_method_content: text_editor::Content,
     db_agent_content: text_editor::Content,
     db_worker_content: text_editor::Content,
     db_sqlite_content: text_editor::Content,
+    ffi_content: text_editor::Content,
+    binding_content: text_editor::Content,
+    bench_content: text_editor::Content,
+    batch_signatures: text_editor::Content,
+    search_query: String,
+    collapsed_sections: HashSet<SectionId>,
+    // 每个输出框的行数和字符数，内容变化时更新
+    section_stats: HashMap<SectionId, (usize, usize)>,
+    // 每种操作类型上次使用的开关状态，当前类型的状态仍保存在各自的字段中
+    operation_toggles: HashMap<OperationType, OperationToggles>,
+    // 生成的代码无法被 syn 解析时，对应输出框的错误位置和信息
+    syntax_errors: HashMap<SectionId, String>,
+    // 最近一次写入磁盘的输出框，用于打开所在文件夹
+    last_written_section: Option<SectionId>,
+    // 写入计划预览（只读），未预览时不显示
+    write_plan: Option<text_editor::Content>,
+    // 粘贴进来的目标文件内容，生成的代码插入到其中的光标处
+    target_file: text_editor::Content,
+    insert_section: SectionId,
+    // 模板编辑：是否显示、正在编辑哪个输出框的模板，以及自定义模板 (保存在配置中)
+    show_templates: bool,
+    template_section: SectionId,
+    template_editor: text_editor::Content,
+    section_templates: HashMap<SectionId, String>,
+    known_types: String,
+    // 最近输入类型的输入框，自动补全的候选显示在它下方
+    type_completion: Option<TypeCompletionTarget>,
     status_message: String,
+    // 第一次点击清空的时间，再次点击时确认清空，其他操作会取消
+    pending_clear: Option<Instant>,
+    // 最近编辑或通过 Tab 切换到的输入框
+    focused_input: Option<FocusField>,
+    // 状态消息历史，最多保留 STATUS_LOG_LIMIT 条
+    log: Vec<(Instant, String)>,
+    started_at: Instant,
+    // 当前窗口宽度，决定是否左右分栏；启动时使用 iced 的默认窗口宽度
+    window_width: f32,
+    // 本次会话和累计 (保存在配置中) 生成的函数数量，按操作类型统计
+    session_generation_counts: HashMap<OperationType, u64>,
+    generation_totals: HashMap<OperationType, u64>,
+    // 配置和生成缓存的保存目录，默认不保存，启动应用时设为 app_data_dir
+    data_dir: Option<PathBuf>,
+    // 输入框中修改了配置但还没保存，生成代码或关闭窗口时再保存，不在每次按键时写文件
+    config_dirty: bool,
 }
 
 #[derive(Debug, Clone)]
 enum Message {
+    FocusNext,
+    FocusPrevious,
+    WindowResized(iced::Size),
+    CloseRequested,
     ProjectPathChanged(String),
     FunctionNameChanged(String),
     FunctionParamsChanged(String),
+    ToggleShowRawParams(bool),
+    InputLanguageSelected(InputLanguage),
+    KnownTypesChanged(String),
+    TypeSuggestionSelected(String),
+    ParamRowNameChanged(usize, String),
+    ParamRowTypeChanged(usize, String),
+    ParamRowAdded,
+    ParamRowRemoved(usize),
     CallbackReturnTypeChanged(String),
+    ExpectedReturnChanged(String),
+    JavaCallbackTypeChanged(String),
     RequestBodyNameChanged(String),
     RequestFileNameChanged(String),
+    RmtpMethodChanged(String),
+    RequestFieldsAction(text_editor::Action),
+    ToggleShowParamDocs(bool),
+    ParamDocsAction(text_editor::Action),
+    ToggleDocumentWire(bool),
+    TraceMacroChanged(String),
+    TraceTagPrefixChanged(String),
+    DbLogMacroChanged(String),
+    ConvTypeNameChanged(String),
+    DbConvTypeNameChanged(String),
+    TimestampTypeChanged(String),
+    TestValueTableChanged(String),
+    EnumParamTableChanged(String),
+    ErrorCodeTableChanged(String),
+    CancelTokenImportChanged(String),
+    CancelErrorChanged(String),
+    TimeoutMsChanged(String),
+    TimeoutErrorChanged(String),
+    ToggleGenerateGuards(bool),
+    GuardErrorChanged(String),
+    ToggleWrapEngineImpl(bool),
+    EngineTypeChanged(String),
+    ToggleUseResultAlias(bool),
+    ResultAliasChanged(String),
+    DefaultReturnTypeChanged(String),
+    CbPoolFnChanged(String),
+    CbPoolVariantSelected(CbPoolVariant),
+    FieldAttributeTemplateChanged(String),
+    FeatureGateChanged(String),
+    UiScaleChanged(f32),
+    ToggleSyntaxHighlighting(bool),
+    VisibilitySelected(Visibility),
+    ResetVisibility,
+    ToggleFeatureGateRequestAndTest(bool),
+    PickProtoFile,
+    LoadProto(PathBuf),
+    ProtoMessageNameChanged(String),
     OperationTypeSelected(OperationType),
+    CallbackStyleSelected(CallbackStyle),
     TogglePassParamsToRequest(bool),
+    ToggleGenerateDefaultRequest(bool),
+    ToggleRequestStoresCallback(bool),
+    ToggleNativeAsyncTrait(bool),
+    PbBufferSelected(PbBuffer),
+    ToggleAsyncMode(bool),
+    ToggleUseBuilderPattern(bool),
+    ToggleFallibleBuilder(bool),
     ToggleGenerateDbFunctions(bool),
+    ChannelKindSelected(ChannelKind),
+    ToggleGenerateFfi(bool),
+    ToggleGenerateBench(bool),
+    BindingTargetSelected(BindingTarget),
+    TesterSelected(TesterChoice),
+    ToggleGracefulConnect(bool),
+    ToggleWrapTestModule(bool),
+    ToggleSharedTestSetup(bool),
+    TestSupportPathChanged(String),
+    TogglePreferOwnedStrings(bool),
+    ToggleSuppressWarnings(bool),
+    ToggleSupportsCancel(bool),
+    ToggleEmptyTestVecs(bool),
+    ToggleValidateSyntax(bool),
+    ToggleKeepNestedTypePaths(bool),
+    ToggleUseLogResultHelper(bool),
+    ToggleLogTraceParams(bool),
+    ToggleUseTracingInstrument(bool),
+    ToggleAnnotateCopies(bool),
+    ToggleCodegenMarkers(bool),
     GenerateCode,
+    GenerateBatch,
+    PickSpecCsv,
+    ImportSpecCsv(PathBuf),
+    RestoreLastGenerated,
+    ExportInputsJson,
+    ImportInputsJson,
+    RevealInFileManager,
+    PreviewWritePlan,
+    WriteFiles,
+    WritePlanAction(text_editor::Action),
+    TargetFileAction(text_editor::Action),
+    InsertSectionSelected(SectionId),
+    ToggleShowTemplates(bool),
+    TemplateSectionSelected(SectionId),
+    TemplateAction(text_editor::Action),
+    ResetTemplate,
+    InsertAtCursor,
+    ShowStats,
     ClearAll,
+    #[cfg(feature = "compile-check")]
+    CompileCheck,
+    #[cfg(feature = "compile-check")]
+    CompileCheckFinished(CompileCheckOutcome),
     CopyEngineSyncToClipboard,
     CopyEngineAsyncToClipboard,
     CopyModuleToClipboard,
     CopyRequestBuilderToClipboard,
     CopyRequestStructToClipboard,
+    CopyFromConversionToClipboard,
     CopyTestMethodToClipboard,
     CopyDbAgentToClipboard,
     CopyDbWorkerToClipboard,
     CopyDbSqliteToClipboard,
+    CopyFfiToClipboard,
+    CopyBindingToClipboard,
+    CopyBenchToClipboard,
     EngineSyncAction(text_editor::Action),
     EngineAsyncAction(text_editor::Action),
     ModuleAction(text_editor::Action),
     RequestBuilderAction(text_editor::Action),
     RequestStructAction(text_editor::Action),
+    FromConversionAction(text_editor::Action),
     TestMethodAction(text_editor::Action),
     DbAgentAction(text_editor::Action),
     DbWorkerAction(text_editor::Action),
     DbSqliteAction(text_editor::Action),
+    FfiAction(text_editor::Action),
+    BindingAction(text_editor::Action),
+    BenchAction(text_editor::Action),
+    BatchSignaturesAction(text_editor::Action),
+    SearchInOutput(String),
+    ToggleSection(SectionId),
+    RegenerateSection(SectionId),
+    MoveSectionUp(SectionId),
+    MoveSectionDown(SectionId),
 }
 
 impl Default for CodeGenerator {
     fn default() -> Self {
+        let config = Config::default();
         Self {
             project_path: "/Users/dxd/workspace/gitlab2/Rust/JQK-rust-universal-imsdk".to_string(),
-            function_name: String::new(),
-            function_params: String::new(),
-            callback_return_type: String::new(),
-            request_body_name: String::new(),
+            spec: GenSpec::default(),
+            raw_function_params: String::new(),
+            show_raw_params: false,
+            input_language: InputLanguage::Java,
+            params: Vec::new(),
+            java_callback_type: String::new(),
             request_file_name: String::new(),
-            operation_type: Some(OperationType::Network),
-            pass_params_to_request: false,
+            request_fields_editor: text_editor::Content::new(),
+            show_param_docs: false,
+            param_docs_editor: text_editor::Content::new(),
             generate_db_functions: false,
+            generate_ffi: false,
+            generate_bench: false,
+            validate_syntax: true,
+            annotate_copies: true,
+            codegen_markers: false,
+            section_order: config.section_order,
+            ui_scale: clamp_ui_scale(config.ui_scale),
+            syntax_highlighting: config.syntax_highlighting,
+            proto_path: None,
             engine_sync_content: text_editor::Content::new(),
             engine_async_content: text_editor::Content::new(),
             module_content: text_editor::Content::new(),
             request_builder_content: text_editor::Content::new(),
             request_struct_content: text_editor::Content::new(),
+            from_conversion_content: text_editor::Content::new(),
             test_method_content: text_editor::Content::new(),
             db_agent_content: text_editor::Content::new(),
             db_worker_content: text_editor::Content::new(),
             db_sqlite_content: text_editor::Content::new(),
+            ffi_content: text_editor::Content::new(),
+            binding_content: text_editor::Content::new(),
+            bench_content: text_editor::Content::new(),
+            batch_signatures: text_editor::Content::new(),
+            search_query: String::new(),
+            collapsed_sections: HashSet::new(),
+            section_stats: HashMap::new(),
+            operation_toggles: HashMap::new(),
+            syntax_errors: HashMap::new(),
+            last_written_section: None,
+            write_plan: None,
+            target_file: text_editor::Content::new(),
+            insert_section: SectionId::EngineAsync,
+            show_templates: false,
+            template_section: SectionId::EngineSync,
+            template_editor: text_editor::Content::new(),
+            section_templates: HashMap::new(),
+            known_types: config.known_types,
+            type_completion: None,
             status_message: String::new(),
+            pending_clear: None,
+            focused_input: None,
+            log: Vec::new(),
+            started_at: Instant::now(),
+            window_width: 1024.0,
+            session_generation_counts: HashMap::new(),
+            generation_totals: HashMap::new(),
+            data_dir: None,
+            config_dirty: false,
         }
     }
 }
 
 impl CodeGenerator {
-    fn update(&mut self, message: Message) {
-        match message {
-            Message::ProjectPathChanged(path) => {
-                self.project_path = path;
-            }
-            Message::FunctionNameChanged(name) => {
-                self.function_name = name;
-            }
-            Message::FunctionParamsChanged(params) => {
-                // 尝试将Java风格参数转换为Rust风格
-                // 如果输入看起来像Java风格（包含final或以逗号分隔的类型 变量名格式），则转换
-                if params.contains("final ")
-                    || params.split(',').any(|p| {
-                        let trimmed = p.trim();
-                        let parts: Vec<&str> = trimmed.split_whitespace().collect();
-                        // 如果格式是 "类型 变量名" 且不包含冒号，则认为是Java风格
-                        parts.len() >= 2 && !trimmed.contains(':')
-                    })
-                {
-                    self.function_params = convert_java_params_to_rust(&params);
-                } else {
-                    self.function_params = params;
+    // 启动时加载持久化配置
+    fn new() -> Self {
+        let mut generator = Self {
+            data_dir: app_data_dir(),
+            ..Self::default()
+        };
+        generator.apply_config(Config::load(generator.data_dir.as_deref()));
+        generator
+    }
+
+    fn apply_config(&mut self, config: Config) {
+        self.spec.trace_macro = config.trace_macro;
+        self.spec.trace_tag_prefix = config.trace_tag_prefix;
+        self.spec.db_log_macro = config.db_log_macro;
+        self.spec.conv_type_name = config.conv_type_name;
+        self.spec.db_conv_type_name = config.db_conv_type_name;
+        self.spec.timestamp_type = config.timestamp_type;
+        self.spec.test_value_table = config.test_value_table;
+        self.spec.enum_param_table = config.enum_param_table;
+        self.spec.error_code_table = config.error_code_table;
+        self.spec.cancel_token_import = config.cancel_token_import;
+        self.spec.test_support_path = config.test_support_path;
+        self.spec.timeout_error = config.timeout_error;
+        self.spec.cancel_error = config.cancel_error;
+        self.spec.guard_error = config.guard_error;
+        self.spec.engine_type = config.engine_type;
+        self.spec.result_alias = config.result_alias;
+        self.spec.default_return_type = config.default_return_type;
+        self.spec.cb_pool_fn = config.cb_pool_fn;
+        self.spec.cb_pool_variant = config.cb_pool_variant;
+        self.spec.field_attribute_template = config.field_attribute_template;
+        self.section_order = config.section_order;
+        self.section_templates = config.section_templates;
+        self.known_types = config.known_types;
+        self.template_editor = self.template_content(self.template_section);
+        self.spec.feature_gate = config.feature_gate;
+        self.ui_scale = clamp_ui_scale(config.ui_scale);
+        self.syntax_highlighting = config.syntax_highlighting;
+        self.spec.visibility = config.visibility;
+        self.generation_totals = config.generation_totals;
+    }
+
+    fn save_config(&mut self) {
+        let config = Config {
+            trace_macro: self.spec.trace_macro.clone(),
+            trace_tag_prefix: self.spec.trace_tag_prefix.clone(),
+            db_log_macro: self.spec.db_log_macro.clone(),
+            conv_type_name: self.spec.conv_type_name.clone(),
+            db_conv_type_name: self.spec.db_conv_type_name.clone(),
+            timestamp_type: self.spec.timestamp_type.clone(),
+            test_value_table: self.spec.test_value_table.clone(),
+            enum_param_table: self.spec.enum_param_table.clone(),
+            error_code_table: self.spec.error_code_table.clone(),
+            cancel_token_import: self.spec.cancel_token_import.clone(),
+            test_support_path: self.spec.test_support_path.clone(),
+            timeout_error: self.spec.timeout_error.clone(),
+            cancel_error: self.spec.cancel_error.clone(),
+            guard_error: self.spec.guard_error.clone(),
+            engine_type: self.spec.engine_type.clone(),
+            result_alias: self.spec.result_alias.clone(),
+            default_return_type: self.spec.default_return_type.clone(),
+            cb_pool_fn: self.spec.cb_pool_fn.clone(),
+            cb_pool_variant: self.spec.cb_pool_variant,
+            field_attribute_template: self.spec.field_attribute_template.clone(),
+            section_order: self.section_order.clone(),
+            section_templates: self.section_templates.clone(),
+            known_types: self.known_types.clone(),
+            feature_gate: self.spec.feature_gate.clone(),
+            ui_scale: self.ui_scale,
+            syntax_highlighting: self.syntax_highlighting,
+            visibility: self.spec.visibility,
+            generation_totals: self.generation_totals.clone(),
+        };
+        self.config_dirty = false;
+        if let Err(e) = config.save(self.data_dir.as_deref()) {
+            self.push_status(format!("错误：保存配置失败：{}", e));
+        }
+    }
+
+    // 保存输入框中还没保存的配置修改
+    fn flush_config(&mut self) {
+        if self.config_dirty {
+            self.save_config();
+        }
+    }
+
+    // 输出框对应的文件相对项目根目录的路径，测试方法没有固定的目标文件
+    fn section_relative_path(&self, id: SectionId) -> Option<PathBuf> {
+        let path = match id {
+            SectionId::EngineSync => "src/engine_sync.rs".to_string(),
+            SectionId::EngineAsync => "src/engine_async.rs".to_string(),
+            SectionId::Module => "src/modules/bugtags.rs".to_string(),
+            SectionId::RequestBuilder => "src/rmtp/request_builder.rs".to_string(),
+            SectionId::RequestStruct => {
+                let file_name = self.request_file_name.trim().trim_end_matches(".rs");
+                if !is_valid_module_name(file_name) {
+                    return None;
                 }
+                format!("src/rmtp/request/{}.rs", file_name)
             }
-            Message::CallbackReturnTypeChanged(return_type) => {
-                self.callback_return_type = return_type;
-            }
-            Message::RequestBodyNameChanged(name) => {
-                self.request_body_name = name.clone();
-                // 自动生成对应的 rust 文件名（snake_case）
-                self.request_file_name = pascal_to_snake_case(&name);
-            }
-            Message::RequestFileNameChanged(name) => {
-                self.request_file_name = name;
-            }
-            Message::OperationTypeSelected(op_type) => {
-                self.operation_type = Some(op_type);
-            }
-            Message::TogglePassParamsToRequest(enabled) => {
-                self.pass_params_to_request = enabled;
-            }
-            Message::ToggleGenerateDbFunctions(enabled) => {
-                self.generate_db_functions = enabled;
-            }
-            Message::GenerateCode => {
-                if self.function_name.is_empty() {
-                    self.status_message = "错误：函数名称不能为空！".to_string();
-                    return;
-                }
-                if self.function_params.is_empty() {
-                    self.status_message = "错误：函数参数不能为空！".to_string();
-                    return;
+            SectionId::FromConversion | SectionId::TestMethod | SectionId::Bench => return None,
+            SectionId::DbAgent => "src/db/db_agent.rs".to_string(),
+            SectionId::DbWorker => "src/db/db_worker.rs".to_string(),
+            SectionId::DbSqlite => "src/db/db_sqlite.rs".to_string(),
+            SectionId::Ffi => "src/ffi.rs".to_string(),
+            SectionId::Binding => match self.spec.binding_target {
+                BindingTarget::Jni => "src/jni.rs".to_string(),
+                BindingTarget::FlutterFfi => "src/api.rs".to_string(),
+                BindingTarget::None => return None,
+            },
+        };
+        Some(PathBuf::from(path))
+    }
+
+    // 打开所在文件夹的目标：优先最近写入的文件所在目录，否则按显示顺序找第一个已存在的目标目录
+    fn reveal_target_dir(&self) -> Option<PathBuf> {
+        self.last_written_section
+            .into_iter()
+            .chain(self.displayed_sections())
+            .filter_map(|id| self.section_target_path(id))
+            .filter_map(|path| path.parent().map(PathBuf::from))
+            .find(|dir| dir.is_dir())
+    }
+
+    // 写入一个输出框：文件不存在时新建；已存在时只替换同一函数的标记块，没有标记块时追加到末尾
+    fn write_section(&self, id: SectionId) -> std::io::Result<()> {
+        let Some(path) = self.section_target_path(id) else {
+            return Err(std::io::Error::other("没有目标文件"));
+        };
+        let block = self.marked_block(&self.output_content(id).text());
+        let content = match std::fs::read_to_string(&path) {
+            Ok(existing) => splice_codegen_block(&existing, &block, &self.codegen_key()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir)?;
                 }
+                block
+            }
+            Err(e) => return Err(e),
+        };
+        std::fs::write(&path, content)
+    }
 
-                let rust_function_name = java_to_rust_naming(&self.function_name);
+    // 标记中的函数名，同一文件中不同函数的生成块互不影响
+    fn codegen_key(&self) -> String {
+        to_snake_case(&self.spec.function_name)
+    }
 
-                // 生成各个部分的代码
-                let engine_sync_code = self.generate_engine_sync_function(&rust_function_name);
-                let engine_async_code = self.generate_engine_async_function(&rust_function_name);
-                let module_code = self.generate_module_function(&rust_function_name);
+    // 用 @generated 头和结束标记包住生成的代码
+    fn marked_block(&self, code: &str) -> String {
+        let key = self.codegen_key();
+        format!(
+            "{} ({}) — do not edit above the marker\n{}\n{} ({})\n",
+            CODEGEN_HEADER_PREFIX,
+            key,
+            code.trim_end(),
+            CODEGEN_END_MARKER_PREFIX,
+            key
+        )
+    }
 
-                // 生成 request_builder 代码（仅网络请求模式）
-                let request_builder_code = if self.operation_type == Some(OperationType::Network) {
-                    self.generate_request_builder_function(&rust_function_name)
-                } else {
-                    String::new()
-                };
+    // 复制到剪贴板的内容，开启标注时在开头加上目标文件路径注释
+    fn copy_text(&self, id: SectionId) -> String {
+        let code = self.output_content(id).text();
+        let code = if self.codegen_markers {
+            self.marked_block(&code)
+        } else {
+            code
+        };
+        match self.section_relative_path(id) {
+            Some(path) if self.annotate_copies => {
+                format!("// File: {}\n{}", path.display(), code)
+            }
+            _ => code,
+        }
+    }
 
-                let request_struct_code = if !self.request_body_name.is_empty() {
-                    self.generate_request_struct()
-                } else {
-                    String::new()
-                };
-                let test_method_code = self.generate_test_method(&rust_function_name);
+    // 有内容的输出框对应的写入目标，每行一个：[已存在]/[新建] 绝对路径 (输出框标题)
+    fn write_plan_summary(&self) -> String {
+        SectionId::ALL
+            .into_iter()
+            .filter(|&id| !self.output_content(id).text().trim().is_empty())
+            .map(|id| match self.section_target_path(id) {
+                Some(path) => {
+                    let state = if path.exists() {
+                        "[已存在]"
+                    } else {
+                        "[新建]"
+                    };
+                    format!("{} {} ({})", state, path.display(), id.title())
+                }
+                None => format!("[跳过] 没有目标文件 ({})", id.title()),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 
-                // 生成数据库函数代码
-                let (db_agent_code, db_worker_code, db_sqlite_code) = if self.generate_db_functions
-                {
-                    (
-                        self.generate_db_agent_function(&rust_function_name),
-                        self.generate_db_worker_function(&rust_function_name),
-                        self.generate_db_sqlite_function(&rust_function_name),
-                    )
-                } else {
-                    (String::new(), String::new(), String::new())
-                };
+    // 输出框对应文件的绝对路径 (项目路径 + 相对路径)
+    fn section_target_path(&self, id: SectionId) -> Option<PathBuf> {
+        let project_path = self.project_path.trim();
+        if project_path.is_empty() {
+            return None;
+        }
+        Some(PathBuf::from(project_path).join(self.section_relative_path(id)?))
+    }
 
-                self.engine_sync_content = text_editor::Content::with_text(&engine_sync_code);
-                self.engine_async_content = text_editor::Content::with_text(&engine_async_code);
-                self.module_content = text_editor::Content::with_text(&module_code);
-                self.request_builder_content =
-                    text_editor::Content::with_text(&request_builder_code);
-                self.request_struct_content = text_editor::Content::with_text(&request_struct_code);
-                self.test_method_content = text_editor::Content::with_text(&test_method_code);
-                self.db_agent_content = text_editor::Content::with_text(&db_agent_code);
-                self.db_worker_content = text_editor::Content::with_text(&db_worker_code);
-                self.db_sqlite_content = text_editor::Content::with_text(&db_sqlite_code);
+    // 开发用：把有内容的输出框写入临时 cargo 工程，返回写入的输出框数量；之后执行 cargo check 验证模板能否编译
+    #[cfg(feature = "compile-check")]
+    fn prepare_compile_check(&mut self) -> Option<usize> {
+        let sections = self.compile_check_sections();
+        if sections.is_empty() {
+            self.push_status("错误：没有可检查的代码，请先生成代码！");
+            return None;
+        }
 
-                self.status_message = "代码生成成功！".to_string();
+        let engine_type = self
+            .spec
+            .wrap_engine_impl
+            .then(|| self.spec.resolved_engine_type());
+        if let Err(e) =
+            write_compile_check_project(&compile_check_dir(), &sections, engine_type.as_deref())
+        {
+            self.push_status(format!("错误：创建编译检查工程失败：{}", e));
+            return None;
+        }
+        self.push_status(format!("正在编译检查 {} 个输出框...", sections.len()));
+        Some(sections.len())
+    }
+
+    #[cfg(feature = "compile-check")]
+    fn compile_check_sections(&self) -> Vec<(SectionId, String)> {
+        SectionId::ALL
+            .into_iter()
+            .map(|id| (id, self.output_content(id).text()))
+            .filter(|(_, code)| !code.trim().is_empty())
+            .collect()
+    }
+
+    #[cfg(feature = "compile-check")]
+    fn finish_compile_check(&mut self, outcome: CompileCheckOutcome) {
+        match outcome {
+            CompileCheckOutcome::Passed(sections) => {
+                self.push_status(format!("编译检查成功：{} 个输出框", sections));
             }
-            Message::ClearAll => {
-                // 不清空项目路径，只清空其他输入框
-                self.function_name.clear();
-                self.function_params.clear();
-                self.callback_return_type.clear();
-                self.request_body_name.clear();
-                self.request_file_name.clear();
-                self.operation_type = Some(OperationType::Network);
-                self.engine_sync_content = text_editor::Content::new();
-                self.engine_async_content = text_editor::Content::new();
-                self.module_content = text_editor::Content::new();
-                self.request_builder_content = text_editor::Content::new();
-                self.request_struct_content = text_editor::Content::new();
-                self.test_method_content = text_editor::Content::new();
-                self.db_agent_content = text_editor::Content::new();
-                self.db_worker_content = text_editor::Content::new();
-                self.db_sqlite_content = text_editor::Content::new();
-                self.status_message = "已清空所有输入！".to_string();
+            CompileCheckOutcome::NotRun(error) => {
+                self.push_status(format!("错误：无法运行 cargo check：{}", error));
             }
-            Message::CopyEngineSyncToClipboard => {
-                if let Ok(mut clipboard) = Clipboard::new() {
-                    if clipboard.set_text(&self.engine_sync_content.text()).is_ok() {
-                        self.status_message = "engine_sync.rs 已复制到剪贴板！".to_string();
-                    } else {
-                        self.status_message = "复制失败！".to_string();
-                    }
+            CompileCheckOutcome::Failed(stderr) => {
+                let errors: Vec<&str> = stderr
+                    .lines()
+                    .filter(|line| line.contains("error"))
+                    .collect();
+                // 逐条写入日志，最后一条作为当前状态
+                for line in errors.iter().take(20) {
+                    self.push_status(format!("  {}", line));
                 }
+                self.push_status(format!(
+                    "错误：编译检查失败，共 {} 条错误，工程位于 {}",
+                    errors.len(),
+                    compile_check_dir().display()
+                ));
             }
-            Message::CopyEngineAsyncToClipboard => {
-                if let Ok(mut clipboard) = Clipboard::new() {
-                    if clipboard
-                        .set_text(&self.engine_async_content.text())
-                        .is_ok()
-                    {
-                        self.status_message = "engine_async.rs 已复制到剪贴板！".to_string();
-                    } else {
-                        self.status_message = "复制失败！".to_string();
-                    }
+        }
+    }
+
+    // 生成前检查函数名和括号，有问题时显示错误
+    fn check_generation_inputs(&mut self) -> bool {
+        if self.spec.function_name.is_empty() {
+            self.push_status("错误：函数名称不能为空！");
+            return false;
+        }
+        self.check_brackets("函数参数", &self.spec.function_params.clone())
+            && self.check_brackets(
+                "Callback 返回值类型",
+                &self.spec.callback_return_type.clone(),
+            )
+    }
+
+    // 按当前输入重新生成一个输出框的代码，不显示的输出框清空
+    fn regenerate_section(&mut self, id: SectionId) {
+        let code = if self.section_visible(id) {
+            self.wrap_engine_section(id, &self.render_section(id, &self.spec))
+        } else {
+            String::new()
+        };
+        *self.output_content_mut(id) = text_editor::Content::with_text(&code);
+    }
+
+    // 检查输入中的括号是否匹配，不匹配时显示错误；匹配时清除之前的括号错误提示
+    fn check_brackets(&mut self, label: &str, value: &str) -> bool {
+        if brackets_balanced(value) {
+            if self.status_message.contains("括号不匹配") {
+                self.status_message.clear();
+            }
+            true
+        } else {
+            self.push_status(format!("错误：{}中的尖括号或圆括号不匹配！", label));
+            false
+        }
+    }
+
+    // rust 文件名同时是模块名，无效时提示修正后的名称，改为有效名称后清除提示
+    fn check_request_file_name(&mut self) {
+        let file_name = self.request_file_name.trim().trim_end_matches(".rs");
+        if file_name.is_empty() || is_valid_module_name(file_name) {
+            if self.status_message.contains("不是有效的模块名") {
+                self.status_message.clear();
+            }
+            return;
+        }
+        let message = match sanitize_module_name(file_name) {
+            suggestion if suggestion.is_empty() => {
+                format!("错误：rust 文件名 \"{}\" 不是有效的模块名", file_name)
+            }
+            suggestion => format!(
+                "错误：rust 文件名 \"{}\" 不是有效的模块名，建议改为 {}",
+                file_name, suggestion
+            ),
+        };
+        self.push_status(message);
+    }
+
+    // 按当前操作类型累加本次会话和累计的生成数量，累计数量保存到配置
+    fn record_generation(&mut self, count: u64) {
+        self.record_generation_for(self.spec.operation_type.clone(), count);
+    }
+
+    fn record_generation_for(&mut self, operation_type: Option<OperationType>, count: u64) {
+        let Some(operation_type) = operation_type else {
+            return;
+        };
+        *self
+            .session_generation_counts
+            .entry(operation_type.clone())
+            .or_default() += count;
+        *self.generation_totals.entry(operation_type).or_default() += count;
+        self.save_config();
+    }
+
+    // 设置最新状态并追加到历史日志
+    fn push_status(&mut self, msg: impl Into<String>) {
+        let msg = msg.into();
+        self.log.push((Instant::now(), msg.clone()));
+        if self.log.len() > STATUS_LOG_LIMIT {
+            let overflow = self.log.len() - STATUS_LOG_LIMIT;
+            self.log.drain(..overflow);
+        }
+        self.status_message = msg;
+    }
+
+    // 复制到剪贴板，失败时显示具体原因；剪贴板不可用时 (例如 Linux 下没有剪贴板服务) 改为写入临时文件
+    fn copy_to_clipboard(&mut self, label: &str, text: &str) {
+        let error = match Clipboard::new() {
+            Ok(mut clipboard) => match clipboard.set_text(text) {
+                Ok(()) => {
+                    self.push_status(format!("{} 已复制到剪贴板！", label));
+                    return;
                 }
+                Err(e) => e,
+            },
+            Err(e) => e,
+        };
+
+        let path = std::env::temp_dir().join("auto_universal_sdk_clipboard.txt");
+        match std::fs::write(&path, text) {
+            Ok(()) => self.push_status(format!(
+                "错误：{} 复制失败：{}，内容已写入 {}",
+                label,
+                error,
+                path.display()
+            )),
+            Err(write_error) => self.push_status(format!(
+                "错误：{} 复制失败：{}；写入临时文件也失败：{}",
+                label, error, write_error
+            )),
+        }
+    }
+
+    // 生成结束：检查语法后设置状态并缓存输出
+    fn finish_generation(&mut self, success_message: String) {
+        self.refresh_section_stats();
+        let invalid_count = self.validate_generated_syntax();
+        self.push_status(if invalid_count == 0 {
+            success_message
+        } else {
+            format!(
+                "错误：代码已生成，但有 {} 个输出框的代码无法解析，请检查模板！",
+                invalid_count
+            )
+        });
+        self.save_last_generated();
+    }
+
+    // 用 syn 解析每个有内容的输出框，记录错误位置，返回无法解析的输出框数量
+    fn validate_generated_syntax(&mut self) -> usize {
+        self.syntax_errors.clear();
+        if !self.validate_syntax {
+            return 0;
+        }
+
+        for id in SectionId::ALL {
+            let code = self.output_content(id).text();
+            if code.trim().is_empty() {
+                continue;
             }
-            Message::CopyModuleToClipboard => {
-                if let Ok(mut clipboard) = Clipboard::new() {
-                    if clipboard.set_text(&self.module_content.text()).is_ok() {
-                        self.status_message = "module 文件已复制到剪贴板！".to_string();
-                    } else {
-                        self.status_message = "复制失败！".to_string();
-                    }
+            if let Err(e) = syn::parse_file(&code) {
+                let start = e.span().start();
+                self.syntax_errors.insert(
+                    id,
+                    format!("第 {} 行第 {} 列：{}", start.line, start.column + 1, e),
+                );
+            }
+        }
+        self.syntax_errors.len()
+    }
+
+    // 缓存本次生成的所有输出，失败时只提示不影响生成结果
+    fn save_last_generated(&mut self) {
+        let last_generated = LastGenerated {
+            sections: SectionId::ALL
+                .into_iter()
+                .map(|id| (id, self.output_content(id).text()))
+                .collect(),
+        };
+        if let Err(e) = last_generated.save(self.data_dir.as_deref()) {
+            self.push_status(format!("错误：缓存生成结果失败：{}", e));
+        }
+    }
+
+    fn inputs_snapshot(&self) -> InputsSnapshot {
+        InputsSnapshot {
+            version: InputsSnapshot::VERSION,
+            project_path: self.project_path.clone(),
+            function_name: self.spec.function_name.clone(),
+            function_params: self.spec.function_params.clone(),
+            input_language: self.input_language,
+            callback_return_type: self.spec.callback_return_type.clone(),
+            expected_return: self.spec.expected_return.clone(),
+            java_callback_type: self.java_callback_type.clone(),
+            request_body_name: self.spec.request_body_name.clone(),
+            request_file_name: self.request_file_name.clone(),
+            rmtp_method: self.spec.rmtp_method.clone(),
+            document_wire: self.spec.document_wire,
+            request_fields: self.spec.request_fields.clone(),
+            param_docs: self.spec.param_docs.clone(),
+            operation_type: self.spec.operation_type.clone(),
+            visibility: self.spec.visibility,
+            callback_style: self.spec.callback_style,
+            pass_params_to_request: self.spec.pass_params_to_request,
+            generate_default_request: self.spec.generate_default_request,
+            request_stores_callback: self.spec.request_stores_callback,
+            native_async_trait: self.spec.native_async_trait,
+            pb_buffer: self.spec.pb_buffer,
+            async_mode: self.spec.async_mode,
+            use_builder_pattern: self.spec.use_builder_pattern,
+            fallible_builder: self.spec.fallible_builder,
+            generate_db_functions: self.generate_db_functions,
+            generate_ffi: self.generate_ffi,
+            generate_bench: self.generate_bench,
+            binding_target: self.spec.binding_target,
+            wrap_test_module: self.spec.wrap_test_module,
+            shared_test_setup: self.spec.shared_test_setup,
+            tester: self.spec.tester,
+            graceful_connect: self.spec.graceful_connect,
+            channel_kind: self.spec.channel_kind,
+            prefer_owned_strings: self.spec.prefer_owned_strings,
+            suppress_warnings: self.spec.suppress_warnings,
+            supports_cancel: self.spec.supports_cancel,
+            timeout_ms: self.spec.timeout_ms,
+            empty_test_vecs: self.spec.empty_test_vecs,
+            validate_syntax: self.validate_syntax,
+            keep_nested_type_paths: self.spec.keep_nested_type_paths,
+            use_log_result_helper: self.spec.use_log_result_helper,
+            log_trace_params: self.spec.log_trace_params,
+            use_tracing_instrument: self.spec.use_tracing_instrument,
+            feature_gate_request_and_test: self.spec.feature_gate_request_and_test,
+            trace_macro: self.spec.trace_macro.clone(),
+            trace_tag_prefix: self.spec.trace_tag_prefix.clone(),
+            db_log_macro: self.spec.db_log_macro.clone(),
+            conv_type_name: self.spec.conv_type_name.clone(),
+            db_conv_type_name: self.spec.db_conv_type_name.clone(),
+            timestamp_type: self.spec.timestamp_type.clone(),
+            test_value_table: self.spec.test_value_table.clone(),
+            enum_param_table: self.spec.enum_param_table.clone(),
+            error_code_table: self.spec.error_code_table.clone(),
+            cancel_token_import: self.spec.cancel_token_import.clone(),
+            test_support_path: self.spec.test_support_path.clone(),
+            timeout_error: self.spec.timeout_error.clone(),
+            cancel_error: self.spec.cancel_error.clone(),
+            generate_guards: self.spec.generate_guards,
+            guard_error: self.spec.guard_error.clone(),
+            wrap_engine_impl: self.spec.wrap_engine_impl,
+            engine_type: self.spec.engine_type.clone(),
+            use_result_alias: self.spec.use_result_alias,
+            result_alias: self.spec.result_alias.clone(),
+            default_return_type: self.spec.default_return_type.clone(),
+            cb_pool_fn: self.spec.cb_pool_fn.clone(),
+            cb_pool_variant: self.spec.cb_pool_variant,
+            field_attribute_template: self.spec.field_attribute_template.clone(),
+            feature_gate: self.spec.feature_gate.clone(),
+            proto_message_name: self.spec.proto_message_name.clone(),
+        }
+    }
+
+    fn apply_inputs_snapshot(&mut self, snapshot: InputsSnapshot) {
+        self.project_path = snapshot.project_path;
+        self.spec.function_name = snapshot.function_name;
+        self.params = params_to_rows(&snapshot.function_params);
+        self.raw_function_params = snapshot.function_params.clone();
+        self.spec.function_params = snapshot.function_params;
+        self.input_language = snapshot.input_language;
+        self.spec.callback_return_type = snapshot.callback_return_type;
+        self.spec.expected_return = snapshot.expected_return;
+        self.java_callback_type = snapshot.java_callback_type;
+        self.spec.request_body_name = snapshot.request_body_name;
+        self.request_file_name = snapshot.request_file_name;
+        self.spec.rmtp_method = snapshot.rmtp_method;
+        self.spec.document_wire = snapshot.document_wire;
+        self.request_fields_editor = text_editor::Content::with_text(&snapshot.request_fields);
+        self.spec.request_fields = snapshot.request_fields;
+        self.param_docs_editor = text_editor::Content::with_text(&snapshot.param_docs);
+        self.spec.param_docs = snapshot.param_docs;
+        self.spec.operation_type = snapshot.operation_type;
+        self.spec.visibility = snapshot.visibility;
+        self.spec.callback_style = snapshot.callback_style;
+        self.spec.pass_params_to_request = snapshot.pass_params_to_request;
+        self.spec.generate_default_request = snapshot.generate_default_request;
+        self.spec.request_stores_callback = snapshot.request_stores_callback;
+        self.spec.native_async_trait = snapshot.native_async_trait;
+        self.spec.pb_buffer = snapshot.pb_buffer;
+        self.spec.async_mode = snapshot.async_mode;
+        self.spec.use_builder_pattern = snapshot.use_builder_pattern;
+        self.spec.fallible_builder = snapshot.fallible_builder;
+        self.generate_db_functions = snapshot.generate_db_functions;
+        self.generate_ffi = snapshot.generate_ffi;
+        self.generate_bench = snapshot.generate_bench;
+        self.spec.binding_target = snapshot.binding_target;
+        self.spec.wrap_test_module = snapshot.wrap_test_module;
+        self.spec.shared_test_setup = snapshot.shared_test_setup;
+        self.spec.tester = snapshot.tester;
+        self.spec.graceful_connect = snapshot.graceful_connect;
+        self.spec.channel_kind = snapshot.channel_kind;
+        self.spec.prefer_owned_strings = snapshot.prefer_owned_strings;
+        self.spec.suppress_warnings = snapshot.suppress_warnings;
+        self.spec.supports_cancel = snapshot.supports_cancel;
+        self.spec.timeout_ms = snapshot.timeout_ms;
+        self.spec.empty_test_vecs = snapshot.empty_test_vecs;
+        self.validate_syntax = snapshot.validate_syntax;
+        self.spec.keep_nested_type_paths = snapshot.keep_nested_type_paths;
+        self.spec.use_log_result_helper = snapshot.use_log_result_helper;
+        self.spec.log_trace_params = snapshot.log_trace_params;
+        self.spec.use_tracing_instrument = snapshot.use_tracing_instrument;
+        self.spec.feature_gate_request_and_test = snapshot.feature_gate_request_and_test;
+        self.spec.trace_macro = snapshot.trace_macro;
+        self.spec.trace_tag_prefix = snapshot.trace_tag_prefix;
+        self.spec.db_log_macro = snapshot.db_log_macro;
+        self.spec.conv_type_name = snapshot.conv_type_name;
+        self.spec.db_conv_type_name = snapshot.db_conv_type_name;
+        self.spec.timestamp_type = snapshot.timestamp_type;
+        self.spec.test_value_table = snapshot.test_value_table;
+        self.spec.enum_param_table = snapshot.enum_param_table;
+        self.spec.error_code_table = snapshot.error_code_table;
+        self.spec.cancel_token_import = snapshot.cancel_token_import;
+        self.spec.test_support_path = snapshot.test_support_path;
+        self.spec.timeout_error = snapshot.timeout_error;
+        self.spec.cancel_error = snapshot.cancel_error;
+        self.spec.generate_guards = snapshot.generate_guards;
+        self.spec.guard_error = snapshot.guard_error;
+        self.spec.wrap_engine_impl = snapshot.wrap_engine_impl;
+        self.spec.engine_type = snapshot.engine_type;
+        self.spec.use_result_alias = snapshot.use_result_alias;
+        self.spec.result_alias = snapshot.result_alias;
+        self.spec.default_return_type = snapshot.default_return_type;
+        self.spec.cb_pool_fn = snapshot.cb_pool_fn;
+        self.spec.cb_pool_variant = snapshot.cb_pool_variant;
+        self.spec.field_attribute_template = snapshot.field_attribute_template;
+        self.spec.feature_gate = snapshot.feature_gate;
+        self.spec.proto_message_name = snapshot.proto_message_name;
+    }
+
+    // 切换操作类型：记下旧类型的开关状态，恢复新类型上次的状态
+    fn switch_operation_type(&mut self, op_type: OperationType) {
+        if let Some(previous) = self.spec.operation_type.take() {
+            self.operation_toggles.insert(
+                previous,
+                OperationToggles {
+                    generate_db_functions: self.generate_db_functions,
+                    pass_params_to_request: self.spec.pass_params_to_request,
+                },
+            );
+        }
+        if let Some(toggles) = self.operation_toggles.get(&op_type) {
+            self.generate_db_functions = toggles.generate_db_functions;
+            self.spec.pass_params_to_request = toggles.pass_params_to_request;
+        }
+        self.spec.operation_type = Some(op_type);
+    }
+
+    // 表格编辑后的参数直接就是 Rust 风格，原始输入也同步为拼接结果
+    fn sync_params_from_rows(&mut self) {
+        self.spec.function_params = rows_to_params(&self.params);
+        self.raw_function_params = self.spec.function_params.clone();
+    }
+
+    // 应用入口：焦点切换需要返回 Task，其余消息交给 update 处理
+    fn handle(&mut self, message: Message) -> Task<Message> {
+        // cargo check 耗时较长，在后台线程中执行，不阻塞界面
+        #[cfg(feature = "compile-check")]
+        if matches!(message, Message::CompileCheck) {
+            return match self.prepare_compile_check() {
+                Some(sections) => Task::perform(
+                    run_cargo_check_in_background(compile_check_dir(), sections),
+                    Message::CompileCheckFinished,
+                ),
+                None => Task::none(),
+            };
+        }
+        let refocus = matches!(message, Message::FocusNext | Message::FocusPrevious);
+        let exit = matches!(message, Message::CloseRequested);
+        self.update(message);
+        if exit {
+            return iced::exit();
+        }
+        match self.focused_input {
+            Some(field) if refocus => text_input::focus(field.id()),
+            _ => Task::none(),
+        }
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch([
+            keyboard::on_key_press(|key, modifiers| match key {
+                keyboard::Key::Named(keyboard::key::Named::Tab) if modifiers.shift() => {
+                    Some(Message::FocusPrevious)
                 }
+                keyboard::Key::Named(keyboard::key::Named::Tab) => Some(Message::FocusNext),
+                _ => None,
+            }),
+            window::resize_events().map(|(_id, size)| Message::WindowResized(size)),
+            window::close_requests().map(|_id| Message::CloseRequested),
+        ])
+    }
+
+    // 可用宽度足够时左右分栏，Resized 报告的已是缩放后的逻辑宽度
+    fn uses_split_layout(&self) -> bool {
+        self.window_width >= SPLIT_LAYOUT_MIN_WIDTH
+    }
+
+    fn update(&mut self, message: Message) {
+        let pending_clear = self.pending_clear.take();
+        if let Some(field) = FocusField::edited_by(&message) {
+            self.focused_input = Some(field);
+        }
+        match message {
+            Message::FocusNext => {
+                self.focused_input = Some(FocusField::step(self.focused_input, true));
             }
-            Message::CopyRequestBuilderToClipboard => {
-                if let Ok(mut clipboard) = Clipboard::new() {
-                    if clipboard
-                        .set_text(&self.request_builder_content.text())
-                        .is_ok()
-                    {
-                        self.status_message = "request_builder 文件已复制到剪贴板！".to_string();
-                    } else {
-                        self.status_message = "复制失败！".to_string();
+            Message::FocusPrevious => {
+                self.focused_input = Some(FocusField::step(self.focused_input, false));
+            }
+            // 退出前保存输入框中还没保存的配置修改，退出由 handle 完成
+            Message::CloseRequested => self.flush_config(),
+            Message::WindowResized(size) => {
+                self.window_width = size.width;
+                // 调整窗口大小不算其他操作，不取消待确认的清空
+                self.pending_clear = pending_clear;
+            }
+            Message::ProjectPathChanged(path) => {
+                self.project_path = path;
+            }
+            Message::FunctionNameChanged(name) => {
+                self.spec.function_name = name;
+            }
+            Message::FunctionParamsChanged(params) => {
+                // 保留转换前的原始输入，便于回看和重新编辑 Java 参数
+                self.raw_function_params = params.clone();
+                self.type_completion = Some(TypeCompletionTarget::Params);
+
+                // 括号不匹配时保留原始输入，不做转换
+                if !self.check_brackets("函数参数", &params) {
+                    self.params = params_to_rows(&params);
+                    self.spec.function_params = params;
+                    return;
+                }
+
+                // 按输入语言将参数转换为Rust风格
+                self.spec.function_params = match self.input_language {
+                    InputLanguage::Java if looks_like_java_params(&params) => {
+                        convert_java_params_to_rust(&params, &self.java_type_options())
                     }
+                    InputLanguage::Kotlin => {
+                        convert_kotlin_params_to_rust(&params, &self.java_type_options())
+                    }
+                    InputLanguage::Java | InputLanguage::Rust => params,
+                };
+                self.params = params_to_rows(&self.spec.function_params);
+            }
+            Message::ParamRowNameChanged(index, name) => {
+                if let Some(param) = self.params.get_mut(index) {
+                    param.name = name;
+                    self.sync_params_from_rows();
                 }
             }
-            Message::CopyRequestStructToClipboard => {
-                if let Ok(mut clipboard) = Clipboard::new() {
-                    if clipboard
-                        .set_text(&self.request_struct_content.text())
-                        .is_ok()
-                    {
-                        self.status_message = "请求体结构已复制到剪贴板！".to_string();
-                    } else {
-                        self.status_message = "复制失败！".to_string();
-                    }
+            Message::ParamRowTypeChanged(index, param_type) => {
+                self.type_completion = Some(TypeCompletionTarget::Row(index));
+                if let Some(param) = self.params.get_mut(index) {
+                    param.param_type = param_type;
+                    self.sync_params_from_rows();
                 }
             }
-            Message::EngineSyncAction(action) => {
-                self.engine_sync_content.perform(action);
+            Message::ToggleShowRawParams(enabled) => {
+                self.show_raw_params = enabled;
             }
-            Message::EngineAsyncAction(action) => {
-                self.engine_async_content.perform(action);
+            Message::InputLanguageSelected(language) => {
+                self.input_language = language;
+                // 切换语言后按新语言重新转换原始输入
+                if !self.raw_function_params.is_empty() {
+                    self.update(Message::FunctionParamsChanged(
+                        self.raw_function_params.clone(),
+                    ));
+                }
             }
-            Message::ModuleAction(action) => {
-                self.module_content.perform(action);
+            Message::ParamRowAdded => {
+                self.params.push(ParamRow::default());
             }
-            Message::RequestBuilderAction(action) => {
-                self.request_builder_content.perform(action);
+            Message::ParamRowRemoved(index) => {
+                if index < self.params.len() {
+                    self.params.remove(index);
+                    self.sync_params_from_rows();
+                }
+                self.type_completion = None;
             }
-            Message::RequestStructAction(action) => {
-                self.request_struct_content.perform(action);
+            Message::KnownTypesChanged(known_types) => {
+                self.known_types = known_types;
+                self.config_dirty = true;
             }
-            Message::CopyTestMethodToClipboard => {
-                if let Ok(mut clipboard) = Clipboard::new() {
-                    if clipboard.set_text(&self.test_method_content.text()).is_ok() {
-                        self.status_message = "测试方法已复制到剪贴板！".to_string();
-                    } else {
-                        self.status_message = "复制失败！".to_string();
+            Message::TypeSuggestionSelected(type_name) => {
+                let Some((target, text)) = self.type_completion_text() else {
+                    return;
+                };
+                // 用选中的类型替换正在输入的类型片段
+                let partial = trailing_type_token(text);
+                let completed = format!("{}{}", &text[..text.len() - partial.len()], type_name);
+                match target {
+                    TypeCompletionTarget::Params => {
+                        self.update(Message::FunctionParamsChanged(completed))
+                    }
+                    TypeCompletionTarget::Row(index) => {
+                        self.update(Message::ParamRowTypeChanged(index, completed))
                     }
                 }
             }
-            Message::TestMethodAction(action) => {
-                self.test_method_content.perform(action);
+            Message::ExpectedReturnChanged(expected) => {
+                self.spec.expected_return = expected;
             }
-            Message::CopyDbAgentToClipboard => {
-                if let Ok(mut clipboard) = Clipboard::new() {
-                    if clipboard.set_text(&self.db_agent_content.text()).is_ok() {
-                        self.status_message = "db_agent.rs 已复制到剪贴板！".to_string();
-                    } else {
-                        self.status_message = "复制失败！".to_string();
-                    }
+            Message::CallbackReturnTypeChanged(return_type) => {
+                self.check_brackets("Callback 返回值类型", &return_type);
+                self.spec.callback_return_type = return_type;
+            }
+            Message::JavaCallbackTypeChanged(callback_type) => {
+                // 能解析出泛型参数时自动填充 Callback 返回值类型
+                if let Some(return_type) =
+                    parse_java_callback_return_type(&callback_type, &self.java_type_options())
+                {
+                    self.spec.callback_return_type = return_type;
                 }
+                self.java_callback_type = callback_type;
             }
-            Message::CopyDbWorkerToClipboard => {
-                if let Ok(mut clipboard) = Clipboard::new() {
-                    if clipboard.set_text(&self.db_worker_content.text()).is_ok() {
-                        self.status_message = "db_worker.rs 已复制到剪贴板！".to_string();
-                    } else {
-                        self.status_message = "复制失败！".to_string();
-                    }
+            Message::RequestBodyNameChanged(name) => {
+                self.spec.request_body_name = name.clone();
+                // 自动生成对应的 rust 文件名（snake_case）
+                self.request_file_name = to_snake_case(&name);
+                self.check_request_file_name();
+            }
+            Message::RequestFileNameChanged(name) => {
+                self.request_file_name = name;
+                self.check_request_file_name();
+            }
+            Message::RmtpMethodChanged(method) => {
+                self.spec.rmtp_method = method;
+            }
+            Message::ToggleDocumentWire(enabled) => {
+                self.spec.document_wire = enabled;
+            }
+            Message::RequestFieldsAction(action) => {
+                self.request_fields_editor.perform(action);
+                self.spec.request_fields = self.request_fields_editor.text();
+            }
+            Message::ToggleShowParamDocs(enabled) => {
+                self.show_param_docs = enabled;
+            }
+            Message::ParamDocsAction(action) => {
+                self.param_docs_editor.perform(action);
+                self.spec.param_docs = self.param_docs_editor.text();
+            }
+            Message::TraceMacroChanged(name) => {
+                self.spec.trace_macro = name;
+                self.config_dirty = true;
+            }
+            Message::TraceTagPrefixChanged(prefix) => {
+                self.spec.trace_tag_prefix = prefix;
+                self.config_dirty = true;
+            }
+            Message::DbLogMacroChanged(db_log_macro) => {
+                self.spec.db_log_macro = db_log_macro;
+                self.config_dirty = true;
+            }
+            Message::ConvTypeNameChanged(name) => {
+                self.spec.conv_type_name = name;
+                self.config_dirty = true;
+            }
+            Message::DbConvTypeNameChanged(name) => {
+                self.spec.db_conv_type_name = name;
+                self.config_dirty = true;
+            }
+            Message::TimestampTypeChanged(timestamp_type) => {
+                self.spec.timestamp_type = timestamp_type;
+                self.config_dirty = true;
+            }
+            Message::TestValueTableChanged(table) => {
+                self.spec.test_value_table = table;
+                self.config_dirty = true;
+            }
+            Message::EnumParamTableChanged(table) => {
+                self.spec.enum_param_table = table;
+                self.config_dirty = true;
+            }
+            Message::ErrorCodeTableChanged(table) => {
+                self.spec.error_code_table = table;
+                self.config_dirty = true;
+            }
+            Message::CancelTokenImportChanged(import) => {
+                self.spec.cancel_token_import = import;
+                self.config_dirty = true;
+            }
+            Message::CancelErrorChanged(cancel_error) => {
+                self.spec.cancel_error = cancel_error;
+                self.config_dirty = true;
+            }
+            // 只接受数字，清空时不加超时
+            Message::TimeoutMsChanged(value) => {
+                let value = value.trim();
+                if value.is_empty() {
+                    self.spec.timeout_ms = None;
+                } else if let Ok(ms) = value.parse() {
+                    self.spec.timeout_ms = Some(ms);
                 }
             }
-            Message::CopyDbSqliteToClipboard => {
-                if let Ok(mut clipboard) = Clipboard::new() {
-                    if clipboard.set_text(&self.db_sqlite_content.text()).is_ok() {
-                        self.status_message = "db_sqlite.rs 已复制到剪贴板！".to_string();
-                    } else {
-                        self.status_message = "复制失败！".to_string();
-                    }
+            Message::TimeoutErrorChanged(timeout_error) => {
+                self.spec.timeout_error = timeout_error;
+                self.config_dirty = true;
+            }
+            Message::ToggleGenerateGuards(enabled) => {
+                self.spec.generate_guards = enabled;
+            }
+            Message::GuardErrorChanged(guard_error) => {
+                self.spec.guard_error = guard_error;
+                self.config_dirty = true;
+            }
+            Message::ToggleWrapEngineImpl(enabled) => {
+                self.spec.wrap_engine_impl = enabled;
+            }
+            Message::EngineTypeChanged(engine_type) => {
+                self.spec.engine_type = engine_type;
+                self.config_dirty = true;
+            }
+            Message::ToggleUseResultAlias(enabled) => {
+                self.spec.use_result_alias = enabled;
+            }
+            Message::ResultAliasChanged(alias) => {
+                self.spec.result_alias = alias;
+                self.config_dirty = true;
+            }
+            Message::DefaultReturnTypeChanged(return_type) => {
+                self.spec.default_return_type = return_type;
+                self.config_dirty = true;
+            }
+            Message::CbPoolFnChanged(name) => {
+                self.spec.cb_pool_fn = name;
+                self.config_dirty = true;
+            }
+            Message::CbPoolVariantSelected(variant) => {
+                self.spec.cb_pool_variant = variant;
+                self.save_config();
+            }
+            Message::FieldAttributeTemplateChanged(template) => {
+                self.spec.field_attribute_template = template;
+                self.config_dirty = true;
+            }
+            Message::UiScaleChanged(scale) => {
+                self.ui_scale = clamp_ui_scale(scale);
+                self.config_dirty = true;
+            }
+            Message::ToggleSyntaxHighlighting(enabled) => {
+                self.syntax_highlighting = enabled;
+                self.save_config();
+            }
+            Message::VisibilitySelected(visibility) => {
+                self.spec.visibility = Some(visibility);
+                self.save_config();
+            }
+            Message::ResetVisibility => {
+                self.spec.visibility = None;
+                self.save_config();
+            }
+            Message::FeatureGateChanged(feature) => {
+                self.spec.feature_gate = feature;
+                self.config_dirty = true;
+            }
+            Message::ToggleFeatureGateRequestAndTest(enabled) => {
+                self.spec.feature_gate_request_and_test = enabled;
+            }
+            Message::PickProtoFile => {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("proto", &["proto"])
+                    .pick_file()
+                {
+                    self.update(Message::LoadProto(path));
                 }
             }
-            Message::DbAgentAction(action) => {
-                self.db_agent_content.perform(action);
+            Message::LoadProto(path) => match std::fs::read_to_string(&path) {
+                Ok(source) => {
+                    self.spec.proto_source = source;
+                    self.proto_path = Some(path);
+                    self.push_status(format!(
+                        "已加载 proto 文件，消息 {} 共 {} 个字段",
+                        self.spec.resolved_proto_message_name(),
+                        self.spec.proto_fields().len()
+                    ));
+                }
+                Err(e) => {
+                    self.push_status(format!(
+                        "错误：读取 proto 文件 {} 失败：{}",
+                        path.display(),
+                        e
+                    ));
+                }
+            },
+            Message::ProtoMessageNameChanged(name) => {
+                self.spec.proto_message_name = name;
             }
-            Message::DbWorkerAction(action) => {
-                self.db_worker_content.perform(action);
+            Message::OperationTypeSelected(op_type) => {
+                self.switch_operation_type(op_type);
             }
-            Message::DbSqliteAction(action) => {
-                self.db_sqlite_content.perform(action);
+            Message::CallbackStyleSelected(style) => {
+                self.spec.callback_style = style;
             }
-        }
-    }
+            Message::TogglePassParamsToRequest(enabled) => {
+                self.spec.pass_params_to_request = enabled;
+            }
+            Message::ToggleGenerateDefaultRequest(enabled) => {
+                self.spec.generate_default_request = enabled;
+            }
+            Message::ToggleRequestStoresCallback(enabled) => {
+                self.spec.request_stores_callback = enabled;
+            }
+            Message::ToggleNativeAsyncTrait(enabled) => {
+                self.spec.native_async_trait = enabled;
+            }
+            Message::PbBufferSelected(pb_buffer) => {
+                self.spec.pb_buffer = pb_buffer;
+            }
+            Message::ToggleAsyncMode(enabled) => {
+                self.spec.async_mode = enabled;
+            }
+            Message::ToggleUseBuilderPattern(enabled) => {
+                self.spec.use_builder_pattern = enabled;
+            }
+            Message::ToggleFallibleBuilder(enabled) => {
+                self.spec.fallible_builder = enabled;
+            }
+            Message::ToggleGenerateDbFunctions(enabled) => {
+                self.generate_db_functions = enabled;
+            }
+            Message::ChannelKindSelected(channel_kind) => {
+                self.spec.channel_kind = channel_kind;
+            }
+            Message::ToggleGenerateFfi(enabled) => {
+                self.generate_ffi = enabled;
+            }
+            Message::ToggleGenerateBench(enabled) => {
+                self.generate_bench = enabled;
+            }
+            Message::BindingTargetSelected(target) => {
+                self.spec.binding_target = target;
+            }
+            Message::TesterSelected(tester) => {
+                self.spec.tester = tester;
+            }
+            Message::ToggleGracefulConnect(enabled) => {
+                self.spec.graceful_connect = enabled;
+            }
+            Message::ToggleWrapTestModule(enabled) => {
+                self.spec.wrap_test_module = enabled;
+            }
+            Message::ToggleSharedTestSetup(enabled) => {
+                self.spec.shared_test_setup = enabled;
+            }
+            Message::TestSupportPathChanged(path) => {
+                self.spec.test_support_path = path;
+                self.config_dirty = true;
+            }
+            Message::TogglePreferOwnedStrings(enabled) => {
+                self.spec.prefer_owned_strings = enabled;
+            }
+            Message::ToggleSuppressWarnings(enabled) => {
+                self.spec.suppress_warnings = enabled;
+            }
+            Message::ToggleSupportsCancel(enabled) => {
+                self.spec.supports_cancel = enabled;
+            }
+            Message::ToggleEmptyTestVecs(enabled) => {
+                self.spec.empty_test_vecs = enabled;
+            }
+            Message::ToggleKeepNestedTypePaths(enabled) => {
+                self.spec.keep_nested_type_paths = enabled;
+            }
+            Message::ToggleUseLogResultHelper(enabled) => {
+                self.spec.use_log_result_helper = enabled;
+                if enabled {
+                    self.spec.use_tracing_instrument = false;
+                }
+            }
+            Message::ToggleLogTraceParams(enabled) => {
+                self.spec.log_trace_params = enabled;
+            }
+            Message::ToggleAnnotateCopies(enabled) => {
+                self.annotate_copies = enabled;
+            }
+            Message::ToggleCodegenMarkers(enabled) => {
+                self.codegen_markers = enabled;
+            }
+            Message::ToggleUseTracingInstrument(enabled) => {
+                self.spec.use_tracing_instrument = enabled;
+                if enabled {
+                    self.spec.use_log_result_helper = false;
+                }
+            }
+            Message::ToggleValidateSyntax(enabled) => {
+                self.validate_syntax = enabled;
+                if !enabled {
+                    self.syntax_errors.clear();
+                }
+            }
+            Message::GenerateCode => {
+                if !self.check_generation_inputs() {
+                    return;
+                }
 
-    fn view(&self) -> Element<Message> {
-        let title = text("Rust 代码生成器").size(28);
+                for id in SectionId::ALL {
+                    self.regenerate_section(id);
+                }
 
-        let project_path_input = column![
-            text("项目路径:"),
-            text_input("输入项目路径", &self.project_path)
-                .on_input(Message::ProjectPathChanged)
-                .padding(8)
-                .width(Length::Fill),
-        ]
-        .spacing(5);
+                self.record_generation(1);
+                self.finish_generation("代码生成成功！".to_string());
+            }
+            Message::GenerateBatch => {
+                let batch_text = self.batch_signatures.text();
+                let java_type_options = self.java_type_options();
+                if batch_text.trim().is_empty() {
+                    self.push_status("错误：批量签名不能为空！");
+                    return;
+                }
 
-        let function_name_input = column![
-            text("函数名称 (Java 风格):"),
-            text_input(
-                "例如: deleteUltraGroupMessagesForAllChannel",
-                &self.function_name
+                let mut specs = Vec::new();
+                let mut failed_lines = Vec::new();
+
+                for (index, line) in batch_text.lines().enumerate() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let Some(signature) = parse_java_signature(line, &java_type_options) else {
+                        failed_lines.push((index + 1).to_string());
+                        continue;
+                    };
+
+                    // 每行签名只替换函数名、参数和返回值，其余选项沿用当前输入
+                    specs.push(GenSpec {
+                        function_name: signature.function_name,
+                        function_params: signature.params,
+                        callback_return_type: signature.callback_return_type,
+                        ..self.spec.clone()
+                    });
+                }
+
+                if specs.is_empty() {
+                    self.push_status(format!(
+                        "错误：没有可解析的签名，解析失败的行：{}",
+                        failed_lines.join(", ")
+                    ));
+                    return;
+                }
+
+                self.generate_batch(&specs);
+                self.finish_generation(if failed_lines.is_empty() {
+                    format!("批量生成成功：共 {} 个函数", specs.len())
+                } else {
+                    format!(
+                        "批量生成成功：共 {} 个函数，解析失败的行：{}",
+                        specs.len(),
+                        failed_lines.join(", ")
+                    )
+                });
+            }
+            Message::PickSpecCsv => {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("CSV/TSV", &["csv", "tsv", "txt"])
+                    .pick_file()
+                {
+                    self.update(Message::ImportSpecCsv(path));
+                }
+            }
+            Message::ImportSpecCsv(path) => {
+                let source = match std::fs::read_to_string(&path) {
+                    Ok(source) => source,
+                    Err(e) => {
+                        self.push_status(format!(
+                            "错误：读取导入文件 {} 失败：{}",
+                            path.display(),
+                            e
+                        ));
+                        return;
+                    }
+                };
+                let (mut specs, errors) = parse_spec_table(&source, &self.spec);
+                if specs.is_empty() {
+                    self.push_status(format!(
+                        "错误：{} 中没有可导入的函数{}",
+                        path.display(),
+                        if errors.is_empty() {
+                            String::new()
+                        } else {
+                            format!("：{}", errors.join("；"))
+                        }
+                    ));
+                    return;
+                }
+
+                // 参数列可以直接粘贴 Java 参数，按当前的类型选项转换
+                let java_type_options = self.java_type_options();
+                for spec in &mut specs {
+                    if looks_like_java_params(&spec.function_params) {
+                        spec.function_params =
+                            convert_java_params_to_rust(&spec.function_params, &java_type_options);
+                    }
+                }
+
+                self.generate_batch(&specs);
+                self.finish_generation(if errors.is_empty() {
+                    format!("导入成功：共 {} 个函数", specs.len())
+                } else {
+                    format!(
+                        "导入成功：共 {} 个函数，解析失败：{}",
+                        specs.len(),
+                        errors.join("；")
+                    )
+                });
+            }
+            Message::RestoreLastGenerated => {
+                let Some(last_generated) = LastGenerated::load(self.data_dir.as_deref()) else {
+                    self.push_status("错误：没有找到上次生成的代码！");
+                    return;
+                };
+                for id in SectionId::ALL {
+                    let code = last_generated
+                        .sections
+                        .get(&id)
+                        .map(String::as_str)
+                        .unwrap_or_default();
+                    *self.output_content_mut(id) = text_editor::Content::with_text(code);
+                }
+                self.refresh_section_stats();
+                self.validate_generated_syntax();
+                self.push_status("已恢复上次生成的代码！");
+            }
+            Message::ExportInputsJson => {
+                let json = match serde_json::to_string_pretty(&self.inputs_snapshot()) {
+                    Ok(json) => json,
+                    Err(e) => {
+                        self.push_status(format!("错误：导出输入失败：{}", e));
+                        return;
+                    }
+                };
+                self.copy_to_clipboard("输入 JSON", &json);
+            }
+            Message::ImportInputsJson => {
+                let json = match Clipboard::new().and_then(|mut c| c.get_text()) {
+                    Ok(json) => json,
+                    Err(e) => {
+                        self.push_status(format!("错误：无法读取剪贴板：{}", e));
+                        return;
+                    }
+                };
+                let snapshot = match serde_json::from_str::<InputsSnapshot>(&json) {
+                    Ok(snapshot) => snapshot,
+                    Err(e) => {
+                        self.push_status(format!("错误：剪贴板中不是有效的输入 JSON：{}", e));
+                        return;
+                    }
+                };
+                if snapshot.version > InputsSnapshot::VERSION {
+                    self.push_status(format!(
+                        "错误：输入 JSON 版本 {} 高于当前支持的版本 {}，请升级后再导入",
+                        snapshot.version,
+                        InputsSnapshot::VERSION
+                    ));
+                    return;
+                }
+                self.apply_inputs_snapshot(snapshot);
+                // 导入的日志宏等配置项和手动修改一样持久化
+                self.save_config();
+                self.push_status("已从剪贴板导入输入，导入成功！");
+            }
+            Message::RevealInFileManager => {
+                let Some(dir) = self.reveal_target_dir() else {
+                    self.push_status("项目中还没有输出框对应的目录，请先写入文件");
+                    return;
+                };
+                if let Err(e) = open_in_file_manager(&dir) {
+                    self.push_status(format!("错误：打开文件夹 {} 失败：{}", dir.display(), e));
+                }
+            }
+            Message::PreviewWritePlan => {
+                if self.project_path.trim().is_empty() {
+                    self.push_status("错误：项目路径不能为空！");
+                    return;
+                }
+                let plan = self.write_plan_summary();
+                if plan.is_empty() {
+                    self.push_status("错误：没有可写入的代码，请先生成代码！");
+                    return;
+                }
+                self.write_plan = Some(text_editor::Content::with_text(&plan));
+                self.status_message.clear();
+            }
+            Message::WriteFiles => {
+                if self.project_path.trim().is_empty() {
+                    self.push_status("错误：项目路径不能为空！");
+                    return;
+                }
+                let mut written = 0;
+                for id in SectionId::ALL {
+                    if self.output_content(id).text().trim().is_empty()
+                        || self.section_target_path(id).is_none()
+                    {
+                        continue;
+                    }
+                    match self.write_section(id) {
+                        Ok(()) => {
+                            written += 1;
+                            self.last_written_section = Some(id);
+                        }
+                        Err(e) => {
+                            self.push_status(format!("错误：写入 {} 失败：{}", id.title(), e));
+                            return;
+                        }
+                    }
+                }
+                if written == 0 {
+                    self.push_status("错误：没有可写入的代码，请先生成代码！");
+                } else {
+                    self.write_plan = None;
+                    self.push_status(format!("已写入 {} 个文件，写入成功！", written));
+                }
+            }
+            Message::TargetFileAction(action) => {
+                self.target_file.perform(action);
+            }
+            Message::InsertSectionSelected(id) => {
+                self.insert_section = id;
+            }
+            Message::ToggleShowTemplates(enabled) => {
+                self.show_templates = enabled;
+            }
+            Message::TemplateSectionSelected(id) => {
+                self.template_section = id;
+                self.template_editor = self.template_content(id);
+            }
+            Message::TemplateAction(action) => {
+                let is_edit = action.is_edit();
+                self.template_editor.perform(action);
+                if is_edit {
+                    let template = self.template_editor.text();
+                    if template.trim().is_empty() {
+                        self.section_templates.remove(&self.template_section);
+                    } else {
+                        self.section_templates
+                            .insert(self.template_section, template);
+                    }
+                    self.config_dirty = true;
+                }
+            }
+            Message::ResetTemplate => {
+                self.section_templates.remove(&self.template_section);
+                self.template_editor = text_editor::Content::new();
+                self.save_config();
+                self.push_status(format!(
+                    "{} 已恢复为内置模板",
+                    self.template_section.title()
+                ));
+            }
+            Message::InsertAtCursor => {
+                let id = self.insert_section;
+                let code = self.output_content(id).text();
+                if code.trim().is_empty() {
+                    self.push_status(format!("错误：{} 还没有生成代码！", id.title()));
+                    return;
+                }
+                let code = if self.codegen_markers {
+                    self.marked_block(&code)
+                } else {
+                    code
+                };
+                let spliced = splice_at_cursor(
+                    &self.target_file.text(),
+                    self.target_file.cursor_position(),
+                    &code,
+                );
+                self.target_file = text_editor::Content::with_text(&spliced);
+                self.push_status(format!("已将 {} 插入到光标处，插入成功！", id.title()));
+            }
+            Message::WritePlanAction(action) => {
+                // 预览只读，只允许选择和移动光标
+                if let Some(write_plan) = &mut self.write_plan {
+                    if !action.is_edit() {
+                        write_plan.perform(action);
+                    }
+                }
+            }
+            // cargo check 由 handle 放到后台执行，这里只处理结果
+            #[cfg(feature = "compile-check")]
+            Message::CompileCheck => {}
+            #[cfg(feature = "compile-check")]
+            Message::CompileCheckFinished(outcome) => self.finish_compile_check(outcome),
+            Message::ShowStats => {
+                self.push_status(format!(
+                    "本次会话：{}；累计：{}",
+                    format_generation_counts(&self.session_generation_counts),
+                    format_generation_counts(&self.generation_totals)
+                ));
+            }
+            Message::ClearAll => {
+                // 误触保护：超时或中间有其他操作时需要重新确认
+                if pending_clear.is_none_or(|armed_at| armed_at.elapsed() > CLEAR_CONFIRM_WINDOW) {
+                    self.pending_clear = Some(Instant::now());
+                    self.push_status("再次点击确认清空");
+                    return;
+                }
+
+                // 不清空项目路径，只清空其他输入框
+                self.spec.function_name.clear();
+                self.spec.function_params.clear();
+                self.raw_function_params.clear();
+                self.params.clear();
+                self.spec.callback_return_type.clear();
+                self.spec.expected_return.clear();
+                self.java_callback_type.clear();
+                self.spec.request_body_name.clear();
+                self.request_file_name.clear();
+                self.spec.rmtp_method.clear();
+                self.spec.request_fields.clear();
+                self.request_fields_editor = text_editor::Content::new();
+                self.spec.param_docs.clear();
+                self.param_docs_editor = text_editor::Content::new();
+                self.spec.timeout_ms = None;
+                self.spec.operation_type = Some(OperationType::Network);
+                self.engine_sync_content = text_editor::Content::new();
+                self.engine_async_content = text_editor::Content::new();
+                self.module_content = text_editor::Content::new();
+                self.request_builder_content = text_editor::Content::new();
+                self.request_struct_content = text_editor::Content::new();
+                self.from_conversion_content = text_editor::Content::new();
+                self.test_method_content = text_editor::Content::new();
+                self.db_agent_content = text_editor::Content::new();
+                self.db_worker_content = text_editor::Content::new();
+                self.db_sqlite_content = text_editor::Content::new();
+                self.ffi_content = text_editor::Content::new();
+                self.binding_content = text_editor::Content::new();
+                self.bench_content = text_editor::Content::new();
+                self.section_stats.clear();
+                self.batch_signatures = text_editor::Content::new();
+                self.write_plan = None;
+                self.target_file = text_editor::Content::new();
+                self.syntax_errors.clear();
+                self.push_status("已清空所有输入！");
+            }
+            Message::CopyEngineSyncToClipboard => {
+                let text = self.copy_text(SectionId::EngineSync);
+                self.copy_to_clipboard("engine_sync.rs", &text);
+            }
+            Message::CopyEngineAsyncToClipboard => {
+                let text = self.copy_text(SectionId::EngineAsync);
+                self.copy_to_clipboard("engine_async.rs", &text);
+            }
+            Message::CopyModuleToClipboard => {
+                let text = self.copy_text(SectionId::Module);
+                self.copy_to_clipboard("module 文件", &text);
+            }
+            Message::CopyRequestBuilderToClipboard => {
+                let text = self.copy_text(SectionId::RequestBuilder);
+                self.copy_to_clipboard("request_builder 文件", &text);
+            }
+            Message::CopyRequestStructToClipboard => {
+                let text = self.copy_text(SectionId::RequestStruct);
+                self.copy_to_clipboard("请求体结构", &text);
+            }
+            Message::EngineSyncAction(action) => {
+                self.perform_section_action(SectionId::EngineSync, action);
+            }
+            Message::EngineAsyncAction(action) => {
+                self.perform_section_action(SectionId::EngineAsync, action);
+            }
+            Message::ModuleAction(action) => {
+                self.perform_section_action(SectionId::Module, action);
+            }
+            Message::RequestBuilderAction(action) => {
+                self.perform_section_action(SectionId::RequestBuilder, action);
+            }
+            Message::RequestStructAction(action) => {
+                self.perform_section_action(SectionId::RequestStruct, action);
+            }
+            Message::CopyTestMethodToClipboard => {
+                let text = self.copy_text(SectionId::TestMethod);
+                self.copy_to_clipboard("测试方法", &text);
+            }
+            Message::TestMethodAction(action) => {
+                self.perform_section_action(SectionId::TestMethod, action);
+            }
+            Message::CopyDbAgentToClipboard => {
+                let text = self.copy_text(SectionId::DbAgent);
+                self.copy_to_clipboard("db_agent.rs", &text);
+            }
+            Message::CopyDbWorkerToClipboard => {
+                let text = self.copy_text(SectionId::DbWorker);
+                self.copy_to_clipboard("db_worker.rs", &text);
+            }
+            Message::CopyDbSqliteToClipboard => {
+                let text = self.copy_text(SectionId::DbSqlite);
+                self.copy_to_clipboard("db_sqlite.rs", &text);
+            }
+            Message::CopyFromConversionToClipboard => {
+                let text = self.copy_text(SectionId::FromConversion);
+                self.copy_to_clipboard("From 转换", &text);
+            }
+            Message::FromConversionAction(action) => {
+                self.perform_section_action(SectionId::FromConversion, action);
+            }
+            Message::DbAgentAction(action) => {
+                self.perform_section_action(SectionId::DbAgent, action);
+            }
+            Message::DbWorkerAction(action) => {
+                self.perform_section_action(SectionId::DbWorker, action);
+            }
+            Message::DbSqliteAction(action) => {
+                self.perform_section_action(SectionId::DbSqlite, action);
+            }
+            Message::CopyFfiToClipboard => {
+                let text = self.copy_text(SectionId::Ffi);
+                self.copy_to_clipboard("ffi.rs", &text);
+            }
+            Message::FfiAction(action) => {
+                self.perform_section_action(SectionId::Ffi, action);
+            }
+            Message::CopyBindingToClipboard => {
+                let text = self.copy_text(SectionId::Binding);
+                self.copy_to_clipboard("绑定层", &text);
+            }
+            Message::BindingAction(action) => {
+                self.perform_section_action(SectionId::Binding, action);
+            }
+            Message::CopyBenchToClipboard => {
+                let text = self.copy_text(SectionId::Bench);
+                self.copy_to_clipboard("基准测试", &text);
+            }
+            Message::BenchAction(action) => {
+                self.perform_section_action(SectionId::Bench, action);
+            }
+            Message::BatchSignaturesAction(action) => {
+                self.batch_signatures.perform(action);
+            }
+            Message::SearchInOutput(query) => {
+                self.search_query = query;
+                self.search_in_output();
+            }
+            Message::ToggleSection(id) => {
+                if !self.collapsed_sections.remove(&id) {
+                    self.collapsed_sections.insert(id);
+                }
+            }
+            // 只刷新这一个输出框，其他输出框中手动修改的内容保持不变
+            Message::RegenerateSection(id) => {
+                if !self.check_generation_inputs() {
+                    return;
+                }
+                self.regenerate_section(id);
+                self.finish_generation(format!("已重新生成：{}", id.title()));
+            }
+            Message::MoveSectionUp(id) => {
+                self.move_section(id, true);
+            }
+            Message::MoveSectionDown(id) => {
+                self.move_section(id, false);
+            }
+        }
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        // 整个界面（文字、按钮、编辑器）按 ui_scale 缩放，见 main 中的 scale_factor
+        let title = row![
+            text("Rust 代码生成器").size(28),
+            iced::widget::horizontal_space(),
+            checkbox("代码高亮", self.syntax_highlighting)
+                .on_toggle(Message::ToggleSyntaxHighlighting),
+            button(text("A-").size(14))
+                .on_press(Message::UiScaleChanged(self.ui_scale - UI_SCALE_STEP))
+                .padding(5),
+            text(format!("{:.0}%", self.ui_scale * 100.0)).size(14),
+            button(text("A+").size(14))
+                .on_press(Message::UiScaleChanged(self.ui_scale + UI_SCALE_STEP))
+                .padding(5),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+        let project_path_input = column![
+            text("项目路径:"),
+            text_input("输入项目路径", &self.project_path)
+                .id(FocusField::ProjectPath.id())
+                .on_input(Message::ProjectPathChanged)
+                .padding(8)
+                .width(Length::Fill),
+        ]
+        .spacing(5);
+
+        let function_name_input = column![
+            text("函数名称 (Java 风格):"),
+            text_input(
+                "例如: deleteUltraGroupMessagesForAllChannel",
+                &self.spec.function_name
             )
+            .id(FocusField::FunctionName.id())
             .on_input(Message::FunctionNameChanged)
             .padding(8)
             .width(Length::Fill),
@@ -382,23 +2703,112 @@ impl CodeGenerator {
         .spacing(5);
 
         let function_params_input = column![
-            text("函数参数:"),
+            row![
+                text("函数参数:"),
+                pick_list(
+                    &InputLanguage::ALL[..],
+                    Some(self.input_language),
+                    Message::InputLanguageSelected,
+                )
+                .padding(4),
+                checkbox("显示原始输入", self.show_raw_params)
+                    .on_toggle(Message::ToggleShowRawParams),
+            ]
+            .spacing(20)
+            .align_y(iced::Alignment::Center),
             text_input(
                 "例如: params: SearchLocalFriendParams",
-                &self.function_params
+                if self.show_raw_params {
+                    &self.raw_function_params
+                } else {
+                    &self.spec.function_params
+                }
             )
+            .id(FocusField::FunctionParams.id())
             .on_input(Message::FunctionParamsChanged)
             .padding(8)
             .width(Length::Fill),
         ]
-        .spacing(5);
+        .spacing(5)
+        .push_maybe(self.type_suggestion_row(TypeCompletionTarget::Params))
+        .push(
+            Column::with_children(self.params.iter().enumerate().map(|(index, param)| {
+                let param_row = row![
+                    text_input("参数名", &param.name)
+                        .on_input(move |name| Message::ParamRowNameChanged(index, name))
+                        .padding(8)
+                        .width(Length::FillPortion(1)),
+                    text_input("参数类型", &param.param_type)
+                        .on_input(move |param_type| Message::ParamRowTypeChanged(index, param_type))
+                        .padding(8)
+                        .width(Length::FillPortion(2)),
+                    button(text("删除").size(14))
+                        .on_press(Message::ParamRowRemoved(index))
+                        .padding(8),
+                ]
+                .spacing(10);
+                column![param_row]
+                    .spacing(5)
+                    .push_maybe(self.type_suggestion_row(TypeCompletionTarget::Row(index)))
+                    .into()
+            }))
+            .spacing(5),
+        )
+        .push(
+            button(text("添加参数").size(14))
+                .on_press(Message::ParamRowAdded)
+                .padding(8),
+        )
+        .push(
+            row![
+                text("已知类型 (参数类型自动补全):"),
+                text_input("例如: ConversationType, MessageId", &self.known_types)
+                    .on_input(Message::KnownTypesChanged)
+                    .padding(5)
+                    .width(Length::Fill),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+        );
 
-        let callback_return_input = column![
-            text("Callback 返回值类型:"),
-            text_input("例如: Vec<FriendInfo>", &self.callback_return_type)
-                .on_input(Message::CallbackReturnTypeChanged)
-                .padding(8)
-                .width(Length::Fill),
+        let callback_return_input = row![
+            column![
+                text("Callback 返回值类型:"),
+                text_input("例如: Vec<FriendInfo>", &self.spec.callback_return_type)
+                    .id(FocusField::CallbackReturnType.id())
+                    .on_input(Message::CallbackReturnTypeChanged)
+                    .padding(8)
+                    .width(Length::Fill),
+            ]
+            .spacing(5),
+            column![
+                text("期望返回值 (可选，测试中断言相等):"),
+                text_input("例如: true", &self.spec.expected_return)
+                    .on_input(Message::ExpectedReturnChanged)
+                    .padding(8)
+                    .width(Length::Fill),
+            ]
+            .spacing(5),
+            column![
+                text("默认返回值类型 (返回值为空时):"),
+                text_input("例如: ()", &self.spec.default_return_type)
+                    .on_input(Message::DefaultReturnTypeChanged)
+                    .padding(8)
+                    .width(Length::Fill),
+            ]
+            .spacing(5),
+        ]
+        .spacing(10);
+
+        let java_callback_input = column![
+            text("Java Callback 类型 (可选，自动推断返回值):"),
+            text_input(
+                "例如: ResultCallback<List<GroupInfo>>",
+                &self.java_callback_type
+            )
+            .on_input(Message::JavaCallbackTypeChanged)
+            .padding(8)
+            .width(Length::Fill),
         ]
         .spacing(5);
 
@@ -406,19 +2816,134 @@ impl CodeGenerator {
             text("请求体名称 (可选):"),
             text_input(
                 "例如: SetUltraGroupOperateStatusRequest",
-                &self.request_body_name
+                &self.spec.request_body_name
             )
+            .id(FocusField::RequestBody.id())
             .on_input(Message::RequestBodyNameChanged)
             .padding(8)
             .width(Length::Fill),
+            row![
+                text_input(
+                    "协议方法名 (get_method 返回值，可选)",
+                    &self.spec.rmtp_method
+                )
+                .on_input(Message::RmtpMethodChanged)
+                .padding(8)
+                .width(Length::Fill),
+                checkbox("生成 // Wire: 注释和请求字段文档", self.spec.document_wire)
+                    .on_toggle(Message::ToggleDocumentWire),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            text("请求体字段 (可选，每行 field_name: rust_type，优先于 proto 字段):"),
+            text_editor(&self.request_fields_editor)
+                .placeholder("例如: group_id: String\nuser_ids: Vec<String>")
+                .on_action(Message::RequestFieldsAction)
+                .height(80),
+            checkbox("编辑请求字段说明", self.show_param_docs)
+                .on_toggle(Message::ToggleShowParamDocs),
+        ]
+        .spacing(5)
+        .push_maybe(self.show_param_docs.then(|| {
+            text_editor(&self.param_docs_editor)
+                .placeholder(
+                    "每行 name: description，未填写的字段使用 Java 参数名\n例如: group_id: 群组 ID",
+                )
+                .on_action(Message::ParamDocsAction)
+                .height(80)
+        }));
+
+        let trace_config_input = row![
+            column![
+                text("日志宏:"),
+                text_input("例如: trace_i_json", &self.spec.trace_macro)
+                    .on_input(Message::TraceMacroChanged)
+                    .padding(8)
+                    .width(Length::Fill),
+            ]
+            .spacing(5),
+            column![
+                text("日志标签前缀:"),
+                text_input("例如: P", &self.spec.trace_tag_prefix)
+                    .on_input(Message::TraceTagPrefixChanged)
+                    .padding(8)
+                    .width(Length::Fill),
+            ]
+            .spacing(5),
+            column![
+                text("数据库日志宏:"),
+                text_input("例如: log_db_i", &self.spec.db_log_macro)
+                    .on_input(Message::DbLogMacroChanged)
+                    .padding(8)
+                    .width(Length::Fill),
+            ]
+            .spacing(5),
+        ]
+        .spacing(10);
+
+        let test_value_table_input = column![
+            text("测试参数取值 (按参数名，格式 name=value, ...):"),
+            text_input("例如: limit=20, offset=0", &self.spec.test_value_table)
+                .on_input(Message::TestValueTableChanged)
+                .padding(8)
+                .width(Length::Fill),
+        ]
+        .spacing(5);
+
+        let enum_param_table_input = column![
+            text("整数枚举参数 (按参数名，格式 name=EnumType, ...):"),
+            text_input("例如: status=MessageStatus", &self.spec.enum_param_table)
+                .on_input(Message::EnumParamTableChanged)
+                .padding(8)
+                .width(Length::Fill),
+        ]
+        .spacing(5);
+
+        let error_code_table_input = column![
+            text("响应错误码映射 (格式 code => error, ...，为空时直接回调 code):"),
+            text_input(
+                "例如: EngineError::NotFound => EngineError::GroupNotExist",
+                &self.spec.error_code_table
+            )
+            .on_input(Message::ErrorCodeTableChanged)
+            .padding(8)
+            .width(Length::Fill),
         ]
         .spacing(5);
 
+        let conv_type_config_input = row![
+            column![
+                text("会话类型 (网络层):"),
+                text_input("例如: ConversationType", &self.spec.conv_type_name)
+                    .on_input(Message::ConvTypeNameChanged)
+                    .padding(8)
+                    .width(Length::Fill),
+            ]
+            .spacing(5),
+            column![
+                text("会话类型 (数据库层):"),
+                text_input("例如: DbConversationType", &self.spec.db_conv_type_name)
+                    .on_input(Message::DbConvTypeNameChanged)
+                    .padding(8)
+                    .width(Length::Fill),
+            ]
+            .spacing(5),
+            column![
+                text("时间戳类型 (Date/Instant):"),
+                text_input("例如: i64", &self.spec.timestamp_type)
+                    .on_input(Message::TimestampTypeChanged)
+                    .padding(8)
+                    .width(Length::Fill),
+            ]
+            .spacing(5),
+        ]
+        .spacing(10);
+
         let operation_type_picker = column![
             text("操作类型:"),
             pick_list(
                 &OperationType::ALL[..],
-                self.operation_type.as_ref(),
+                self.spec.operation_type.as_ref(),
                 Message::OperationTypeSelected,
             )
             .padding(8)
@@ -426,26 +2951,496 @@ impl CodeGenerator {
         ]
         .spacing(5);
 
-        let params_to_request_checkbox =
-            checkbox("参数传递到 Request 结构体", self.pass_params_to_request)
-                .on_toggle(Message::TogglePassParamsToRequest);
+        let callback_style_picker = column![
+            text("回调写法:"),
+            pick_list(
+                &CallbackStyle::ALL[..],
+                Some(self.spec.callback_style),
+                Message::CallbackStyleSelected,
+            )
+            .padding(8)
+            .width(200),
+        ]
+        .spacing(5);
 
-        let generate_db_functions_checkbox = checkbox("生成数据库函数", self.generate_db_functions)
-            .on_toggle(Message::ToggleGenerateDbFunctions);
+        let visibility_picker = column![
+            text("函数可见性:"),
+            row![
+                pick_list(
+                    &Visibility::ALL[..],
+                    self.spec.visibility,
+                    Message::VisibilitySelected,
+                )
+                .placeholder("按输出框默认")
+                .padding(8)
+                .width(200),
+                button("默认").on_press(Message::ResetVisibility).padding(8),
+            ]
+            .spacing(5),
+        ]
+        .spacing(5);
 
-        let generate_button = button(text("生成代码").size(16))
-            .on_press(Message::GenerateCode)
-            .padding(10)
-            .width(150);
+        let params_to_request_checkbox = checkbox(
+            "参数传递到 Request 结构体",
+            self.spec.pass_params_to_request,
+        )
+        .on_toggle(Message::TogglePassParamsToRequest);
 
-        let clear_button = button(text("清空").size(16))
-            .on_press(Message::ClearAll)
-            .padding(10)
-            .width(100);
+        let field_attribute_input = column![
+            text("Request 成员属性模板 ({field} 为成员名，留空不生成):"),
+            text_input(
+                "例如: #[serde(rename = \"{field}\")]",
+                &self.spec.field_attribute_template
+            )
+            .on_input(Message::FieldAttributeTemplateChanged)
+            .padding(8)
+            .width(Length::Fill),
+        ]
+        .spacing(5);
 
-        let status_color = if self.status_message.contains("错误") {
-            iced::Color::from_rgb(1.0, 0.3, 0.3)
-        } else if self.status_message.contains("成功")
+        let feature_gate_input = row![
+            text("Cargo feature:"),
+            text_input("留空不加 #[cfg(feature)]", &self.spec.feature_gate)
+                .on_input(Message::FeatureGateChanged)
+                .padding(5)
+                .width(200),
+            checkbox(
+                "request 和测试方法也加 feature 条件",
+                self.spec.feature_gate_request_and_test
+            )
+            .on_toggle(Message::ToggleFeatureGateRequestAndTest),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+        let proto_file_name = self
+            .proto_path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "未选择".to_string());
+        let proto_input = row![
+            button(text("选择 .proto 文件").size(14))
+                .on_press(Message::PickProtoFile)
+                .padding(5),
+            text(proto_file_name),
+            text_input("消息名 (留空使用请求体名称)", &self.spec.proto_message_name)
+                .on_input(Message::ProtoMessageNameChanged)
+                .padding(5)
+                .width(300),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+        let default_request_checkbox = checkbox(
+            "生成 default_request(cb) 构造函数 (需关闭参数传递)",
+            self.spec.generate_default_request,
+        )
+        .on_toggle(Message::ToggleGenerateDefaultRequest);
+
+        let request_stores_callback_checkbox = checkbox(
+            "Request 保存回调 (关闭时回调放入回调池，按 msg_uid 取出)",
+            self.spec.request_stores_callback,
+        )
+        .on_toggle(Message::ToggleRequestStoresCallback);
+
+        let native_async_trait_checkbox = checkbox(
+            "Request 使用原生 async fn (不使用 #[async_trait])",
+            self.spec.native_async_trait,
+        )
+        .on_toggle(Message::ToggleNativeAsyncTrait);
+
+        let pb_buffer_picker = row![
+            text("pb 数据类型:"),
+            pick_list(
+                &PbBuffer::ALL[..],
+                Some(self.spec.pb_buffer),
+                Message::PbBufferSelected,
+            )
+            .padding(5),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+        let async_mode_checkbox = checkbox(
+            "生成 async 代码 (关闭时 module / db_agent 用 block_on 生成阻塞版本，不支持取消)",
+            self.spec.async_mode,
+        )
+        .on_toggle(Message::ToggleAsyncMode);
+
+        let use_builder_pattern_checkbox = checkbox(
+            "Request 使用 Builder 模式构造 (需开启参数传递)",
+            self.spec.use_builder_pattern,
+        )
+        .on_toggle(Message::ToggleUseBuilderPattern);
+
+        let fallible_builder_checkbox = checkbox(
+            "request builder 返回 Result (字段转换失败时返回错误)",
+            self.spec.fallible_builder,
+        )
+        .on_toggle(Message::ToggleFallibleBuilder);
+
+        let generate_db_functions_checkbox = row![
+            checkbox("生成数据库函数", self.generate_db_functions)
+                .on_toggle(Message::ToggleGenerateDbFunctions),
+            text("db_agent 通道:"),
+            pick_list(
+                &ChannelKind::ALL[..],
+                Some(self.spec.channel_kind),
+                Message::ChannelKindSelected,
+            )
+            .padding(5),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+        let generate_ffi_checkbox = checkbox("生成 FFI (C 接口) 函数", self.generate_ffi)
+            .on_toggle(Message::ToggleGenerateFfi);
+
+        let generate_bench_checkbox = checkbox(
+            "生成 criterion 基准测试 (需在 Cargo.toml 中添加 [[bench]])",
+            self.generate_bench,
+        )
+        .on_toggle(Message::ToggleGenerateBench);
+
+        let binding_target_picker = row![
+            text("上层绑定:"),
+            pick_list(
+                &BindingTarget::ALL[..],
+                Some(self.spec.binding_target),
+                Message::BindingTargetSelected,
+            )
+            .padding(8),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+        let tester_picker = row![
+            text("测试账号:"),
+            pick_list(
+                &TesterChoice::ALL[..],
+                Some(self.spec.tester),
+                Message::TesterSelected,
+            )
+            .padding(8),
+            checkbox("连接失败时跳过测试", self.spec.graceful_connect)
+                .on_toggle(Message::ToggleGracefulConnect),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+        let wrap_test_module_checkbox = row![
+            checkbox("测试方法包裹为完整模块", self.spec.wrap_test_module)
+                .on_toggle(Message::ToggleWrapTestModule),
+            text("ServerApi 等测试支持代码所在模块:"),
+            text_input("例如: crate::common", &self.spec.test_support_path)
+                .on_input(Message::TestSupportPathChanged)
+                .padding(5)
+                .width(300),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+        let shared_test_setup_checkbox = checkbox(
+            "测试参数放进共用的辅助函数 (批量生成时同时生成测试)",
+            self.spec.shared_test_setup,
+        )
+        .on_toggle(Message::ToggleSharedTestSetup);
+
+        let empty_test_vecs_checkbox =
+            checkbox("测试中 Vec 参数使用空 vec![]", self.spec.empty_test_vecs)
+                .on_toggle(Message::ToggleEmptyTestVecs);
+
+        let keep_nested_type_paths_checkbox = checkbox(
+            "Java 内部类保留外部类路径 (Outer.Inner -> Outer::Inner)",
+            self.spec.keep_nested_type_paths,
+        )
+        .on_toggle(Message::ToggleKeepNestedTypePaths);
+
+        let use_log_result_helper_checkbox = checkbox(
+            "数据库操作用 log_result 记录结果 (不生成 match)",
+            self.spec.use_log_result_helper,
+        )
+        .on_toggle(Message::ToggleUseLogResultHelper);
+
+        let log_trace_params_checkbox = checkbox(
+            "-T 日志记录入参 (敏感函数请关闭)",
+            self.spec.log_trace_params,
+        )
+        .on_toggle(Message::ToggleLogTraceParams);
+
+        let use_tracing_instrument_checkbox = checkbox(
+            "异步函数使用 #[tracing::instrument] (不生成手动 trace 日志)",
+            self.spec.use_tracing_instrument,
+        )
+        .on_toggle(Message::ToggleUseTracingInstrument);
+
+        let prefer_owned_strings_checkbox = checkbox(
+            "字符串参数使用 String (不转换为 &str)",
+            self.spec.prefer_owned_strings,
+        )
+        .on_toggle(Message::TogglePreferOwnedStrings);
+
+        let annotate_copies_checkbox = checkbox(
+            "复制时标注目标文件路径 (// File: ...)",
+            self.annotate_copies,
+        )
+        .on_toggle(Message::ToggleAnnotateCopies);
+
+        let codegen_markers_checkbox = checkbox(
+            "复制时加上 @generated 头和结束标记 (写入文件时总是加上)",
+            self.codegen_markers,
+        )
+        .on_toggle(Message::ToggleCodegenMarkers);
+
+        let validate_syntax_checkbox = checkbox(
+            "生成后检查代码语法 (syn 可能误报宏较多的代码)",
+            self.validate_syntax,
+        )
+        .on_toggle(Message::ToggleValidateSyntax);
+
+        let suppress_warnings_checkbox = checkbox(
+            "消除未使用变量警告 (_ 前缀、去掉多余 mut)",
+            self.spec.suppress_warnings,
+        )
+        .on_toggle(Message::ToggleSuppressWarnings);
+
+        let supports_cancel_input = row![
+            checkbox(
+                "网络请求支持取消 (CancellationToken)",
+                self.spec.supports_cancel
+            )
+            .on_toggle(Message::ToggleSupportsCancel),
+            text_input(
+                "例如: tokio_util::sync::CancellationToken",
+                &self.spec.cancel_token_import
+            )
+            .on_input(Message::CancelTokenImportChanged)
+            .padding(5)
+            .width(400),
+            text_input("例如: EngineError::Cancelled", &self.spec.cancel_error)
+                .on_input(Message::CancelErrorChanged)
+                .padding(5)
+                .width(300),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+        let timeout_ms = self
+            .spec
+            .timeout_ms
+            .map(|ms| ms.to_string())
+            .unwrap_or_default();
+        let timeout_input = row![
+            text("网络请求超时 (毫秒):"),
+            text_input("为空时不加超时", &timeout_ms)
+                .on_input(Message::TimeoutMsChanged)
+                .padding(5)
+                .width(120),
+            text_input("例如: EngineError::Timeout", &self.spec.timeout_error)
+                .on_input(Message::TimeoutErrorChanged)
+                .padding(5)
+                .width(400),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+        let guards_input = row![
+            checkbox("engine_sync 生成参数校验", self.spec.generate_guards)
+                .on_toggle(Message::ToggleGenerateGuards),
+            text_input("例如: EngineError::InvalidParam", &self.spec.guard_error)
+                .on_input(Message::GuardErrorChanged)
+                .padding(5)
+                .width(400),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+        let engine_impl_input = row![
+            checkbox("engine 包裹在 impl 块中", self.spec.wrap_engine_impl)
+                .on_toggle(Message::ToggleWrapEngineImpl),
+            text_input("例如: Engine", &self.spec.engine_type)
+                .on_input(Message::EngineTypeChanged)
+                .padding(5)
+                .width(200),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+        let result_alias_input = row![
+            checkbox("使用 Result 类型别名", self.spec.use_result_alias)
+                .on_toggle(Message::ToggleUseResultAlias),
+            text_input("例如: EngineResult", &self.spec.result_alias)
+                .on_input(Message::ResultAliasChanged)
+                .padding(5)
+                .width(400),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+        let cb_pool_input = row![
+            text("回调包装函数:"),
+            text_input("例如: cb_pool_once", &self.spec.cb_pool_fn)
+                .on_input(Message::CbPoolFnChanged)
+                .padding(5)
+                .width(200),
+            pick_list(
+                &CbPoolVariant::ALL[..],
+                Some(self.spec.cb_pool_variant),
+                Message::CbPoolVariantSelected,
+            )
+            .padding(5),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+
+        let batch_input = column![
+            row![
+                text("批量 Java 签名 (每行一个):"),
+                button(text("从 CSV/TSV 导入").size(14))
+                    .on_press(Message::PickSpecCsv)
+                    .padding(5),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            text_editor(&self.batch_signatures)
+                .placeholder(
+                    "例如: public void getGroupInfo(String groupId, ResultCallback<GroupInfo> callback);"
+                )
+                .on_action(Message::BatchSignaturesAction)
+                .height(120),
+        ]
+        .spacing(5);
+
+        let insert_at_cursor_input = column![
+            text("插入到已有文件 (粘贴目标文件内容，放好光标后插入):"),
+            row![
+                pick_list(
+                    &SectionId::ALL[..],
+                    Some(self.insert_section),
+                    Message::InsertSectionSelected,
+                )
+                .padding(5),
+                button("在光标处插入")
+                    .on_press(Message::InsertAtCursor)
+                    .padding(5),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+            text_editor(&self.target_file)
+                .placeholder("在这里粘贴目标文件的完整内容")
+                .on_action(Message::TargetFileAction)
+                .font(Font::MONOSPACE)
+                .height(200),
+        ]
+        .spacing(5);
+
+        let template_input = if self.show_templates {
+            column![
+                checkbox("编辑代码模板", self.show_templates)
+                    .on_toggle(Message::ToggleShowTemplates),
+                row![
+                    pick_list(
+                        &SectionId::ALL[..],
+                        Some(self.template_section),
+                        Message::TemplateSectionSelected,
+                    )
+                    .padding(5),
+                    button("恢复内置模板")
+                        .on_press(Message::ResetTemplate)
+                        .padding(5),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+                text_editor(&self.template_editor)
+                    .placeholder(
+                        "为空时使用内置模板；可用占位符: {name} {params} {args} {return} {request}"
+                    )
+                    .on_action(Message::TemplateAction)
+                    .font(Font::MONOSPACE)
+                    .height(200),
+            ]
+            .spacing(5)
+        } else {
+            column![checkbox("编辑代码模板", self.show_templates)
+                .on_toggle(Message::ToggleShowTemplates)]
+        };
+
+        let generate_button = button(text("生成代码").size(16))
+            .on_press(Message::GenerateCode)
+            .padding(10)
+            .width(150);
+
+        let batch_button = button(text("批量生成").size(16))
+            .on_press(Message::GenerateBatch)
+            .padding(10)
+            .width(150);
+
+        let restore_button = button(text("恢复上次生成").size(16))
+            .on_press(Message::RestoreLastGenerated)
+            .padding(10)
+            .width(150);
+
+        let export_inputs_button = button(text("导出输入").size(16))
+            .on_press(Message::ExportInputsJson)
+            .padding(10)
+            .width(100);
+
+        let import_inputs_button = button(text("导入输入").size(16))
+            .on_press(Message::ImportInputsJson)
+            .padding(10)
+            .width(100);
+
+        let reveal_button = button(text("打开所在文件夹").size(16))
+            .on_press(Message::RevealInFileManager)
+            .padding(10)
+            .width(150);
+
+        let preview_write_button = button(text("预览写入文件").size(16))
+            .on_press(Message::PreviewWritePlan)
+            .padding(10)
+            .width(150);
+
+        let write_files_button = button(text("写入文件").size(16))
+            .on_press(Message::WriteFiles)
+            .padding(10)
+            .width(100);
+
+        let stats_button = button(text("生成统计").size(16))
+            .on_press(Message::ShowStats)
+            .padding(10)
+            .width(100);
+
+        let clear_button = button(text("清空").size(16))
+            .on_press(Message::ClearAll)
+            .padding(10)
+            .width(100);
+
+        let action_row = row![
+            generate_button,
+            batch_button,
+            restore_button,
+            export_inputs_button,
+            import_inputs_button,
+            preview_write_button,
+            write_files_button,
+            reveal_button,
+            stats_button,
+            clear_button
+        ]
+        .spacing(10);
+
+        // 开发用：编译检查需要完整跑一次 cargo check，只在开启 compile-check feature 时显示
+        #[cfg(feature = "compile-check")]
+        let action_row = action_row.push(
+            button(text("编译检查").size(16))
+                .on_press(Message::CompileCheck)
+                .padding(10)
+                .width(100),
+        );
+
+        let status_color = if self.status_message.contains("错误") {
+            iced::Color::from_rgb(1.0, 0.3, 0.3)
+        } else if self.status_message.contains("成功")
             || self.status_message.contains("复制")
             || self.status_message.contains("清空")
         {
@@ -454,1434 +3449,2637 @@ impl CodeGenerator {
             iced::Color::WHITE
         };
 
+        // 状态历史，最新的在最上面，时间为启动后经过的时间
+        let status_log = if self.log.is_empty() {
+            column![]
+        } else {
+            column![
+                text("状态日志:").size(14),
+                scrollable(
+                    Column::with_children(self.log.iter().rev().map(|(at, msg)| {
+                        let elapsed = at.duration_since(self.started_at).as_secs();
+                        text(format!(
+                            "[{:02}:{:02}:{:02}] {}",
+                            elapsed / 3600,
+                            elapsed / 60 % 60,
+                            elapsed % 60,
+                            msg
+                        ))
+                        .size(12)
+                        .into()
+                    }))
+                    .spacing(2),
+                )
+                .height(100),
+            ]
+            .spacing(5)
+        };
+
         let status = text(&self.status_message)
             .size(14)
             .style(move |_theme: &Theme| text::Style {
                 color: Some(status_color),
             });
 
-        // engine_sync.rs 输出框
-        let engine_sync_section = column![
-            row![
-                text("engine_sync.rs").size(16),
-                button(text("复制").size(14))
-                    .on_press(Message::CopyEngineSyncToClipboard)
-                    .padding(5),
-            ]
-            .spacing(10),
-            text_editor(&self.engine_sync_content)
-                .on_action(Message::EngineSyncAction)
-                .height(200),
-        ]
-        .spacing(5);
+        let search_input = text_input("在生成的代码中搜索", &self.search_query)
+            .on_input(Message::SearchInOutput)
+            .padding(8)
+            .width(Length::Fill);
+
+        // 输出框按配置的顺序排列，不适用于当前模式或没有内容的输出框不显示
+        let output_sections = Column::with_children(
+            self.displayed_sections()
+                .into_iter()
+                .map(|id| self.output_section_for(id).into()),
+        )
+        .spacing(15);
+
+        let write_plan_view = if let Some(write_plan) = &self.write_plan {
+            column![
+                text("将要写入的文件:").size(16),
+                text_editor(write_plan)
+                    .on_action(Message::WritePlanAction)
+                    .font(Font::MONOSPACE)
+                    .height(160),
+            ]
+            .spacing(5)
+        } else {
+            column![]
+        };
+
+        let inputs = column![
+            title,
+            project_path_input,
+            function_name_input,
+            function_params_input,
+            callback_return_input,
+            java_callback_input,
+            request_body_input,
+            trace_config_input,
+            conv_type_config_input,
+            test_value_table_input,
+            enum_param_table_input,
+            error_code_table_input,
+            row![
+                operation_type_picker,
+                callback_style_picker,
+                visibility_picker
+            ]
+            .spacing(20)
+            .wrap(),
+            params_to_request_checkbox,
+            default_request_checkbox,
+            request_stores_callback_checkbox,
+            use_builder_pattern_checkbox,
+            fallible_builder_checkbox,
+            native_async_trait_checkbox,
+            pb_buffer_picker,
+            async_mode_checkbox,
+            field_attribute_input,
+            proto_input,
+            feature_gate_input,
+            generate_db_functions_checkbox,
+            generate_ffi_checkbox,
+            generate_bench_checkbox,
+            binding_target_picker,
+            tester_picker,
+            wrap_test_module_checkbox,
+            shared_test_setup_checkbox,
+            empty_test_vecs_checkbox,
+            prefer_owned_strings_checkbox,
+            keep_nested_type_paths_checkbox,
+            use_log_result_helper_checkbox,
+            log_trace_params_checkbox,
+            use_tracing_instrument_checkbox,
+            suppress_warnings_checkbox,
+            validate_syntax_checkbox,
+            annotate_copies_checkbox,
+            codegen_markers_checkbox,
+            supports_cancel_input,
+            timeout_input,
+            guards_input,
+            engine_impl_input,
+            result_alias_input,
+            cb_pool_input,
+            batch_input,
+            insert_at_cursor_input,
+            template_input,
+            // 左右分栏时输入栏较窄，按钮放不下时换行
+            action_row.wrap(),
+            status,
+            status_log,
+            write_plan_view,
+        ]
+        .spacing(15);
+
+        let outputs = column![search_input, output_sections].spacing(15);
+
+        // 宽窗口左侧固定宽度放输入和按钮，右侧单独滚动查看输出；窄窗口仍然上下排列
+        if self.uses_split_layout() {
+            row![
+                scrollable(inputs.padding(20)).width(INPUT_COLUMN_WIDTH),
+                scrollable(outputs.padding(20)).width(Length::Fill),
+            ]
+            .spacing(10)
+            .into()
+        } else {
+            let content = column![inputs, outputs]
+                .spacing(15)
+                .padding(20)
+                .width(Length::Fill);
+
+            container(scrollable(content)).center_x(Length::Fill).into()
+        }
+    }
+
+    // 配置中的顺序可能缺少新增的输出框或有重复，这里补全并去重
+    fn resolved_section_order(&self) -> Vec<SectionId> {
+        let mut order = Vec::new();
+        for id in self.section_order.iter().chain(SectionId::ALL.iter()) {
+            if !order.contains(id) {
+                order.push(*id);
+            }
+        }
+        order
+    }
+
+    // 界面上按顺序显示的输出框：跳过不适用于当前模式的和没有内容的
+    fn displayed_sections(&self) -> Vec<SectionId> {
+        self.resolved_section_order()
+            .into_iter()
+            .filter(|&id| self.section_visible(id))
+            .filter(|&id| !self.output_content(id).text().trim().is_empty())
+            .collect()
+    }
+
+    // 输出框是否适用于当前的模式和输入
+    fn section_visible(&self, id: SectionId) -> bool {
+        match id {
+            SectionId::RequestBuilder => self.spec.is_network(),
+            SectionId::RequestStruct => !self.spec.request_body_name.is_empty(),
+            SectionId::FromConversion => self.spec.needs_from_conversion(),
+            SectionId::DbAgent | SectionId::DbWorker | SectionId::DbSqlite => {
+                self.generate_db_functions
+            }
+            SectionId::Ffi => self.generate_ffi,
+            SectionId::Binding => self.spec.binding_target != BindingTarget::None,
+            SectionId::Bench => self.generate_bench,
+            SectionId::EngineSync
+            | SectionId::EngineAsync
+            | SectionId::Module
+            | SectionId::TestMethod => true,
+        }
+    }
+
+    // 批量生成：engine 和 module 按顺序拼接，共用测试参数时一起生成测试，参数相同的测试共用一个辅助函数
+    fn generate_batch(&mut self, specs: &[GenSpec]) {
+        let join_section = |id: SectionId| {
+            specs
+                .iter()
+                .map(|spec| self.render_section(id, spec))
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        };
+        let engine_sync_code =
+            self.wrap_engine_section(SectionId::EngineSync, &join_section(SectionId::EngineSync));
+        let engine_async_code = self.wrap_engine_section(
+            SectionId::EngineAsync,
+            &join_section(SectionId::EngineAsync),
+        );
+        let module_code = join_section(SectionId::Module);
+        let test_code = if !self.spec.shared_test_setup {
+            None
+        } else if self.section_templates.contains_key(&SectionId::TestMethod) {
+            Some(join_section(SectionId::TestMethod))
+        } else {
+            Some(normalize_whitespace(&generate_test_methods(specs)))
+        };
+
+        self.engine_sync_content = text_editor::Content::with_text(&engine_sync_code);
+        self.engine_async_content = text_editor::Content::with_text(&engine_async_code);
+        self.module_content = text_editor::Content::with_text(&module_code);
+        if let Some(test_code) = test_code {
+            self.test_method_content = text_editor::Content::with_text(&test_code);
+        }
+
+        // 导入的函数可能有不同的操作类型，按各自的类型统计
+        let mut counts: HashMap<Option<OperationType>, u64> = HashMap::new();
+        for spec in specs {
+            *counts.entry(spec.operation_type.clone()).or_default() += 1;
+        }
+        for (operation_type, count) in counts {
+            self.record_generation_for(operation_type, count);
+        }
+    }
+
+    // 有自定义模板时按模板替换占位符，否则使用内置的生成函数；显示前统一整理空白
+    fn render_section(&self, id: SectionId, spec: &GenSpec) -> String {
+        let code = match self.section_templates.get(&id) {
+            Some(template) if !template.trim().is_empty() => {
+                render_template(template, &spec.template_vars())
+            }
+            _ => id.generate(spec),
+        };
+        normalize_whitespace(&code)
+    }
+
+    // engine 的两个输出框按选项包裹在 impl 块中，其他输出框原样返回
+    fn wrap_engine_section(&self, id: SectionId, code: &str) -> String {
+        match id {
+            SectionId::EngineSync | SectionId::EngineAsync => wrap_in_engine_impl(&self.spec, code),
+            _ => code.to_string(),
+        }
+    }
+
+    fn template_content(&self, id: SectionId) -> text_editor::Content {
+        self.section_templates
+            .get(&id)
+            .map(|template| text_editor::Content::with_text(template))
+            .unwrap_or_default()
+    }
+
+    // 无界面模式的输出：按默认顺序输出可见且非空的输出框，每个前面加一行 // === 标题 === 分隔
+    fn headless_output(&self) -> String {
+        SectionId::ALL
+            .into_iter()
+            .filter(|&id| self.section_visible(id))
+            .map(|id| {
+                (
+                    id,
+                    self.wrap_engine_section(id, &self.render_section(id, &self.spec)),
+                )
+            })
+            .filter(|(_, code)| !code.trim().is_empty())
+            .map(|(id, code)| format!("// === {} ===\n{}\n", id.title(), code.trim_end()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // 与上一个/下一个显示的输出框交换位置，并保存到配置
+    fn move_section(&mut self, id: SectionId, up: bool) {
+        let mut order = self.resolved_section_order();
+        let displayed = self.displayed_sections();
+        let visible: Vec<usize> = (0..order.len())
+            .filter(|&i| displayed.contains(&order[i]))
+            .collect();
+        let Some(pos) = visible.iter().position(|&i| order[i] == id) else {
+            return;
+        };
+        let target = if up {
+            pos.checked_sub(1)
+        } else {
+            Some(pos + 1).filter(|&next| next < visible.len())
+        };
+        let Some(target) = target else {
+            return;
+        };
+
+        order.swap(visible[pos], visible[target]);
+        self.section_order = order;
+        self.save_config();
+    }
+
+    fn output_section_for(&self, id: SectionId) -> Column<'_, Message> {
+        // 请求体结构的标题栏中可以编辑 rust 文件名
+        let header_extra = (id == SectionId::RequestStruct).then(|| {
+            text_input("rust 文件名", &self.request_file_name)
+                .on_input(Message::RequestFileNameChanged)
+                .padding(5)
+                .width(400)
+                .into()
+        });
+        self.output_section(id, header_extra)
+    }
+
+    // 输出框：标题栏（折叠按钮、标题、重新生成和复制按钮）和可折叠的编辑器
+    fn output_section<'a>(
+        &'a self,
+        id: SectionId,
+        header_extra: Option<Element<'a, Message>>,
+    ) -> Column<'a, Message> {
+        let expanded = !self.collapsed_sections.contains(&id);
+        let caret = if expanded { "▼" } else { "▶" };
+
+        let mut header = row![
+            button(text(caret).size(14))
+                .on_press(Message::ToggleSection(id))
+                .padding(5),
+            text(id.title()).size(16),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center);
+        if let Some((lines, chars)) = self.section_stats.get(&id) {
+            header = header.push(text(format!("{} 行 / {} 字符", lines, chars)).size(12));
+        }
+        if let Some(extra) = header_extra {
+            header = header.push(extra);
+        }
+        header = header
+            .push(
+                button(text("重新生成").size(14))
+                    .on_press(Message::RegenerateSection(id))
+                    .padding(5),
+            )
+            .push(
+                button(text("复制").size(14))
+                    .on_press(id.copy_message())
+                    .padding(5),
+            )
+            .push(
+                button(text("↑").size(14))
+                    .on_press(Message::MoveSectionUp(id))
+                    .padding(5),
+            )
+            .push(
+                button(text("↓").size(14))
+                    .on_press(Message::MoveSectionDown(id))
+                    .padding(5),
+            );
+
+        let mut section = column![header].spacing(5);
+        if let Some(error) = self.syntax_errors.get(&id) {
+            section = section.push(text(format!("语法错误：{}", error)).size(14).style(
+                |_theme: &Theme| text::Style {
+                    color: Some(iced::Color::from_rgb(1.0, 0.3, 0.3)),
+                },
+            ));
+        }
+        if expanded {
+            section = section.push(
+                text_editor(self.output_content(id))
+                    .on_action(id.action_message())
+                    .height(200)
+                    .highlight_with::<RustHighlighter>(self.syntax_highlighting, rust_token_format),
+            );
+        }
+        section
+    }
+
+    fn output_content(&self, id: SectionId) -> &text_editor::Content {
+        match id {
+            SectionId::EngineSync => &self.engine_sync_content,
+            SectionId::EngineAsync => &self.engine_async_content,
+            SectionId::Module => &self.module_content,
+            SectionId::RequestBuilder => &self.request_builder_content,
+            SectionId::RequestStruct => &self.request_struct_content,
+            SectionId::FromConversion => &self.from_conversion_content,
+            SectionId::TestMethod => &self.test_method_content,
+            SectionId::DbAgent => &self.db_agent_content,
+            SectionId::DbWorker => &self.db_worker_content,
+            SectionId::DbSqlite => &self.db_sqlite_content,
+            SectionId::Ffi => &self.ffi_content,
+            SectionId::Binding => &self.binding_content,
+            SectionId::Bench => &self.bench_content,
+        }
+    }
+
+    // 编辑器操作只有修改内容时才需要重新统计
+    fn perform_section_action(&mut self, id: SectionId, action: text_editor::Action) {
+        let is_edit = action.is_edit();
+        self.output_content_mut(id).perform(action);
+        if is_edit {
+            self.update_section_stats(id);
+        }
+    }
+
+    fn refresh_section_stats(&mut self) {
+        for id in SectionId::ALL {
+            self.update_section_stats(id);
+        }
+    }
+
+    // 统计输出框的行数和字符数，只在内容变化时计算，view 中直接读取
+    fn update_section_stats(&mut self, id: SectionId) {
+        let text = self.output_content(id).text();
+        let text = text.strip_suffix('\n').unwrap_or(&text);
+        if text.trim().is_empty() {
+            self.section_stats.remove(&id);
+        } else {
+            self.section_stats
+                .insert(id, (text.lines().count(), text.chars().count()));
+        }
+    }
+
+    fn output_content_mut(&mut self, id: SectionId) -> &mut text_editor::Content {
+        match id {
+            SectionId::EngineSync => &mut self.engine_sync_content,
+            SectionId::EngineAsync => &mut self.engine_async_content,
+            SectionId::Module => &mut self.module_content,
+            SectionId::RequestBuilder => &mut self.request_builder_content,
+            SectionId::RequestStruct => &mut self.request_struct_content,
+            SectionId::FromConversion => &mut self.from_conversion_content,
+            SectionId::TestMethod => &mut self.test_method_content,
+            SectionId::DbAgent => &mut self.db_agent_content,
+            SectionId::DbWorker => &mut self.db_worker_content,
+            SectionId::DbSqlite => &mut self.db_sqlite_content,
+            SectionId::Ffi => &mut self.ffi_content,
+            SectionId::Binding => &mut self.binding_content,
+            SectionId::Bench => &mut self.bench_content,
+        }
+    }
+
+    // 在输出框中查找第一个匹配项，将光标移动到匹配处并选中
+    // text_editor 没有内置搜索，这里通过逐字符移动光标实现定位
+    fn search_in_output(&mut self) {
+        let query = self.search_query.clone();
+        if query.is_empty() {
+            return;
+        }
+
+        let mut found_in = None;
+        for id in self.displayed_sections() {
+            let content = self.output_content_mut(id);
+            let text = content.text();
+            let Some(byte_offset) = text.find(&query) else {
+                continue;
+            };
+
+            let char_offset = text[..byte_offset].chars().count();
+            content.perform(text_editor::Action::Move(
+                text_editor::Motion::DocumentStart,
+            ));
+            for _ in 0..char_offset {
+                content.perform(text_editor::Action::Move(text_editor::Motion::Right));
+            }
+            for _ in 0..query.chars().count() {
+                content.perform(text_editor::Action::Select(text_editor::Motion::Right));
+            }
+            found_in = Some(id);
+            break;
+        }
+
+        let message = match found_in {
+            Some(id) => {
+                // 匹配项所在的输出框如果已折叠，则展开
+                self.collapsed_sections.remove(&id);
+                format!("已在 {} 中找到 \"{}\"", id.title(), query)
+            }
+            None => format!("未找到 \"{}\"", query),
+        };
+        // 每次输入都会搜索，只更新状态栏，不写入状态日志
+        self.status_message = message;
+    }
+
+    // 正在输入类型的输入框及其内容。text_input 不提供光标位置，按输入末尾的片段补全，
+    // 参数输入框只在最后一个参数的类型位置补全
+    fn type_completion_text(&self) -> Option<(TypeCompletionTarget, &str)> {
+        match self.type_completion? {
+            TypeCompletionTarget::Params => {
+                let text = self.raw_function_params.as_str();
+                let partial = trailing_type_token(text);
+                let param = split_top_level_params(text).pop()?;
+                let before = param[..param.len() - partial.len()].trim_end();
+                // Rust/Kotlin 的类型在冒号后面；Java 的类型在参数开头或泛型参数中
+                let at_type = param.contains(':')
+                    || (self.input_language == InputLanguage::Java
+                        && (before.is_empty() || before.ends_with(['<', ',', '('])));
+                at_type.then_some((TypeCompletionTarget::Params, text))
+            }
+            TypeCompletionTarget::Row(index) => self
+                .params
+                .get(index)
+                .map(|param| (TypeCompletionTarget::Row(index), param.param_type.as_str())),
+        }
+    }
+
+    fn type_suggestions(&self) -> Vec<String> {
+        self.type_completion_text()
+            .map(|(_, text)| match_known_types(&self.known_types, trailing_type_token(text)))
+            .unwrap_or_default()
+    }
+
+    // 候选类型按钮，显示在 target 输入框下方；没有候选时不显示
+    fn type_suggestion_row(&self, target: TypeCompletionTarget) -> Option<Element<'_, Message>> {
+        if self.type_completion != Some(target) {
+            return None;
+        }
+        let suggestions = self.type_suggestions();
+        if suggestions.is_empty() {
+            return None;
+        }
+        Some(
+            Row::with_children(suggestions.into_iter().map(|type_name| {
+                button(text(type_name.clone()).size(14))
+                    .on_press(Message::TypeSuggestionSelected(type_name))
+                    .padding(4)
+                    .into()
+            }))
+            .spacing(5)
+            .into(),
+        )
+    }
+
+    fn java_type_options(&self) -> JavaTypeOptions {
+        JavaTypeOptions {
+            timestamp_type: self.spec.resolved_timestamp_type(),
+            keep_nested_type_paths: self.spec.keep_nested_type_paths,
+        }
+    }
+}
+
+// 编译检查的结果：通过时带检查的输出框数量，失败时带 cargo 的错误输出
+#[cfg(feature = "compile-check")]
+#[derive(Debug, Clone)]
+enum CompileCheckOutcome {
+    Passed(usize),
+    Failed(String),
+    NotRun(String),
+}
+
+#[cfg(feature = "compile-check")]
+fn compile_check_dir() -> PathBuf {
+    std::env::temp_dir().join("auto_universal_sdk_compile_check")
+}
+
+// 在工程目录中执行 cargo check，测试方法只在 cfg(test) 下编译，所以检查所有 target
+#[cfg(feature = "compile-check")]
+fn run_cargo_check(dir: &Path, sections: usize) -> CompileCheckOutcome {
+    match std::process::Command::new("cargo")
+        .args([
+            "check",
+            "--offline",
+            "--quiet",
+            "--all-targets",
+            "--message-format",
+            "short",
+        ])
+        .current_dir(dir)
+        .output()
+    {
+        Ok(output) if output.status.success() => CompileCheckOutcome::Passed(sections),
+        Ok(output) => {
+            CompileCheckOutcome::Failed(String::from_utf8_lossy(&output.stderr).into_owned())
+        }
+        Err(e) => CompileCheckOutcome::NotRun(e.to_string()),
+    }
+}
+
+// cargo check 是阻塞的进程调用，放到独立线程中执行，完成后通过通道返回结果
+#[cfg(feature = "compile-check")]
+async fn run_cargo_check_in_background(dir: PathBuf, sections: usize) -> CompileCheckOutcome {
+    let (tx, rx) = iced::futures::channel::oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(run_cargo_check(&dir, sections));
+    });
+    rx.await
+        .unwrap_or_else(|_| CompileCheckOutcome::NotRun("检查线程意外退出".to_string()))
+}
+
+// 编译检查工程中各输出框对应的模块名，module 输出框按生成代码中的调用路径命名为 bugtags
+#[cfg(feature = "compile-check")]
+fn compile_check_module_name(id: SectionId) -> &'static str {
+    match id {
+        SectionId::EngineSync => "engine_sync",
+        SectionId::EngineAsync => "engine_async",
+        SectionId::Module => "bugtags",
+        SectionId::RequestBuilder => "request_builder",
+        SectionId::RequestStruct => "request_struct",
+        SectionId::FromConversion => "from_conversion",
+        SectionId::TestMethod => "test_method",
+        SectionId::DbAgent => "db_agent",
+        SectionId::DbWorker => "db_worker",
+        SectionId::DbSqlite => "db_sqlite",
+        SectionId::Ffi => "ffi",
+        SectionId::Binding => "binding",
+        SectionId::Bench => "bench",
+    }
+}
+
+// 输出框中的方法所属的占位类型，方法需要放进该类型的 impl 块才能编译；不是方法时返回 None
+#[cfg(feature = "compile-check")]
+fn compile_check_impl_target(id: SectionId) -> Option<&'static str> {
+    match id {
+        SectionId::EngineSync => Some("SyncEngine"),
+        SectionId::EngineAsync => Some("Engine"),
+        SectionId::RequestBuilder => Some("RequestBuilder"),
+        SectionId::DbAgent => Some("DbAgent"),
+        SectionId::DbWorker => Some("DbWorker"),
+        SectionId::DbSqlite => Some("DbSqlite"),
+        _ => None,
+    }
+}
+
+// 写入编译检查工程：每个输出框一个模块，方法包在对应占位类型的 impl 块中；
+// engine 已经包裹在 impl engine_type 中时，在该模块中把 engine_type 定义为占位类型的别名
+#[cfg(feature = "compile-check")]
+fn write_compile_check_project(
+    dir: &Path,
+    sections: &[(SectionId, String)],
+    engine_type: Option<&str>,
+) -> std::io::Result<()> {
+    let src = dir.join("src");
+    // 清掉上次检查留下的模块
+    if src.exists() {
+        std::fs::remove_dir_all(&src)?;
+    }
+    std::fs::create_dir_all(&src)?;
+    std::fs::write(dir.join("Cargo.toml"), COMPILE_CHECK_MANIFEST)?;
+
+    // 生成代码中 use rust_universal_logger::err 引用的是外部 crate，用本地的占位 crate 代替
+    let logger_src = dir.join("rust_universal_logger").join("src");
+    std::fs::create_dir_all(&logger_src)?;
+    std::fs::write(
+        dir.join("rust_universal_logger").join("Cargo.toml"),
+        "[package]\nname = \"rust_universal_logger\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )?;
+    std::fs::write(
+        logger_src.join("lib.rs"),
+        "#[macro_export]\nmacro_rules! err {\n    ($e:expr) => {\n        $e\n    };\n}\n",
+    )?;
+
+    let all_code = sections
+        .iter()
+        .map(|(_, code)| code.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    std::fs::write(
+        src.join("stubs.rs"),
+        format!(
+            "{}{}",
+            COMPILE_CHECK_STUBS,
+            compile_check_pb_stubs(&all_code)
+        ),
+    )?;
+
+    let mut lib = String::from(COMPILE_CHECK_LIB);
+    for (id, code) in sections {
+        let name = compile_check_module_name(*id);
+        let wrapped_engine =
+            engine_type.is_some() && matches!(id, SectionId::EngineSync | SectionId::EngineAsync);
+        let body = match (compile_check_impl_target(*id), engine_type) {
+            (Some(target), Some(engine_type)) if wrapped_engine => {
+                format!("type {} = {};\n\n{}", engine_type, target, code)
+            }
+            (Some(target), _) => format!("impl {} {{\n{}\n}}\n", target, code),
+            (None, _) => code.clone(),
+        };
+        std::fs::write(
+            src.join(format!("{}.rs", name)),
+            format!("use crate::stubs::*;\nuse crate::*;\n\n{}", body),
+        )?;
+        lib.push_str(&format!("mod {};\npub(crate) use {}::*;\n", name, name));
+    }
+    std::fs::write(src.join("lib.rs"), lib)
+}
+
+// 生成代码中引用的 protobuf 消息 (PbXxx)，只提供 new 和 write_to_bytes
+#[cfg(feature = "compile-check")]
+fn compile_check_pb_stubs(code: &str) -> String {
+    let mut names: Vec<&str> = code
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|token| {
+            token
+                .strip_prefix("Pb")
+                .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_uppercase()))
+        })
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+        .into_iter()
+        .map(|name| {
+            format!(
+                "\n#[derive(Default)]\npub struct {0};\n\nimpl {0} {{\n    pub fn new() -> Self {{\n        Self\n    }}\n\n    pub fn write_to_bytes(&self) -> protobuf::Result<Vec<u8>> {{\n        Ok(Vec::new())\n    }}\n}}\n",
+                name
+            )
+        })
+        .collect()
+}
+
+// 编译检查工程的依赖：生成代码用到的第三方 crate，需要本地 cargo 缓存中已有
+#[cfg(feature = "compile-check")]
+const COMPILE_CHECK_MANIFEST: &str = r#"[package]
+name = "compile_check"
+version = "0.1.0"
+edition = "2021"
+
+[workspace]
+
+[dependencies]
+async-trait = "0.1"
+bytes = "1"
+protobuf = "3"
+tokio = { version = "1", features = ["full"] }
+tokio-util = "0.7"
+rust_universal_logger = { path = "rust_universal_logger" }
+"#;
+
+// 编译检查工程的 lib.rs：生成代码按 crate:: 路径引用的模块都指向 stubs 中的占位定义
+#[cfg(feature = "compile-check")]
+const COMPILE_CHECK_LIB: &str = r#"#![allow(dead_code, unused)]
+
+#[macro_use]
+mod stubs;
+
+mod engine_context {
+    pub use crate::stubs::EngineContext;
+}
+
+mod engine_def {
+    pub use crate::stubs::{EngineError, EngineResult};
+}
+
+mod rmtp {
+    pub mod request {
+        pub mod request_trait {
+            pub use crate::stubs::Request;
+        }
+    }
+
+    pub mod rmtp_def {
+        pub use crate::stubs::RmtpQos;
+    }
+}
+
+"#;
+
+// 编译检查工程的占位定义，只保证生成代码引用的类型、方法和宏存在，不关心具体实现
+#[cfg(feature = "compile-check")]
+const COMPILE_CHECK_STUBS: &str = r#"pub use rust_universal_logger::err;
+pub use std::future::Future;
+pub use std::pin::Pin;
+pub use std::sync::{Arc, LazyLock};
+pub use tokio::sync::{mpsc, oneshot};
+pub use tokio::task::{spawn_blocking, JoinHandle};
+pub use tokio_util::sync::CancellationToken;
+
+macro_rules! trace_i_json {
+    ($($arg:tt)*) => {};
+}
+
+macro_rules! log_db_i {
+    ($($arg:tt)*) => {};
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {};
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EngineError {
+    Success,
+    InvalidParam,
+    InvalidArgument,
+    NetDataParserFailed,
+    NotFound,
+    GroupNotExist,
+    Cancelled,
+    Timeout,
+    RequestTimeout,
+}
+
+impl std::fmt::Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl EngineError {
+    pub fn code(&self) -> i32 {
+        0
+    }
+}
+
+pub type EngineResult<T> = Result<T, EngineError>;
+
+#[derive(Debug)]
+pub enum DbError {
+    NotOpen,
+}
+
+impl std::fmt::Display for DbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl From<DbError> for EngineError {
+    fn from(_error: DbError) -> Self {
+        EngineError::NotFound
+    }
+}
+
+#[derive(Debug)]
+pub struct DbErrorInfo;
+
+impl DbErrorInfo {
+    pub fn from_join_error(_error: tokio::task::JoinError) -> Self {
+        Self
+    }
+
+    pub fn from_lock<T>(_error: T) -> Self {
+        Self
+    }
+}
+
+impl std::fmt::Display for DbErrorInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DbErrorInfo")
+    }
+}
+
+#[derive(Clone)]
+pub struct Logger;
+
+impl Logger {
+    pub fn generate_trace_id(&self) -> String {
+        String::new()
+    }
+}
+
+pub enum RmtpQos {
+    QosAtLastOnce,
+}
+
+pub struct RmtpQuery;
+
+impl RmtpQuery {
+    pub fn msg_uid(&self) -> String {
+        String::new()
+    }
+}
+
+#[async_trait::async_trait]
+pub trait Request: Send {
+    fn get_method(&self) -> String;
+
+    fn get_qos(&self) -> RmtpQos;
+
+    async fn deal_with_response(
+        self: Box<Self>,
+        ctx: &Arc<EngineContext>,
+        code: EngineError,
+        timestamp: i64,
+        msg_uid: String,
+        pb_data: Option<Vec<u8>>,
+    );
+
+    fn get_pb_data(&self) -> Vec<u8>;
+}
+
+pub struct RequestBuilder;
+
+impl RequestBuilder {
+    pub fn build_query(
+        &self,
+        _method: String,
+        _target: &str,
+        _qos: RmtpQos,
+        _request: Box<dyn Request>,
+    ) -> RmtpQuery {
+        RmtpQuery
+    }
+
+    pub fn insert_callback<CB>(&self, _msg_uid: String, _cb: CB) {}
+}
+
+pub struct EngineContext {
+    logger: Logger,
+    request_builder: RequestBuilder,
+    db_agent: DbAgent,
+}
+
+impl EngineContext {
+    pub fn logger(&self) -> &Logger {
+        &self.logger
+    }
+
+    pub fn request_builder(&self) -> &RequestBuilder {
+        &self.request_builder
+    }
+
+    pub fn db_agent(&self) -> &DbAgent {
+        &self.db_agent
+    }
 
-        // engine_async.rs 输出框
-        let engine_async_section = column![
-            row![
-                text("engine_async.rs").size(16),
-                button(text("复制").size(14))
-                    .on_press(Message::CopyEngineAsyncToClipboard)
-                    .padding(5),
-            ]
-            .spacing(10),
-            text_editor(&self.engine_async_content)
-                .on_action(Message::EngineAsyncAction)
-                .height(200),
-        ]
-        .spacing(5);
+    pub fn runtime(&self) -> &tokio::runtime::Runtime {
+        unimplemented!()
+    }
 
-        // module 文件输出框
-        let module_section = column![
-            row![
-                text("module 文件").size(16),
-                button(text("复制").size(14))
-                    .on_press(Message::CopyModuleToClipboard)
-                    .padding(5),
-            ]
-            .spacing(10),
-            text_editor(&self.module_content)
-                .on_action(Message::ModuleAction)
-                .height(200),
-        ]
-        .spacing(5);
+    pub async fn send_query(&self, _query: RmtpQuery) {}
 
-        // request_builder 文件输出框（仅在网络请求模式下显示）
-        let request_builder_section = if self.operation_type == Some(OperationType::Network) {
-            column![
-                row![
-                    text("request_builder 文件").size(16),
-                    button(text("复制").size(14))
-                        .on_press(Message::CopyRequestBuilderToClipboard)
-                        .padding(5),
-                ]
-                .spacing(10),
-                text_editor(&self.request_builder_content)
-                    .on_action(Message::RequestBuilderAction)
-                    .height(200),
-            ]
-            .spacing(5)
-        } else {
-            column![]
-        };
+    pub fn take_callback<CB>(&self, _msg_uid: String) -> Option<CB> {
+        None
+    }
+}
 
-        // 请求体结构输出框（仅在有请求体名称时显示）
-        let request_struct_section = if !self.request_body_name.is_empty() {
-            column![
-                row![
-                    text("请求体结构").size(16),
-                    text_input("rust 文件名", &self.request_file_name)
-                        .on_input(Message::RequestFileNameChanged)
-                        .padding(5)
-                        .width(400),
-                    button(text("复制").size(14))
-                        .on_press(Message::CopyRequestStructToClipboard)
-                        .padding(5),
-                ]
-                .spacing(10),
-                text_editor(&self.request_struct_content)
-                    .on_action(Message::RequestStructAction)
-                    .height(200),
-            ]
-            .spacing(5)
-        } else {
-            column![]
-        };
+pub struct Engine {
+    pub ctx: Arc<EngineContext>,
+}
 
-        // 测试方法输出框
-        let test_method_section = column![
-            row![
-                text("测试方法").size(16),
-                button(text("复制").size(14))
-                    .on_press(Message::CopyTestMethodToClipboard)
-                    .padding(5),
-            ]
-            .spacing(10),
-            text_editor(&self.test_method_content)
-                .on_action(Message::TestMethodAction)
-                .height(200),
-        ]
-        .spacing(5);
+pub struct SyncEngine {
+    pub engine: Arc<Engine>,
+}
 
-        // 数据库函数输出框（仅在勾选生成数据库函数时显示）
-        let db_sections = if self.generate_db_functions {
-            column![
-                column![
-                    row![
-                        text("db_agent.rs (A函数)").size(16),
-                        button(text("复制").size(14))
-                            .on_press(Message::CopyDbAgentToClipboard)
-                            .padding(5),
-                    ]
-                    .spacing(10),
-                    text_editor(&self.db_agent_content)
-                        .on_action(Message::DbAgentAction)
-                        .height(200),
-                ]
-                .spacing(5),
-                column![
-                    row![
-                        text("db_worker.rs (B函数)").size(16),
-                        button(text("复制").size(14))
-                            .on_press(Message::CopyDbWorkerToClipboard)
-                            .padding(5),
-                    ]
-                    .spacing(10),
-                    text_editor(&self.db_worker_content)
-                        .on_action(Message::DbWorkerAction)
-                        .height(200),
-                ]
-                .spacing(5),
-                column![
-                    row![
-                        text("db_sqlite.rs (C函数)").size(16),
-                        button(text("复制").size(14))
-                            .on_press(Message::CopyDbSqliteToClipboard)
-                            .padding(5),
-                    ]
-                    .spacing(10),
-                    text_editor(&self.db_sqlite_content)
-                        .on_action(Message::DbSqliteAction)
-                        .height(200),
-                ]
-                .spacing(5),
-            ]
-        } else {
-            column![]
-        };
+impl SyncEngine {
+    pub fn post<F>(&self, _future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+    }
 
-        let content = column![
-            title,
-            project_path_input,
-            function_name_input,
-            function_params_input,
-            callback_return_input,
-            request_body_input,
-            operation_type_picker,
-            params_to_request_checkbox,
-            generate_db_functions_checkbox,
-            row![generate_button, clear_button].spacing(10),
-            status,
-            engine_sync_section,
-            engine_async_section,
-            module_section,
-            request_builder_section,
-            request_struct_section,
-            test_method_section,
-            db_sections,
-        ]
-        .spacing(15)
-        .padding(20)
-        .width(Length::Fill);
+    pub fn cb_pool_once<CB>(&self, cb: CB) -> CB {
+        cb
+    }
 
-        container(scrollable(content)).center_x(Length::Fill).into()
+    pub fn cb_pool<CB>(&self, cb: CB) -> CB {
+        cb
     }
+}
 
-    fn generate_engine_sync_function(&self, rust_function_name: &str) -> String {
-        let cb_type = if self.callback_return_type.is_empty() {
-            "()".to_string()
-        } else {
-            self.callback_return_type.clone()
-        };
+pub struct DbAgent {
+    pub db_worker: Arc<tokio::sync::RwLock<DbWorker>>,
+    pub runtime: tokio::runtime::Runtime,
+}
 
-        let cleaned_params = self.clean_params(&self.function_params);
-        let str_conversions = self.generate_str_to_string_conversions();
-
-        match self.operation_type {
-            Some(OperationType::Database) => {
-                format!(
-                    r#"pub fn {}<CB>(&self, {}, cb: CB)
-where
-    CB: FnOnce(Result<{}, EngineError>) + Send + 'static,
-{{
-    let engine = self.engine.clone();
-    let cb = self.cb_pool_once(cb);
-{}
-    self.post(async move {{
-        let ret = engine.{}({}).await;
-        cb(ret);
-    }});
-}}"#,
-                    rust_function_name,
-                    cleaned_params,
-                    cb_type,
-                    str_conversions,
-                    rust_function_name,
-                    self.extract_param_names_with_ref()
-                )
-            }
-            Some(OperationType::Network) => {
-                format!(
-                    r#"pub fn {}<CB>(&self, {}, cb: CB)
-where
-    CB: FnOnce(Result<{}, EngineError>) + Send + 'static,
-{{
-    let engine = self.engine.clone();
-    let callback = self.cb_pool_once(cb);
-{}
-    self.post(async move {{
-        engine.{}({}, callback).await;
-    }});
-}}"#,
-                    rust_function_name,
-                    cleaned_params,
-                    cb_type,
-                    str_conversions,
-                    rust_function_name,
-                    self.extract_param_names_with_ref()
-                )
-            }
-            None => String::new(),
-        }
+impl DbAgent {
+    pub async fn execute<T>(
+        &self,
+        _task: Pin<Box<dyn Future<Output = ()> + Send>>,
+        _resp_rx: oneshot::Receiver<Result<T, DbError>>,
+    ) -> Result<T, EngineError> {
+        unimplemented!()
     }
+}
 
-    fn generate_engine_async_function(&self, rust_function_name: &str) -> String {
-        let cb_type = if self.callback_return_type.is_empty() {
-            "()".to_string()
-        } else {
-            self.callback_return_type.clone()
-        };
+pub struct DbWorker {
+    pub db_sqlite_lock: tokio::sync::RwLock<Option<DbSqlite>>,
+}
 
-        let params_with_ref = self.add_ref_to_str_params();
-        let param_names = self.extract_param_names();
+impl DbWorker {
+    pub fn callback_error(&self, _method_name: &str, error: DbError) -> DbError {
+        error
+    }
 
-        // 生成 match 表达式的 Ok 分支
-        let ok_match_pattern = if cb_type == "()" {
-            "Ok(()) => \"\".to_string()".to_string()
-        } else {
-            "Ok(_) => \"\".to_string()".to_string()
-        };
+    pub fn callback<T>(&self, _method_name: &str, ret: Result<T, DbErrorInfo>) -> Result<T, DbError> {
+        ret.map_err(|_| DbError::NotOpen)
+    }
+}
 
-        match self.operation_type {
-            Some(OperationType::Network) => {
-                format!(
-                    r#"pub async fn {}<CB>(&self, {}, cb: CB)
-where
-    CB: FnOnce(Result<{}, EngineError>) + Send + 'static,
-{{
-    let trace_id = self.ctx.logger().generate_trace_id();
-    trace_i_json!(self.ctx.logger(), "P-{}-T", trace_id);
-    let logger = self.ctx.logger().clone();
-    let cb = move |ret: Result<{}, EngineError>| {{
-        let str = match &ret {{
-            {},
-            Err(e) => e.to_string(),
-        }};
-        trace_i_json!(logger, "P-{}-R", trace_id, "result", &str);
-        cb(ret);
-    }};
-    bugtags::{}(&self.ctx, {}, cb).await;
-}}"#,
-                    rust_function_name,
-                    params_with_ref,
-                    cb_type,
-                    rust_function_name,
-                    cb_type,
-                    ok_match_pattern,
-                    rust_function_name,
-                    rust_function_name,
-                    param_names
-                )
-            }
-            Some(OperationType::Database) => {
-                format!(
-                    r#"pub async fn {}(&self, {}) -> Result<{}, EngineError> {{
-    let trace_id = self.ctx.logger().generate_trace_id();
-    trace_i_json!(self.ctx.logger(), "P-{}-T", trace_id);
-    let ret = bugtags::{}(&self.ctx, {}).await;
-    let str = match &ret {{
-        Ok(_) => "".to_string(),
-        Err(e) => e.to_string(),
-    }};
-    trace_i_json!(self.ctx.logger(), "P-{}-R", trace_id, "result", str);
-    ret
-}}"#,
-                    rust_function_name,
-                    params_with_ref,
-                    cb_type,
-                    rust_function_name,
-                    rust_function_name,
-                    param_names,
-                    rust_function_name
-                )
-            }
-            None => String::new(),
-        }
+pub struct DbSqlite {
+    pub db_lock: Arc<std::sync::RwLock<()>>,
+}
+
+pub struct ServerApi;
+
+impl ServerApi {
+    pub fn new() -> Self {
+        Self
     }
 
-    fn generate_module_function(&self, rust_function_name: &str) -> String {
-        let cb_type = if self.callback_return_type.is_empty() {
-            "()".to_string()
-        } else {
-            self.callback_return_type.clone()
-        };
+    pub async fn is_chatroom_exist(&self, _room_name: &str) -> bool {
+        true
+    }
 
-        let params_with_ref = self.add_ref_to_str_params();
-        let param_names = self.extract_param_names();
+    pub async fn create_chatroom(&self, _room_name: &str) {}
+}
 
-        match self.operation_type {
-            Some(OperationType::Network) => {
-                // 始终传递所有参数给 build_xxx_request 方法
-                let build_params = if param_names.is_empty() {
-                    "cb".to_string()
-                } else {
-                    format!("{}, cb", param_names)
-                };
+pub struct Tester {
+    pub engine: Engine,
+}
 
-                format!(
-                    r#"pub(crate) async fn {}<CB>(
-    ctx: &Arc<EngineContext>,
-    {},
-    cb: CB,
-)
-where
-    CB: FnOnce(Result<{}, EngineError>) + Send + 'static,
-{{
-    let query = ctx
-        .request_builder()
-        .build_{}_request({});
-    ctx.send_query(query).await;
-}}"#,
-                    rust_function_name, params_with_ref, cb_type, rust_function_name, build_params
-                )
-            }
-            Some(OperationType::Database) => {
-                format!(
-                    r#"pub(crate) async fn {}(
-    ctx: &Arc<EngineContext>,
-    {},
-) -> Result<{}, EngineError> {{
-    ctx.db_agent()
-        .{}({})
-        .await
-}}"#,
-                    rust_function_name, params_with_ref, cb_type, rust_function_name, param_names
-                )
-            }
-            None => String::new(),
-        }
+impl Tester {
+    pub async fn connect(&self) -> Result<(), EngineError> {
+        Ok(())
     }
+}
 
-    fn generate_request_builder_function(&self, rust_function_name: &str) -> String {
-        let cb_type = if self.callback_return_type.is_empty() {
-            "()".to_string()
+pub static SHARED_RUNTIME: LazyLock<tokio::runtime::Runtime> =
+    LazyLock::new(|| tokio::runtime::Runtime::new().unwrap());
+pub static TESTER_A: LazyLock<Tester> = LazyLock::new(|| unimplemented!());
+pub static TESTER_B: LazyLock<Tester> = LazyLock::new(|| unimplemented!());
+"#;
+
+// 在已有文件中替换 key 对应的生成块 (从 @generated 头到结束标记)，标记之外的用户代码保持不变；
+// 找不到完整的标记块时把新块追加到文件末尾
+fn splice_codegen_block(existing: &str, block: &str, key: &str) -> String {
+    let header = format!("{} ({})", CODEGEN_HEADER_PREFIX, key);
+    let end_marker = format!("{} ({})", CODEGEN_END_MARKER_PREFIX, key);
+
+    let range = existing.find(&header).and_then(|start| {
+        let end = start + existing[start..].find(&end_marker)? + end_marker.len();
+        // 结束标记所在行的换行也属于生成块
+        let end = if existing[end..].starts_with('\n') {
+            end + 1
         } else {
-            self.callback_return_type.clone()
+            end
         };
+        Some(start..end)
+    });
 
-        // 使用规范化的参数处理方法
-        let params_with_ref = self.normalize_params_for_request_builder();
+    match range {
+        Some(range) => format!(
+            "{}{}{}",
+            &existing[..range.start],
+            block,
+            &existing[range.end..]
+        ),
+        None if existing.trim().is_empty() => block.to_string(),
+        None => format!("{}\n\n{}", existing.trim_end(), block),
+    }
+}
 
-        // 如果没有请求体名称，返回空字符串
-        if self.request_body_name.is_empty() {
-            return String::new();
+// 在光标 (行, 列) 处插入生成的代码：光标在行首空白中时插在该行之前，在行中间时插在该行之后的新行；
+// 代码按插入位置的缩进对齐，前后各保留一个空行 (紧挨着 { 或 } 时不加)
+fn splice_at_cursor(file: &str, (line, column): (usize, usize), code: &str) -> String {
+    let lines: Vec<&str> = file.lines().collect();
+    let line = line.min(lines.len());
+    let current = lines.get(line).copied().unwrap_or_default();
+    let before_cursor: String = current.chars().take(column).collect();
+    let index = if before_cursor.trim().is_empty() {
+        line
+    } else {
+        line + 1
+    };
+
+    let leading_whitespace = |text: &str| text[..text.len() - text.trim_start().len()].to_string();
+    // 插入位置后面是 } 时与块内对齐，否则与前一个非空行对齐，前一行以 { 结尾时再缩进一级
+    let indent = match (
+        lines[..index].iter().rev().find(|l| !l.trim().is_empty()),
+        lines[index..].iter().find(|l| !l.trim().is_empty()),
+    ) {
+        (_, Some(next)) if next.trim_start().starts_with('}') => {
+            format!("{}    ", leading_whitespace(next))
+        }
+        (Some(prev), _) if prev.trim_end().ends_with('{') => {
+            format!("{}    ", leading_whitespace(prev))
         }
+        (Some(prev), _) => leading_whitespace(prev),
+        (None, Some(next)) => leading_whitespace(next),
+        (None, None) => String::new(),
+    };
+
+    let mut head: Vec<&str> = lines[..index].to_vec();
+    while head.last().is_some_and(|l| l.trim().is_empty()) {
+        head.pop();
+    }
+    let mut tail: &[&str] = &lines[index..];
+    while tail.first().is_some_and(|l| l.trim().is_empty()) {
+        tail = &tail[1..];
+    }
 
-        // 生成 Pb 结构体名称（添加 "Pb" 前缀）
-        let pb_request_name = format!("Pb{}", self.request_body_name);
+    let mut result: Vec<String> = head.iter().map(|l| l.to_string()).collect();
+    if head.last().is_some_and(|l| !l.trim_end().ends_with('{')) {
+        result.push(String::new());
+    }
+    result.extend(code.trim_end().lines().map(|l| {
+        if l.trim().is_empty() {
+            String::new()
+        } else {
+            format!("{}{}", indent, l)
+        }
+    }));
+    if tail
+        .first()
+        .is_some_and(|l| !l.trim_start().starts_with('}'))
+    {
+        result.push(String::new());
+    }
+    result.extend(tail.iter().map(|l| l.to_string()));
 
-        // 请求体结构名称（不带 "Pb" 前缀）
-        let request_name = &self.request_body_name;
+    let mut spliced = result.join("\n");
+    if file.ends_with('\n') || file.is_empty() {
+        spliced.push('\n');
+    }
+    spliced
+}
 
-        // 构建函数名：在 rust_function_name 前添加 "build_"
-        let build_function_name = format!("build_{}_request", rust_function_name);
+// 用系统文件管理器打开目录 (macOS 用 Finder，Windows 用资源管理器，Linux 用 xdg-open)
+fn open_in_file_manager(dir: &Path) -> std::io::Result<()> {
+    let program = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(target_os = "windows") {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+    std::process::Command::new(program).arg(dir).spawn()?;
+    Ok(())
+}
 
-        format!(
-            r#"pub(crate) fn {}<CB>(
-    &self,
-    {},
-    cb: CB,
-) -> RmtpQuery
-where
-    CB: FnOnce(Result<{}, EngineError>) + Send + 'static,
-{{
-    let mut pb_req = {}::new();
-    let req = {}::new(pb_req, cb);
-    self.build_query(req.get_method(), "", req.get_qos(), Box::new(req))
-}}"#,
-            build_function_name, params_with_ref, cb_type, pb_request_name, request_name
-        )
+// 按操作类型的固定顺序列出生成数量，例如 "网络请求 12，数据库操作 5"
+fn format_generation_counts(counts: &HashMap<OperationType, u64>) -> String {
+    let parts: Vec<String> = OperationType::ALL
+        .iter()
+        .filter_map(|operation_type| {
+            let count = counts.get(operation_type).copied().unwrap_or(0);
+            (count > 0).then(|| format!("{} {}", operation_type, count))
+        })
+        .collect();
+    if parts.is_empty() {
+        "暂无".to_string()
+    } else {
+        parts.join("，")
     }
+}
 
-    // 根据参数类型规范化参数名称
-    fn normalize_param_name(&self, param_name: &str, param_type: &str) -> String {
-        // 如果类型是 ConversationType 或 DbConversationType，统一使用 conv_type
-        if param_type == "ConversationType" || param_type == "DbConversationType" {
-            "conv_type".to_string()
-        } else {
-            param_name.to_string()
+// 模块名 (也是文件名) 需要匹配 ^[a-z_][a-z0-9_]*$
+fn is_valid_module_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_lowercase() || c == '_')
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+// 文本末尾正在输入的标识符片段，作为类型自动补全的前缀
+fn trailing_type_token(text: &str) -> &str {
+    let start = text
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+        .last()
+        .map_or(text.len(), |(i, _)| i);
+    &text[start..]
+}
+
+// 以 partial 开头 (不区分大小写) 的已知类型，已经完整输入的类型不再提示
+fn match_known_types(known_types: &str, partial: &str) -> Vec<String> {
+    if partial.is_empty() {
+        return Vec::new();
+    }
+    let partial_lower = partial.to_lowercase();
+    let mut matches: Vec<String> = Vec::new();
+    for type_name in known_types.split(',').map(str::trim) {
+        if type_name.is_empty() || type_name == partial || matches.iter().any(|m| m == type_name) {
+            continue;
+        }
+        if type_name.to_lowercase().starts_with(&partial_lower) {
+            matches.push(type_name.to_string());
         }
     }
+    matches.truncate(TYPE_SUGGESTION_LIMIT);
+    matches
+}
 
-    // 规范化参数，确保格式为 "name: type"
-    fn normalize_params_for_request_builder(&self) -> String {
-        self.clean_params(&self.function_params)
-            .split(',')
-            .filter_map(|param| {
-                let trimmed = param.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
-
-                // 分割参数为名称和类型
-                let parts: Vec<&str> = trimmed.split(':').map(|s| s.trim()).collect();
-                if parts.len() != 2 {
-                    return Some(trimmed.to_string());
-                }
+// 无效文件名的修正建议：转为下划线命名，其他字符视为分隔符，数字开头时加 _ 前缀
+fn sanitize_module_name(name: &str) -> String {
+    let snake_case: String = to_snake_case(name)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let sanitized = snake_case
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+        .to_ascii_lowercase();
+    if sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", sanitized)
+    } else {
+        sanitized
+    }
+}
 
-                let param_name = parts[0];
-                let mut param_type = parts[1].trim_end_matches(',').trim();
+// 检查 <> 和 () 是否成对出现且顺序正确，-> 中的 > 不计入
+fn brackets_balanced(s: &str) -> bool {
+    let mut angle = 0i32;
+    let mut paren = 0i32;
+    let mut prev = None;
+
+    for c in s.chars() {
+        match c {
+            '<' => angle += 1,
+            '>' if prev != Some('-') => angle -= 1,
+            '(' => paren += 1,
+            ')' => paren -= 1,
+            _ => {}
+        }
+        if angle < 0 || paren < 0 {
+            return false;
+        }
+        prev = Some(c);
+    }
 
-                // 如果类型是 String，转换为 &str
-                if param_type == "String" {
-                    param_type = "&str";
-                }
+    angle == 0 && paren == 0
+}
 
-                // 规范化参数名称
-                let normalized_name = self.normalize_param_name(param_name, param_type);
+// Java 类型转换的可配置项
+struct JavaTypeOptions {
+    // Date/Instant 等时间类型对应的 Rust 类型，SDK 中时间戳统一存为毫秒数
+    timestamp_type: String,
+    // 内部类保留外部类路径 (Outer::Inner)，默认只取最后一段
+    keep_nested_type_paths: bool,
+}
 
-                // 返回正确格式: name: type
-                Some(format!("{}: {}", normalized_name, param_type))
-            })
-            .collect::<Vec<_>>()
-            .join(", ")
+impl Default for JavaTypeOptions {
+    fn default() -> Self {
+        Self {
+            timestamp_type: Config::default().timestamp_type,
+            keep_nested_type_paths: false,
+        }
     }
+}
 
-    fn generate_request_struct(&self) -> String {
-        let cb_type = if self.callback_return_type.is_empty() {
-            "()".to_string()
-        } else {
-            self.callback_return_type.clone()
-        };
+struct JavaSignature {
+    function_name: String,
+    params: String,
+    callback_return_type: String,
+}
 
-        let pb_request_name = format!("Pb{}", self.request_body_name);
+// 解析单行 Java 方法签名，例如：
+// public void getGroupInfo(String groupId, ResultCallback<GroupInfo> callback);
+// 回调参数会被去掉，其余参数转换为 Rust 风格
+fn parse_java_signature(line: &str, options: &JavaTypeOptions) -> Option<JavaSignature> {
+    let line = line.trim().trim_end_matches(['{', ';']).trim();
+    if !brackets_balanced(line) {
+        return None;
+    }
+    let open = line.find('(')?;
+    let close = line.rfind(')')?;
+    if close < open {
+        return None;
+    }
 
-        // 根据开关状态决定是否生成额外的成员变量
-        let (extra_fields, extra_new_params, extra_field_inits) = if self.pass_params_to_request {
-            // 开关打开，生成额外的成员变量
-            (
-                self.generate_struct_fields(),
-                self.generate_new_params(),
-                self.generate_field_inits(),
-            )
-        } else {
-            // 开关关闭，不生成额外的成员变量
-            (String::new(), String::new(), String::new())
-        };
+    // 括号前的最后一个单词是函数名
+    let function_name = line[..open].split_whitespace().last()?;
+    if !function_name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return None;
+    }
 
-        // 决定结构体成员和 new 方法的内容
-        let struct_fields = if extra_fields.is_empty() {
-            format!("    pb_req: {},\n    cb: CB,", pb_request_name)
-        } else {
-            format!(
-                "    pb_req: {},\n    cb: CB,\n{}",
-                pb_request_name, extra_fields
-            )
-        };
+    let (callback_params, java_params): (Vec<&str>, Vec<&str>) =
+        split_top_level_params(&line[open + 1..close])
+            .into_iter()
+            .partition(|param| is_java_callback_param(param));
 
-        let new_params = if extra_new_params.is_empty() {
-            format!("pb_req: {}, cb: CB", pb_request_name)
-        } else {
-            format!("pb_req: {}, cb: CB, {}", pb_request_name, extra_new_params)
-        };
+    let callback_return_type = callback_params
+        .first()
+        .and_then(|param| parse_java_callback_return_type(param, options))
+        .unwrap_or_default();
 
-        let field_init = if extra_field_inits.is_empty() {
-            "Self { pb_req, cb }".to_string()
-        } else {
-            format!("Self {{ pb_req, cb, {} }}", extra_field_inits)
-        };
+    Some(JavaSignature {
+        function_name: function_name.to_string(),
+        params: convert_java_params_to_rust(&java_params.join(", "), options),
+        callback_return_type,
+    })
+}
 
-        format!(
-            r#"use crate::engine_context::EngineContext;
-use crate::engine_def::{{EngineError}};
-use crate::rmtp::request::request_trait::Request;
-use crate::rmtp::rmtp_def::RmtpQos;
-use async_trait::async_trait;
-use protobuf::Message;
-use rust_universal_logger::err;
-use std::sync::Arc;
-
-pub(crate) struct {}<CB>
-where
-    CB: FnOnce(Result<{}, EngineError>) + Send + 'static,
-{{
-{}
-}}
-
-impl<CB> {}<CB>
-where
-    CB: FnOnce(Result<{}, EngineError>) + Send + 'static,
-{{
-    pub(crate) fn new({}) -> Self {{
-        {}
-    }}
-}}
-
-#[async_trait]
-impl<CB> Request for {}<CB>
-where
-    CB: FnOnce(Result<{}, EngineError>) + Send + 'static,
-{{
-    fn get_method(&self) -> String {{
-        "".to_string()
-    }}
-
-    fn get_qos(&self) -> RmtpQos {{
-        RmtpQos::QosAtLastOnce
-    }}
+// 把 "名称: 类型" 形式的参数字符串拆成表格行，没有冒号的参数只填名称
+fn params_to_rows(params: &str) -> Vec<ParamRow> {
+    split_top_level_params(params)
+        .into_iter()
+        .map(|param| match param.split_once(':') {
+            Some((name, param_type)) => ParamRow {
+                name: name.trim().to_string(),
+                param_type: param_type.trim().to_string(),
+            },
+            None => ParamRow {
+                name: param.to_string(),
+                param_type: String::new(),
+            },
+        })
+        .collect()
+}
 
-    async fn deal_with_response(
-        self: Box<Self>,
-        ctx: &Arc<EngineContext>,
-        code: EngineError,
-        timestamp: i64,
-        msg_uid: String,
-        pb_data: Option<Vec<u8>>,
-    ) {{
-        if EngineError::Success != code {{
-            (self.cb)(Err(code));
-            return;
-        }}
-
-        let pb_data = match pb_data {{
-            Some(pb_data) => pb_data,
-            None => return (self.cb)(Err(err!(EngineError::NetDataParserFailed))),
-        }};
-
-        // if EngineError::Success == code {{
-        //     (self.cb)(Ok(()));
-        // }} else {{
-        //     (self.cb)(Err(code));
-        // }}
-        
-        // TODO: 解析响应数据
-        // let ret = ...;
-        // (self.cb)(Ok(ret));
-    }}
-
-    fn get_pb_data(&self) -> Vec<u8> {{
-        self.pb_req.write_to_bytes().unwrap_or_default()
-    }}
-}}"#,
-            self.request_body_name,
-            cb_type,
-            struct_fields,
-            self.request_body_name,
-            cb_type,
-            new_params,
-            field_init,
-            self.request_body_name,
-            cb_type
-        )
-    }
+// 把表格行拼接回参数字符串，名称或类型还没填完的行先跳过
+fn rows_to_params(rows: &[ParamRow]) -> String {
+    rows.iter()
+        .filter(|row| !row.name.trim().is_empty() && !row.param_type.trim().is_empty())
+        .map(|row| format!("{}: {}", row.name.trim(), row.param_type.trim()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
-    fn generate_test_method(&self, rust_function_name: &str) -> String {
-        let param_definitions = self.generate_test_param_definitions();
-        let param_names = self.extract_param_names_only();
+// 缩放比例限制在 UI_SCALE_RANGE 内，并去掉连续加减步长产生的浮点误差
+fn clamp_ui_scale(scale: f32) -> f32 {
+    let (min, max) = UI_SCALE_RANGE;
+    ((scale * 100.0).round() / 100.0).clamp(min, max)
+}
 
-        match self.operation_type {
-            Some(OperationType::Database) => {
-                // 数据库操作测试：参考 integration_ultra_group.rs
-                let param_section = if !param_definitions.is_empty() {
-                    format!("{}\n        ", param_definitions)
-                } else {
-                    String::new()
-                };
+// 存在不含冒号的 "类型 变量名"（包括 final 修饰）形式的参数时认为是 Java 风格
+// 按顶层逗号分割，避免 Rust 泛型中逗号后的片段被误判，已转换的参数不会被再次转换
+fn looks_like_java_params(params: &str) -> bool {
+    split_top_level_params(params)
+        .into_iter()
+        .any(|param| !param.contains(':') && param.split_whitespace().count() >= 2)
+}
 
-                format!(
-                    r#"#[test]
-fn {0}() {{
-    SHARED_RUNTIME.block_on(async {{
-        const ROOM_NAME: &str = "test_room";
-        let server_api = ServerApi::new();
-        if !server_api.is_chatroom_exist(ROOM_NAME).await {{
-            server_api.create_chatroom(ROOM_NAME).await;
-        }}
-        TESTER_A.connect().await.unwrap();
-        let engine = &TESTER_A.engine;
-        let (tx, rx) = oneshot::channel();
-        {1}let ret = engine.{0}({2}).await;
-
-        println!("{0}: {{:?}}", ret);
-        assert!(ret.is_ok());
-        tx.send(()).unwrap();
-
-        match rx.await {{
-            Ok(_) => {{}}
-            Err(e) => {{
-                debug!("{0} err: {{:?}}", e);
-                assert!(false);
-            }}
-        }}
-    }});
-}}"#,
-                    rust_function_name, param_section, param_names
-                )
-            }
-            Some(OperationType::Network) => {
-                // 网络请求测试：参考 integration_black_list.rs
-                let param_section = if !param_definitions.is_empty() {
-                    format!("{}\n        ", param_definitions)
-                } else {
-                    String::new()
-                };
+// Java 回调参数的类型名中包含 Callback，例如 ResultCallback<Boolean> callback
+fn is_java_callback_param(param: &str) -> bool {
+    let parts: Vec<&str> = param.split_whitespace().collect();
+    parts.len() >= 2
+        && parts[..parts.len() - 1]
+            .iter()
+            .any(|p| p.contains("Callback"))
+}
 
-                let call_code = if param_names.is_empty() {
-                    format!(
-                        r#"{1}engine
-                .{0}(|ret| {{
-                    println!("{0}: {{:?}}", ret);
-                    assert!(ret.is_ok());
-                    tx.send(()).unwrap();
-                }})
-                .await;"#,
-                        rust_function_name, param_section
-                    )
-                } else {
-                    format!(
-                        r#"{2}engine
-                .{0}({1}, |ret| {{
-                    println!("{0}: {{:?}}", ret);
-                    assert!(ret.is_ok());
-                    tx.send(()).unwrap();
-                }})
-                .await;"#,
-                        rust_function_name, param_names, param_section
-                    )
-                };
+// 从 Java 回调类型中提取泛型参数并转换为 Rust 类型，例如：
+// ResultCallback<Boolean> -> bool, ResultCallback<List<GroupInfo>> -> Vec<GroupInfo>
+fn parse_java_callback_return_type(
+    callback_type: &str,
+    options: &JavaTypeOptions,
+) -> Option<String> {
+    let open = callback_type.find('<')?;
+    let close = callback_type.rfind('>')?;
+    if close <= open {
+        return None;
+    }
 
-                format!(
-                    r#"#[test]
-fn {0}() {{
-    SHARED_RUNTIME.block_on(async {{
-        const ROOM_NAME: &str = "test_room";
-        let server_api = ServerApi::new();
-        if !server_api.is_chatroom_exist(ROOM_NAME).await {{
-            server_api.create_chatroom(ROOM_NAME).await;
-        }}
-        TESTER_A.connect().await.unwrap();
-        let engine = &TESTER_A.engine;
-        let (tx, rx) = oneshot::channel();
-        {1}
-
-        match rx.await {{
-            Ok(_) => {{}}
-            Err(e) => {{
-                debug!("{0} err: {{:?}}", e);
-                assert!(false);
-            }}
-        }}
-    }});
-}}"#,
-                    rust_function_name, call_code
-                )
-            }
-            None => String::new(),
-        }
+    let inner = callback_type[open + 1..close].trim();
+    if inner.is_empty() {
+        return None;
     }
 
-    fn generate_struct_fields(&self) -> String {
-        let cleaned_params = self.clean_params(&self.function_params);
-        if cleaned_params.is_empty() {
-            return String::new();
-        }
+    // 回调返回值需要拥有所有权
+    Some(convert_java_element_type(inner, options))
+}
 
-        cleaned_params
-            .split(',')
-            .filter_map(|param| {
-                let trimmed = param.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
+fn convert_java_params_to_rust(java_params: &str, options: &JavaTypeOptions) -> String {
+    split_top_level_params(java_params)
+        .into_iter()
+        .filter_map(|param| {
+            let trimmed = param.trim().trim_end_matches(',').trim();
+            if trimmed.is_empty() {
+                return None;
+            }
 
-                let parts: Vec<&str> = trimmed.split(':').map(|s| s.trim()).collect();
-                if parts.len() != 2 {
-                    return None;
-                }
+            // 去掉 final 关键字
+            let without_final = trimmed.replace("final ", "");
+            let without_final = without_final.trim();
 
-                let param_name = parts[0];
-                let mut param_type = parts[1];
+            // 最后一个单词是变量名，前面的全部是类型
+            // 类型部分可能是 String[], List< String >, Map<String, Integer> 等；只有变量名时跳过
+            let split_at = without_final.rfind(char::is_whitespace)?;
+            let var_name = without_final[split_at..].trim();
+            let java_type = normalize_java_type_spacing(&without_final[..split_at]);
 
-                // 如果是 &str，转换为 String
-                if param_type == "&str" {
-                    param_type = "String";
-                }
+            // 转换Java类型到Rust类型
+            let rust_type = convert_java_type_to_rust(&java_type, options);
 
-                // 规范化参数名称
-                let normalized_name = self.normalize_param_name(param_name, param_type);
+            // 将Java驼峰命名转换为Rust下划线命名
+            let rust_var_name = to_snake_case(var_name);
 
-                Some(format!("    {}: {},", normalized_name, param_type))
-            })
-            .collect::<Vec<_>>()
-            .join("\n")
-    }
+            Some(format!("{}: {}", rust_var_name, rust_type))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
-    fn generate_new_params(&self) -> String {
-        let cleaned_params = self.clean_params(&self.function_params);
-        if cleaned_params.is_empty() {
-            return String::new();
+// 规范化类型中的空白：连续空白合并为一个，< > [ ] , 两侧的空白去掉，例如 "Map< String , List<Integer> >" -> "Map<String,List<Integer>>"；
+// "? extends Foo" 这类单词之间的空格保留
+fn normalize_java_type_spacing(java_type: &str) -> String {
+    let collapsed = java_type.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut result = String::new();
+    let mut chars = collapsed.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next_is_punct = chars.peek().is_some_and(|n| "<>[],".contains(*n));
+        let prev_is_punct = result.chars().last().is_some_and(|p| "<>[],".contains(p));
+        if c == ' ' && (next_is_punct || prev_is_punct) {
+            continue;
         }
-
-        cleaned_params
-            .split(',')
-            .filter_map(|param| {
-                let trimmed = param.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
-                
-                // 分割参数为名称和类型
-                let parts: Vec<&str> = trimmed.split(':').map(|s| s.trim()).collect();
-                if parts.len() != 2 {
-                    return Some(trimmed.to_string());
-                }
-                
-                let param_name = parts[0];
-                let param_type = parts[1];
-                
-                // 规范化参数名称
-                let normalized_name = self.normalize_param_name(param_name, param_type);
-                
-                Some(format!("{}: {}", normalized_name, param_type))
-            })
-            .collect::<Vec<_>>()
-            .join(", ")
+        result.push(c);
     }
+    result
+}
 
-    fn generate_field_inits(&self) -> String {
-        let cleaned_params = self.clean_params(&self.function_params);
-        if cleaned_params.is_empty() {
-            return String::new();
-        }
+// Kotlin 参数格式为 "name: Type = default"，去掉默认值后逐个转换类型
+fn convert_kotlin_params_to_rust(kotlin_params: &str, options: &JavaTypeOptions) -> String {
+    split_top_level_params(kotlin_params)
+        .into_iter()
+        .filter_map(|param| {
+            let param = param.split_once('=').map_or(param, |(param, _)| param);
+            let (name, kotlin_type) = param.split_once(':')?;
+            let name = name.trim().trim_start_matches("vararg ").trim();
+            Some(format!(
+                "{}: {}",
+                to_snake_case(name),
+                convert_kotlin_type_to_rust(kotlin_type, options)
+            ))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
-        cleaned_params
-            .split(',')
-            .filter_map(|param| {
-                let trimmed = param.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
+// Kotlin 类型先映射为对应的 Java 类型，复用 Java 的转换规则；可空类型转为 Option
+fn convert_kotlin_type_to_rust(kotlin_type: &str, options: &JavaTypeOptions) -> String {
+    let kotlin_type = kotlin_type.trim();
 
-                let parts: Vec<&str> = trimmed.split(':').map(|s| s.trim()).collect();
-                if parts.len() != 2 {
-                    return None;
-                }
+    if let Some(inner) = kotlin_type.strip_suffix('?') {
+        return format!("Option<{}>", convert_kotlin_element_type(inner, options));
+    }
 
-                let param_name = parts[0];
-                let param_type = parts[1];
+    for list_type in ["List<", "MutableList<", "ArrayList<", "Array<"] {
+        if let Some(inner) = kotlin_type
+            .strip_prefix(list_type)
+            .and_then(|rest| rest.strip_suffix('>'))
+        {
+            return format!("Vec<{}>", convert_kotlin_element_type(inner, options));
+        }
+    }
 
-                // 规范化参数名称
-                let normalized_name = self.normalize_param_name(param_name, param_type);
+    // IntArray、LongArray 等基本类型数组
+    if let Some(element) = kotlin_type.strip_suffix("Array") {
+        if let Some(element) = kotlin_primitive_to_java(element) {
+            return format!("Vec<{}>", convert_java_element_type(element, options));
+        }
+    }
 
-                // 如果参数是 &str，需要转换为 String
-                if param_type == "&str" {
-                    Some(format!("{}: {}.to_string()", normalized_name, normalized_name))
-                } else {
-                    Some(format!("{}", normalized_name))
-                }
-            })
-            .collect::<Vec<_>>()
-            .join(", ")
+    match kotlin_primitive_to_java(kotlin_type) {
+        Some(java_type) => convert_java_type_to_rust(java_type, options),
+        None => convert_java_type_to_rust(kotlin_type, options),
     }
+}
 
-    fn extract_param_names(&self) -> String {
-        self.clean_params(&self.function_params)
-            .split(',')
-            .filter_map(|param| {
-                let trimmed = param.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
-                
-                // 分割参数为名称和类型
-                let parts: Vec<&str> = trimmed.split(':').map(|s| s.trim()).collect();
-                if parts.len() != 2 {
-                    return trimmed.split(':').next().map(|name| name.trim().to_string());
-                }
-                
-                let param_name = parts[0];
-                let param_type = parts[1].trim();
-                
-                // 规范化参数名称
-                let normalized_name = self.normalize_param_name(param_name, param_type);
-                
-                Some(normalized_name)
-            })
-            .collect::<Vec<_>>()
-            .join(", ")
+// 转换集合元素和 Option 内部等需要拥有所有权的类型
+fn convert_kotlin_element_type(kotlin_type: &str, options: &JavaTypeOptions) -> String {
+    match convert_kotlin_type_to_rust(kotlin_type, options).as_str() {
+        "&str" => "String".to_string(),
+        other => other.to_string(),
     }
+}
 
-    fn clean_params(&self, params: &str) -> String {
-        // 去除末尾的逗号、空格等
-        let cleaned = params.trim().trim_end_matches(',').trim().to_string();
+// 和 Java 写法不同的 Kotlin 基本类型，Long/Boolean 等同名类型由 Java 规则处理
+fn kotlin_primitive_to_java(kotlin: &str) -> Option<&'static str> {
+    match kotlin {
+        "Int" => Some("int"),
+        "Long" => Some("long"),
+        "Short" => Some("short"),
+        "Byte" => Some("byte"),
+        "Boolean" => Some("boolean"),
+        "Float" => Some("float"),
+        "Double" => Some("double"),
+        "Char" => Some("char"),
+        "Unit" => Some("Void"),
+        _ => None,
+    }
+}
 
-        // 去除 cb: CB 参数
-        let parts: Vec<&str> = cleaned.split(',').collect();
-        let filtered_parts: Vec<&str> = parts
-            .into_iter()
-            .filter(|param| {
-                let trimmed = param.trim();
-                !trimmed.starts_with("cb:") && !trimmed.starts_with("cb :")
-            })
-            .collect();
+fn convert_java_type_to_rust(java_type: &str, options: &JavaTypeOptions) -> String {
+    let java_type = java_type.trim();
 
-        filtered_parts.join(", ")
+    // 处理数组类型
+    if let Some(base_type) = java_type.strip_suffix("[]") {
+        return format!("Vec<{}>", convert_java_element_type(base_type, options));
     }
 
-    fn extract_param_names_for_call(&self) -> String {
-        self.clean_params(&self.function_params)
-            .split(',')
-            .filter_map(|param| {
-                let trimmed = param.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
-                let name = trimmed.split(':').next()?.trim();
+    // 处理 List<T> / ArrayList<T> 泛型集合
+    for list_type in ["List<", "ArrayList<"] {
+        if let Some(inner) = java_type
+            .strip_prefix(list_type)
+            .and_then(|rest| rest.strip_suffix('>'))
+        {
+            return format!("Vec<{}>", convert_java_element_type(inner, options));
+        }
+    }
 
-                // 如果参数名包含 &，说明已经是引用了
-                if trimmed.contains("&str") {
-                    Some(name.to_string())
-                } else {
-                    Some(name.to_string())
-                }
-            })
-            .collect::<Vec<_>>()
-            .join(", ")
+    // 内部类 Outer.Inner：Rust 侧平铺导出，只取最后一段；保留路径时转换为 Outer::Inner
+    if java_type.contains('.') && !java_type.contains('<') {
+        if options.keep_nested_type_paths {
+            return java_type.replace('.', "::");
+        }
+        let last_segment = java_type.rsplit('.').next().unwrap_or(java_type);
+        return convert_java_type_to_rust(last_segment, options);
     }
 
-    fn add_ref_to_str_params(&self) -> String {
-        self.clean_params(&self.function_params)
-            .split(',')
-            .filter_map(|param| {
-                let trimmed = param.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
-                
-                // 分割参数为名称和类型
-                let parts: Vec<&str> = trimmed.split(':').map(|s| s.trim()).collect();
-                if parts.len() != 2 {
-                    return Some(trimmed.to_string());
-                }
-                
-                let param_name = parts[0];
-                let mut param_type = parts[1].trim();
-                
-                // 如果类型是 String，转换为 &str
-                if param_type == "String" {
-                    param_type = "&str";
-                }
-                
-                // 规范化参数名称
-                let normalized_name = self.normalize_param_name(param_name, param_type);
-                
-                Some(format!("{}: {}", normalized_name, param_type))
-            })
-            .collect::<Vec<_>>()
-            .join(", ")
+    match java_type {
+        "String" => "&str".to_string(),
+        "Void" => "()".to_string(),
+        "Date" | "Instant" | "LocalDateTime" | "Timestamp" => options.timestamp_type.clone(),
+        // 自定义类型保持不变
+        _ => map_primitive(java_type)
+            .map(str::to_string)
+            .unwrap_or_else(|| java_type.to_string()),
     }
+}
 
-    fn generate_trace_params(&self) -> String {
-        self.clean_params(&self.function_params)
-            .split(',')
-            .filter_map(|param| {
-                let trimmed = param.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
-                trimmed.split(':').next().map(|name| {
-                    let name = name.trim();
-                    format!("\"{}\": {}", name, name)
-                })
-            })
-            .collect::<Vec<_>>()
-            .join(",\n            ")
+// 转换集合元素等需要拥有所有权的类型，String 不能转换为 &str
+fn convert_java_element_type(java_type: &str, options: &JavaTypeOptions) -> String {
+    match convert_java_type_to_rust(java_type, options).as_str() {
+        "&str" => "String".to_string(),
+        other => other.to_string(),
     }
+}
 
-    fn generate_str_to_string_conversions(&self) -> String {
-        let cleaned_params = self.clean_params(&self.function_params);
-        let conversions: Vec<String> = cleaned_params
-            .split(',')
-            .filter_map(|param| {
-                let trimmed = param.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
+// 基本类型及其包装类型的映射，标量、数组和泛型集合都使用这一份，避免映射不一致
+fn map_primitive(java: &str) -> Option<&'static str> {
+    match java {
+        "int" | "Integer" => Some("i32"),
+        "long" | "Long" => Some("i64"),
+        "short" | "Short" => Some("i16"),
+        "byte" | "Byte" => Some("i8"),
+        "boolean" | "Boolean" => Some("bool"),
+        "float" | "Float" => Some("f32"),
+        "double" | "Double" => Some("f64"),
+        "char" | "Character" => Some("char"),
+        _ => None,
+    }
+}
 
-                // 检查参数类型是否为 &str
-                if trimmed.contains(": &str") {
-                    let param_name = trimmed.split(':').next()?.trim();
-                    Some(format!(
-                        "    let {} = {}.to_string();",
-                        param_name, param_name
-                    ))
-                } else {
-                    None
-                }
-            })
-            .collect();
+#[allow(dead_code)]
+fn to_pascal_case(snake_case: &str) -> String {
+    snake_case
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                None => String::new(),
+                Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+            }
+        })
+        .collect()
+}
 
-        if conversions.is_empty() {
-            String::new()
-        } else {
-            conversions.join("\n") + "\n"
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generator_with_params(params: &str) -> CodeGenerator {
+        CodeGenerator {
+            spec: GenSpec {
+                function_params: params.to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
         }
     }
 
-    fn extract_param_names_with_ref(&self) -> String {
-        self.clean_params(&self.function_params)
-            .split(',')
-            .filter_map(|param| {
-                let trimmed = param.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
+    #[test]
+    fn java_callback_generic_is_converted_to_return_type() {
+        assert_eq!(
+            parse_java_callback_return_type("ResultCallback<Boolean>", &JavaTypeOptions::default())
+                .as_deref(),
+            Some("bool")
+        );
+        assert_eq!(
+            parse_java_callback_return_type(
+                "ResultCallback<List<GroupInfo>>",
+                &JavaTypeOptions::default()
+            )
+            .as_deref(),
+            Some("Vec<GroupInfo>")
+        );
+        assert_eq!(
+            parse_java_callback_return_type("ResultCallback<String>", &JavaTypeOptions::default())
+                .as_deref(),
+            Some("String")
+        );
+        assert_eq!(
+            parse_java_callback_return_type("OperationCallback", &JavaTypeOptions::default()),
+            None
+        );
+    }
 
-                let param_name = trimmed.split(':').next()?.trim();
+    #[test]
+    fn java_int_maps_to_same_element_type_everywhere() {
+        assert_eq!(
+            convert_java_type_to_rust("int", &JavaTypeOptions::default()),
+            "i32"
+        );
+        assert_eq!(
+            convert_java_type_to_rust("int[]", &JavaTypeOptions::default()),
+            "Vec<i32>"
+        );
+        assert_eq!(
+            convert_java_type_to_rust("List<Integer>", &JavaTypeOptions::default()),
+            "Vec<i32>"
+        );
+        assert_eq!(
+            convert_java_type_to_rust("long[]", &JavaTypeOptions::default()),
+            "Vec<i64>"
+        );
+        assert_eq!(
+            convert_java_type_to_rust("List<Long>", &JavaTypeOptions::default()),
+            "Vec<i64>"
+        );
+    }
 
-                // 如果参数类型是 &str，在调用时需要加 &
-                if trimmed.contains(": &str") {
-                    Some(format!("&{}", param_name))
-                } else {
-                    Some(param_name.to_string())
-                }
-            })
-            .collect::<Vec<_>>()
-            .join(", ")
+    #[test]
+    fn brackets_balanced_detects_missing_closing_bracket() {
+        assert!(brackets_balanced("Map<String, List<Int>>"));
+        assert!(brackets_balanced("Box<dyn FnOnce(i32) -> bool>"));
+        assert!(!brackets_balanced("List<String"));
+        assert!(!brackets_balanced("(Vec<i32>, bool"));
+        assert!(!brackets_balanced("String>"));
     }
 
-    fn extract_param_names_only(&self) -> String {
-        self.clean_params(&self.function_params)
-            .split(',')
-            .filter_map(|param| {
-                let trimmed = param.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
+    #[test]
+    fn java_temporal_types_map_to_timestamp_type() {
+        let u64_options = JavaTypeOptions {
+            timestamp_type: "u64".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            convert_java_params_to_rust("Date createdAt", &JavaTypeOptions::default()),
+            "created_at: i64"
+        );
+        assert_eq!(
+            convert_java_params_to_rust("final Instant updatedAt", &u64_options),
+            "updated_at: u64"
+        );
+        assert_eq!(
+            convert_java_type_to_rust("List<LocalDateTime>", &JavaTypeOptions::default()),
+            "Vec<i64>"
+        );
+    }
 
-                // 分割参数为名称和类型
-                let parts: Vec<&str> = trimmed.split(':').map(|s| s.trim()).collect();
-                if parts.len() != 2 {
-                    return trimmed.split(':').next().map(|name| name.trim().to_string());
-                }
-                
-                let param_name = parts[0];
-                let param_type = parts[1];
-                
-                // 规范化参数名称
-                let normalized_name = self.normalize_param_name(param_name, param_type);
-                Some(normalized_name)
-            })
-            .collect::<Vec<_>>()
-            .join(", ")
+    #[test]
+    fn snake_case_conversion_for_function_and_type_names() {
+        // 函数名和请求体文件名共用 to_snake_case，这里覆盖两种输入
+        assert_eq!(to_snake_case("getGroupInfo"), "get_group_info");
+        assert_eq!(to_snake_case("GetGroupReq"), "get_group_req");
+        assert_eq!(to_snake_case("already_snake"), "already_snake");
+        assert_eq!(
+            to_snake_case("SetHTTPConfigRequest"),
+            "set_http_config_request"
+        );
+        assert_eq!(to_snake_case("getURL"), "get_url");
+        assert_eq!(
+            convert_java_params_to_rust("String targetId", &JavaTypeOptions::default()),
+            "target_id: &str"
+        );
     }
 
-    fn generate_test_param_definitions(&self) -> String {
-        let cleaned_params = self.clean_params(&self.function_params);
-        if cleaned_params.is_empty() {
-            return String::new();
+    #[test]
+    fn status_log_keeps_latest_entries() {
+        let mut generator = CodeGenerator::default();
+        for i in 0..STATUS_LOG_LIMIT + 5 {
+            generator.push_status(format!("消息 {}", i));
         }
+        assert_eq!(generator.log.len(), STATUS_LOG_LIMIT);
+        assert_eq!(generator.log[0].1, "消息 5");
+        assert_eq!(generator.status_message, "消息 104");
+    }
 
-        let definitions: Vec<String> = cleaned_params
-            .split(',')
-            .filter_map(|param| {
-                let trimmed = param.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
-
-                // 分割参数名和类型
-                let parts: Vec<&str> = trimmed.split(':').collect();
-                if parts.len() != 2 {
-                    return None;
-                }
-
-                let param_name = parts[0].trim();
-                let param_type = parts[1].trim();
+    #[test]
+    fn java_nested_class_types_use_last_segment() {
+        assert_eq!(
+            convert_java_params_to_rust("A.B.C foo", &JavaTypeOptions::default()),
+            "foo: C"
+        );
+        assert_eq!(
+            convert_java_type_to_rust("List<Message.SentStatus>", &JavaTypeOptions::default()),
+            "Vec<SentStatus>"
+        );
+
+        let keep_paths = JavaTypeOptions {
+            keep_nested_type_paths: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            convert_java_params_to_rust("A.B.C foo", &keep_paths),
+            "foo: A::B::C"
+        );
+    }
 
-                // 根据类型生成默认值
-                let default_value = self.generate_default_value_for_type(param_type);
+    #[test]
+    fn param_rows_round_trip_through_function_params() {
+        let rows = params_to_rows("target_id: &str, extra: HashMap<String, String>");
+        assert_eq!(
+            rows,
+            vec![
+                ParamRow {
+                    name: "target_id".to_string(),
+                    param_type: "&str".to_string(),
+                },
+                ParamRow {
+                    name: "extra".to_string(),
+                    param_type: "HashMap<String, String>".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            rows_to_params(&rows),
+            "target_id: &str, extra: HashMap<String, String>"
+        );
+
+        let mut generator = CodeGenerator::default();
+        generator.update(Message::FunctionParamsChanged("count: i32".to_string()));
+        generator.update(Message::ParamRowAdded);
+        generator.update(Message::ParamRowNameChanged(1, "tag".to_string()));
+        assert_eq!(generator.spec.function_params, "count: i32");
+        generator.update(Message::ParamRowTypeChanged(1, "String".to_string()));
+        assert_eq!(generator.spec.function_params, "count: i32, tag: String");
+        generator.update(Message::ParamRowRemoved(0));
+        assert_eq!(generator.spec.function_params, "tag: String");
+    }
 
-                Some(format!(
-                    "let {}: {} = {};",
-                    param_name, param_type, default_value
-                ))
-            })
-            .collect();
+    #[test]
+    fn inputs_snapshot_round_trips_and_loads_older_json() {
+        let mut generator = generator_with_params("target_id: &str, count: i32");
+        generator.spec.function_name = "getInfo".to_string();
+        generator.spec.operation_type = Some(OperationType::NetworkStream);
+        generator.spec.callback_style = CallbackStyle::BoxedDyn;
+        generator.spec.visibility = Some(Visibility::PubCrate);
+        generator.spec.supports_cancel = true;
+        generator.spec.trace_macro = "trace_d_json".to_string();
+
+        let json = serde_json::to_string(&generator.inputs_snapshot()).unwrap();
+        let mut restored = CodeGenerator::default();
+        restored.apply_inputs_snapshot(serde_json::from_str(&json).unwrap());
+        assert_eq!(restored.inputs_snapshot(), generator.inputs_snapshot());
+        assert_eq!(restored.params.len(), 2);
+        assert_eq!(restored.spec.visibility, Some(Visibility::PubCrate));
+
+        // 旧版本导出的 JSON 只有部分字段，其余使用默认值
+        let snapshot: InputsSnapshot =
+            serde_json::from_str(r#"{"function_name": "disconnect"}"#).unwrap();
+        assert_eq!(snapshot.function_name, "disconnect");
+        assert_eq!(snapshot.trace_macro, Config::default().trace_macro);
+        assert!(snapshot.validate_syntax);
+    }
 
-        if definitions.is_empty() {
-            String::new()
-        } else {
-            definitions.join("\n        ")
-        }
-    }
-
-    fn generate_default_value_for_type(&self, param_type: &str) -> String {
-        match param_type {
-            "&str" => "\"test\"".to_string(),
-            "String" => "\"test\".to_string()".to_string(),
-            "i32" | "i64" | "u32" | "u64" | "i8" | "i16" | "u8" | "u16" | "usize" | "isize" => {
-                "0".to_string()
-            }
-            "f32" | "f64" => "0.0".to_string(),
-            "bool" => "false".to_string(),
-            "Vec<String>" => "vec![]".to_string(),
-            "Vec<i32>" | "Vec<i64>" | "Vec<u32>" | "Vec<u64>" => "vec![]".to_string(),
-            _ => {
-                // 对于复杂类型，尝试生成默认值
-                if param_type.starts_with("Vec<") {
-                    "vec![]".to_string()
-                } else if param_type.starts_with("Option<") {
-                    "None".to_string()
-                } else {
-                    // 对于其他类型，尝试使用 Default trait
-                    format!("Default::default()")
-                }
-            }
-        }
+    #[test]
+    fn java_params_conversion_is_stable_and_keeps_raw_input() {
+        let mut generator = CodeGenerator::default();
+        let java = "final String targetId, int count";
+        generator.update(Message::FunctionParamsChanged(java.to_string()));
+        let converted = generator.spec.function_params.clone();
+        assert_eq!(converted, "target_id: &str, count: i32");
+        assert_eq!(generator.raw_function_params, java);
+
+        generator.update(Message::FunctionParamsChanged(converted.clone()));
+        assert_eq!(generator.spec.function_params, converted);
+
+        let rust = "cb: Box<dyn FnOnce(Result<(), EngineError>) + Send>, count: i32";
+        assert!(!looks_like_java_params(rust));
+        generator.update(Message::FunctionParamsChanged(rust.to_string()));
+        assert_eq!(generator.spec.function_params, rust);
     }
 
-    // 生成 A 函数 - db_agent.rs 中的函数
-    fn generate_db_agent_function(&self, rust_function_name: &str) -> String {
-        let return_type = if self.callback_return_type.is_empty() {
-            "bool".to_string()
-        } else {
-            self.callback_return_type.clone()
+    #[test]
+    fn clear_all_requires_a_second_press() {
+        let mut generator = CodeGenerator {
+            project_path: "/tmp/sdk".to_string(),
+            spec: GenSpec {
+                function_name: "getInfo".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
         };
 
-        let params_with_ref = self.add_ref_to_str_params();
-        let param_names_for_call = self.extract_param_names_for_db_worker_call();
+        generator.update(Message::ClearAll);
+        assert_eq!(generator.spec.function_name, "getInfo");
+        assert_eq!(generator.status_message, "再次点击确认清空");
 
-        // 生成 &str 参数的转换代码
-        let str_conversions = self.generate_str_to_string_conversions_for_db_agent();
+        // 中间有其他操作时取消确认
+        generator.update(Message::ToggleShowRawParams(true));
+        generator.update(Message::ClearAll);
+        assert_eq!(generator.spec.function_name, "getInfo");
 
-        format!(
-            r#"pub async fn {}(
-    &self,
-    {},
-) -> Result<{}, EngineError> {{
-    // 1. 基础参数转化（需要将数据转为 db 模块的类型）
-{}
-    // 2. 创建通道和 db_worker
-    let (resp_tx, resp_rx) = oneshot::channel();
-    let db_worker_clone = self.db_worker.clone();
-
-    // 3. 创建 task，调用 db_worker 对应方法。
-    // task 只负责调用简单的方法，复杂逻辑挪到 db 模块内
-    let task = Box::pin(async move {{
-        let db_worker = db_worker_clone.read().await;
-        let result = db_worker.{}({})
-            .await;
-        let _ = resp_tx.send(result);
-    }});
-
-    // 4. 发任务给 db 模块执行
-    self.execute(task, resp_rx).await
-}}"#,
-            rust_function_name,
-            params_with_ref,
-            return_type,
-            str_conversions,
-            rust_function_name,
-            param_names_for_call
-        )
+        generator.update(Message::ClearAll);
+        assert!(generator.spec.function_name.is_empty());
+        assert_eq!(generator.project_path, "/tmp/sdk");
     }
 
-    // 生成 B 函数 - db_worker.rs 中的函数
-    fn generate_db_worker_function(&self, rust_function_name: &str) -> String {
-        let return_type = if self.callback_return_type.is_empty() {
-            "bool".to_string()
-        } else {
-            self.callback_return_type.clone()
-        };
+    #[test]
+    fn kotlin_params_are_converted_to_rust() {
+        let options = JavaTypeOptions::default();
+        assert_eq!(
+            convert_kotlin_params_to_rust(
+                "targetId: String, count: Int = 20, tag: String?, ids: List<String>, flags: IntArray, done: Boolean",
+                &options
+            ),
+            "target_id: &str, count: i32, tag: Option<String>, ids: Vec<String>, flags: Vec<i32>, done: bool"
+        );
+
+        let mut generator = CodeGenerator::default();
+        generator.update(Message::InputLanguageSelected(InputLanguage::Kotlin));
+        generator.update(Message::FunctionParamsChanged(
+            "sentTime: Long?".to_string(),
+        ));
+        assert_eq!(generator.spec.function_params, "sent_time: Option<i64>");
+
+        // Rust 模式不做任何转换
+        generator.update(Message::InputLanguageSelected(InputLanguage::Rust));
+        assert_eq!(generator.spec.function_params, "sentTime: Long?");
+    }
 
-        let params_with_ref = self.add_ref_to_str_params();
-        let param_names = self.extract_param_names();
+    #[test]
+    fn engine_async_can_use_tracing_instrument_instead_of_manual_trace() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.update(Message::FunctionNameChanged("getInfo".to_string()));
+        generator.update(Message::ToggleUseLogResultHelper(true));
+        generator.update(Message::ToggleUseTracingInstrument(true));
+        assert!(!generator.spec.use_log_result_helper);
+
+        let network = generate_engine_async(&generator.spec);
+        assert!(
+            network.starts_with("#[tracing::instrument(skip(self, cb))]\npub async fn get_info")
+        );
+        assert!(network.contains("span.in_scope(|| tracing::info!(result = %str));"));
+        assert!(!network.contains("trace_id"));
+        syn::parse_file(&network).unwrap();
+
+        generator.spec.supports_cancel = true;
+        let cancellable = generate_engine_async(&generator.spec);
+        assert!(cancellable.contains("#[tracing::instrument(skip(self, cancel_token, cb))]\n"));
+        assert!(!cancellable.contains("trace_id"));
+        syn::parse_file(&cancellable).unwrap();
+
+        generator.spec.operation_type = Some(OperationType::Database);
+        let database = generate_engine_async(&generator.spec);
+        assert!(database.starts_with("#[tracing::instrument(skip(self), err)]\n"));
+        assert!(database.contains("    bugtags::get_info(&self.ctx, target_id).await\n}"));
+    }
 
-        format!(
-            r#"pub async fn {}(
-    &self,
-    {},
-) -> Result<{}, DbError> {{
-    log_db_i!("P-{}-T");
-    let method_name = "{}";
-    let db_lock = self.db_sqlite_lock.read().await;
-    let db = db_lock
-        .as_ref()
-        .ok_or_else(|| self.callback_error(method_name, DbError::NotOpen))?;
-    let ret = db.{}({})
-        .await
-        .unwrap_or_else(|join_error| Err(DbErrorInfo::from_join_error(join_error)));
-    self.callback(method_name, ret)
-}}"#,
-            rust_function_name,
-            params_with_ref,
-            return_type,
-            rust_function_name,
-            rust_function_name,
-            rust_function_name,
-            param_names
-        )
+    #[test]
+    fn copies_are_annotated_with_the_target_file() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.engine_sync_content = text_editor::Content::with_text("pub fn get_info() {}");
+        generator.test_method_content = text_editor::Content::with_text("fn get_info() {}");
+
+        assert_eq!(
+            generator.copy_text(SectionId::EngineSync),
+            "// File: src/engine_sync.rs\npub fn get_info() {}\n"
+        );
+        // 测试方法没有固定的目标文件，不加标注
+        assert_eq!(
+            generator.copy_text(SectionId::TestMethod),
+            "fn get_info() {}\n"
+        );
+
+        generator.annotate_copies = false;
+        assert_eq!(
+            generator.copy_text(SectionId::EngineSync),
+            "pub fn get_info() {}\n"
+        );
     }
 
-    // 生成 C 函数 - db_sqlite.rs 中的函数
-    fn generate_db_sqlite_function(&self, rust_function_name: &str) -> String {
-        let return_type = if self.callback_return_type.is_empty() {
-            "bool".to_string()
-        } else {
-            self.callback_return_type.clone()
+    #[test]
+    fn section_stats_follow_content_edits() {
+        let mut generator = CodeGenerator {
+            engine_sync_content: text_editor::Content::with_text("fn a() {}\nfn b() {}"),
+            ..Default::default()
         };
+        generator.refresh_section_stats();
+        assert_eq!(
+            generator.section_stats.get(&SectionId::EngineSync),
+            Some(&(2, 19))
+        );
+        assert!(!generator.section_stats.contains_key(&SectionId::Module));
+
+        generator.update(Message::EngineSyncAction(text_editor::Action::Move(
+            text_editor::Motion::DocumentEnd,
+        )));
+        generator.update(Message::EngineSyncAction(text_editor::Action::Edit(
+            text_editor::Edit::Insert('x'),
+        )));
+        assert_eq!(
+            generator.section_stats.get(&SectionId::EngineSync),
+            Some(&(2, 20))
+        );
+    }
 
-        let params_with_ref = self.add_ref_to_str_params();
-
-        // 生成 &str 参数的转换代码（在函数体内）
-        let str_conversions = self.generate_str_conversions_in_function_body();
+    #[test]
+    fn ui_scale_is_clamped_to_a_sane_range() {
+        assert_eq!(clamp_ui_scale(1.0 + UI_SCALE_STEP), 1.1);
+        assert_eq!(clamp_ui_scale(0.5), 0.75);
+        assert_eq!(clamp_ui_scale(3.0), 2.0);
+
+        let mut generator = CodeGenerator::default();
+        generator.apply_config(Config {
+            ui_scale: 10.0,
+            ..Config::default()
+        });
+        assert_eq!(generator.ui_scale, 2.0);
+    }
 
-        format!(
-            r#"pub fn {}(
-    &self,
-    {},
-) -> JoinHandle<Result<{}, DbErrorInfo>> {{
-    let db_lock_clone = self.db_lock.clone();
-{}
-    spawn_blocking(move || {{
-        let db = db_lock_clone
-                .read()
-                .map_err(|error| DbErrorInfo::from_lock(error))?;
-            let mut transaction_err_opt = None;
-            let transaction_ret = db.run_transaction(|_| {{
-
-                if let Err(exp) = ret {{
-                    transaction_err_opt = Some(DbErrorInfo::from(exp));
-                    return false;
-                }}
-
-                return true; //返回 false 回滚整个事务
-            }});
-            if let Some(error) = transaction_err_opt {{
-                return Err(error);
-            }}
-            if let Err(exp) = transaction_ret {{
-                return Err(DbErrorInfo::from(exp));
-            }}
-            Ok(())
-    }})
-}}"#,
-            rust_function_name, params_with_ref, return_type, str_conversions
-        )
+    #[test]
+    fn tab_cycles_through_inputs_from_the_last_edited_field() {
+        let mut generator = CodeGenerator::default();
+        generator.update(Message::FocusNext);
+        assert_eq!(generator.focused_input, Some(FocusField::ProjectPath));
+
+        generator.update(Message::CallbackReturnTypeChanged("bool".to_string()));
+        generator.update(Message::FocusNext);
+        assert_eq!(generator.focused_input, Some(FocusField::RequestBody));
+        generator.update(Message::FocusNext);
+        assert_eq!(generator.focused_input, Some(FocusField::ProjectPath));
+        generator.update(Message::FocusPrevious);
+        assert_eq!(generator.focused_input, Some(FocusField::RequestBody));
     }
 
-    // 辅助函数：生成 db_agent 中 &str 参数的转换代码
-    fn generate_str_to_string_conversions_for_db_agent(&self) -> String {
-        let cleaned_params = self.clean_params(&self.function_params);
-        let conversions: Vec<String> = cleaned_params
-            .split(',')
-            .filter_map(|param| {
-                let trimmed = param.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
+    #[test]
+    fn switching_operation_type_restores_its_toggles() {
+        let mut generator = CodeGenerator::default();
+        generator.update(Message::TogglePassParamsToRequest(true));
 
-                if trimmed.contains(": &str") {
-                    let param_name = trimmed.split(':').next()?.trim();
-                    Some(format!(
-                        "    let {} = {}.to_string();",
-                        param_name, param_name
-                    ))
-                } else {
-                    None
-                }
-            })
-            .collect();
+        // 第一次切换到的类型沿用当前状态
+        generator.update(Message::OperationTypeSelected(OperationType::Database));
+        assert!(generator.spec.pass_params_to_request);
+        generator.update(Message::ToggleGenerateDbFunctions(true));
+        generator.update(Message::TogglePassParamsToRequest(false));
 
-        if conversions.is_empty() {
-            String::new()
-        } else {
-            conversions.join("\n") + "\n"
-        }
+        generator.update(Message::OperationTypeSelected(OperationType::Network));
+        assert!(!generator.generate_db_functions);
+        assert!(generator.spec.pass_params_to_request);
+
+        generator.update(Message::OperationTypeSelected(OperationType::Database));
+        assert!(generator.generate_db_functions);
+        assert!(!generator.spec.pass_params_to_request);
+
+        generator.update(Message::OperationTypeSelected(OperationType::Network));
+        assert!(!generator.generate_db_functions);
+        assert!(generator.spec.pass_params_to_request);
     }
 
-    // 辅助函数：生成调用 db_worker 时的参数列表
-    fn extract_param_names_for_db_worker_call(&self) -> String {
-        self.clean_params(&self.function_params)
-            .split(',')
-            .filter_map(|param| {
-                let trimmed = param.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
+    #[test]
+    fn request_file_name_keeps_acronyms_together() {
+        let mut generator = CodeGenerator::default();
+        generator.update(Message::RequestBodyNameChanged(
+            "GetURLInfoRequest".to_string(),
+        ));
+        assert_eq!(generator.request_file_name, "get_url_info_request");
+    }
 
-                let param_name = trimmed.split(':').next()?.trim();
+    #[test]
+    fn codegen_block_is_spliced_between_markers_only() {
+        let generator = CodeGenerator {
+            spec: GenSpec {
+                function_name: "getInfo".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let old_block = generator.marked_block("fn get_info() {}\n");
+        assert_eq!(
+            old_block,
+            "// @generated by auto_universal_sdk (get_info) — do not edit above the marker\nfn get_info() {}\n// codegen:do-not-edit end (get_info)\n"
+        );
+
+        let existing = format!("use a::b;\n\n{}\nfn user_code() {{}}\n", old_block);
+        let new_block = generator.marked_block("fn get_info(id: i32) {}");
+        assert_eq!(
+            splice_codegen_block(&existing, &new_block, "get_info"),
+            format!("use a::b;\n\n{}\nfn user_code() {{}}\n", new_block)
+        );
+
+        // 没有对应的标记块时追加，不覆盖已有代码
+        assert_eq!(
+            splice_codegen_block("fn user_code() {}\n", &new_block, "get_info"),
+            format!("fn user_code() {{}}\n\n{}", new_block)
+        );
+    }
 
-                // 如果参数类型是 &str，在调用时需要使用 .as_str()
-                if trimmed.contains(": &str") {
-                    Some(format!("{}.as_str()", param_name))
-                } else {
-                    Some(param_name.to_string())
-                }
-            })
-            .collect::<Vec<_>>()
-            .join(", ")
+    #[test]
+    fn java_params_with_spaced_generics_are_split_at_the_last_word() {
+        let options = JavaTypeOptions::default();
+        assert_eq!(
+            normalize_java_type_spacing("Map< String , List<Integer> >"),
+            "Map<String,List<Integer>>"
+        );
+        assert_eq!(normalize_java_type_spacing("String [ ]"), "String[]");
+        assert_eq!(
+            convert_java_params_to_rust("List< String > userIds, int count", &options),
+            convert_java_params_to_rust("List<String> userIds, int count", &options)
+        );
+        assert_eq!(
+            convert_java_params_to_rust("Map<String,  Integer>   counts", &options),
+            convert_java_params_to_rust("Map<String,Integer> counts", &options)
+        );
+        assert_eq!(
+            convert_java_params_to_rust("String [] ids", &options),
+            convert_java_params_to_rust("String[] ids", &options)
+        );
+        assert_eq!(convert_java_params_to_rust("targetId", &options), "");
     }
 
-    // 辅助函数：生成 db_sqlite 中 &str 参数的转换代码（在 spawn_blocking 外部）
-    fn generate_str_conversions_in_function_body(&self) -> String {
-        let cleaned_params = self.clean_params(&self.function_params);
-        let conversions: Vec<String> = cleaned_params
-            .split(',')
-            .filter_map(|param| {
-                let trimmed = param.trim();
-                if trimmed.is_empty() {
-                    return None;
-                }
+    #[test]
+    fn split_layout_depends_on_logical_window_width() {
+        let mut generator = CodeGenerator::default();
+        generator.update(Message::WindowResized(iced::Size::new(1400.0, 900.0)));
+        assert!(generator.uses_split_layout());
 
-                if trimmed.contains(": &str") {
-                    let param_name = trimmed.split(':').next()?.trim();
-                    Some(format!(
-                        "    let {} = {}.to_string();",
-                        param_name, param_name
-                    ))
-                } else {
-                    None
-                }
-            })
-            .collect();
+        // Resized 的宽度已经包含界面缩放，放大界面后同样的逻辑宽度仍然分栏
+        generator.ui_scale = 2.0;
+        generator.update(Message::WindowResized(iced::Size::new(1200.0, 900.0)));
+        assert!(generator.uses_split_layout());
 
-        if conversions.is_empty() {
-            String::new()
-        } else {
-            conversions.join("\n") + "\n"
-        }
+        generator.ui_scale = 1.0;
+        generator.update(Message::WindowResized(iced::Size::new(800.0, 900.0)));
+        assert!(!generator.uses_split_layout());
     }
-}
 
-fn java_to_rust_naming(java_name: &str) -> String {
-    let mut result = String::new();
-    let mut chars = java_name.chars().peekable();
+    #[test]
+    fn bench_stub_times_one_call_with_test_defaults() {
+        let mut generator = CodeGenerator {
+            generate_bench: true,
+            ..generator_with_params("target_id: &str, limit: i32")
+        };
+        generator.spec.function_name = "getInfo".to_string();
+        assert!(generator.section_visible(SectionId::Bench));
+        let bench = generate_bench(&generator.spec);
+        assert!(bench.contains("fn bench_get_info(c: &mut Criterion) {"));
+        assert!(bench.contains(
+            "                let target_id: &str = \"test\";\n                let limit: i32 = 20;\n                let (tx, rx) = oneshot::channel();\n                engine\n                    .get_info(target_id, limit, |ret| {"
+        ));
+        syn::parse_file(&bench).unwrap();
+
+        generator.spec.operation_type = Some(OperationType::Database);
+        let bench = generate_bench(&generator.spec);
+        assert!(bench.contains(
+            "let ret = engine.get_info(target_id, limit).await;\n                black_box(ret);"
+        ));
+        syn::parse_file(&bench).unwrap();
+    }
 
-    while let Some(c) = chars.next() {
-        if c.is_uppercase() {
-            if !result.is_empty() {
-                result.push('_');
-            }
-            result.push(c.to_lowercase().next().unwrap());
-        } else {
-            result.push(c);
-        }
+    #[test]
+    fn headless_args_build_a_spec_and_print_banners() {
+        let args: Vec<String> = [
+            "--headless",
+            "--fn",
+            "getInfo",
+            "--params",
+            "target_id: &str",
+            "--op",
+            "db",
+            "--db",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        let parsed = parse_headless_args(&args).unwrap();
+        let spec = parsed.spec.unwrap();
+        assert_eq!(spec.function_name, "getInfo");
+        assert_eq!(spec.operation_type, Some(OperationType::Database));
+        assert!(parsed.generate_db_functions);
+
+        let generator = CodeGenerator {
+            spec,
+            generate_db_functions: true,
+            ..Default::default()
+        };
+        let output = generator.headless_output();
+        assert!(output.starts_with("// === engine_sync.rs ===\npub fn get_info"));
+        assert!(output.contains("\n\n// === db_sqlite.rs (C函数) ===\n"));
+        assert!(!output.contains("request_builder"));
+
+        // 没有指定函数参数时从标准输入读取 JSON，缺少的字段使用默认值
+        let parsed = parse_headless_args(&args[..1]).unwrap();
+        assert!(parsed.spec.is_none());
+        let spec: GenSpec = serde_json::from_str(r#"{"function_name": "getInfo"}"#).unwrap();
+        assert_eq!(spec.operation_type, Some(OperationType::Network));
+        assert!(parse_headless_args(&["--op".to_string(), "rpc".to_string()]).is_err());
+        assert!(parse_headless_args(&["--fn".to_string()]).is_err());
     }
 
-    result
-}
+    #[test]
+    fn request_file_name_must_be_a_valid_module_name() {
+        assert!(is_valid_module_name("get_info_request"));
+        assert!(is_valid_module_name("_v2"));
+        assert!(!is_valid_module_name("get.info"));
+        assert!(!is_valid_module_name("2fa_request"));
+        assert_eq!(sanitize_module_name("get.info request"), "get_info_request");
+        assert_eq!(sanitize_module_name("2FA-Request"), "_2_fa_request");
+
+        let mut generator = CodeGenerator::default();
+        generator.update(Message::RequestFileNameChanged("get info".to_string()));
+        assert_eq!(
+            generator.status_message,
+            "错误：rust 文件名 \"get info\" 不是有效的模块名，建议改为 get_info"
+        );
+        assert!(generator
+            .section_relative_path(SectionId::RequestStruct)
+            .is_none());
+
+        generator.update(Message::RequestFileNameChanged("get_info.rs".to_string()));
+        assert!(generator.status_message.is_empty());
+        assert_eq!(
+            generator.section_relative_path(SectionId::RequestStruct),
+            Some(PathBuf::from("src/rmtp/request/get_info.rs"))
+        );
+    }
 
-fn pascal_to_snake_case(pascal_name: &str) -> String {
-    let mut result = String::new();
-    let mut chars = pascal_name.chars().peekable();
+    #[test]
+    fn generation_counts_are_tracked_per_operation_type() {
+        // 累计数量写入临时的数据目录，不影响用户真实的统计
+        let dir = std::env::temp_dir().join(format!(
+            "auto_universal_sdk_generation_counts_test_{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut generator = CodeGenerator {
+            spec: GenSpec {
+                function_name: "getInfo".to_string(),
+                ..Default::default()
+            },
+            data_dir: Some(dir.clone()),
+            ..Default::default()
+        };
+        generator.update(Message::ShowStats);
+        assert_eq!(generator.status_message, "本次会话：暂无；累计：暂无");
+
+        generator.update(Message::GenerateCode);
+        generator.update(Message::GenerateCode);
+        generator.spec.operation_type = Some(OperationType::Database);
+        generator.update(Message::GenerateCode);
+        generator.update(Message::ShowStats);
+        assert_eq!(
+            generator.status_message,
+            "本次会话：数据库操作 1，网络请求 2；累计：数据库操作 1，网络请求 2"
+        );
+        assert_eq!(
+            generator.generation_totals.get(&OperationType::Network),
+            Some(&2)
+        );
+
+        // 重新打开应用时累计数量从配置中恢复，本次会话重新计数
+        let mut reopened = CodeGenerator::default();
+        reopened.apply_config(Config::load(Some(&dir)));
+        reopened.update(Message::ShowStats);
+        assert_eq!(
+            reopened.status_message,
+            "本次会话：暂无；累计：数据库操作 1，网络请求 2"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 
-    while let Some(c) = chars.next() {
-        if c.is_uppercase() {
-            if !result.is_empty() {
-                result.push('_');
-            }
-            result.push(c.to_lowercase().next().unwrap());
-        } else {
-            result.push(c);
-        }
+    #[test]
+    fn user_template_replaces_only_its_own_section() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.update(Message::FunctionNameChanged("getInfo".to_string()));
+        generator.update(Message::TemplateSectionSelected(SectionId::EngineSync));
+        generator.update(Message::TemplateAction(text_editor::Action::Edit(
+            text_editor::Edit::Paste(std::sync::Arc::new(
+                "pub fn {name}(&self, {params}) {}".to_string(),
+            )),
+        )));
+        generator.update(Message::GenerateCode);
+
+        assert_eq!(
+            generator.engine_sync_content.text().trim_end(),
+            "pub fn get_info(&self, target_id: &str) {}"
+        );
+        assert_eq!(
+            generator.engine_async_content.text().trim_end(),
+            generate_engine_async(&generator.spec).trim_end()
+        );
+
+        generator.update(Message::ResetTemplate);
+        generator.update(Message::GenerateCode);
+        assert_eq!(
+            generator.engine_sync_content.text().trim_end(),
+            generate_engine_sync(&generator.spec).trim_end()
+        );
     }
 
-    result
-}
+    #[test]
+    fn batch_generation_wraps_all_engine_methods_in_one_impl_block() {
+        let mut generator = CodeGenerator {
+            spec: GenSpec {
+                wrap_engine_impl: true,
+                engine_type: "ImEngine".to_string(),
+                ..GenSpec::default()
+            },
+            batch_signatures: text_editor::Content::with_text(
+                "public void getInfo(String id, ResultCallback<Boolean> callback);\npublic void clearInfo(ResultCallback<Boolean> callback);",
+            ),
+            ..CodeGenerator::default()
+        };
+        generator.update(Message::GenerateBatch);
+
+        for code in [
+            generator.engine_sync_content.text(),
+            generator.engine_async_content.text(),
+        ] {
+            assert!(code.starts_with("impl ImEngine {\n"));
+            assert_eq!(code.matches("impl ImEngine").count(), 1);
+            assert!(
+                code.contains("    pub fn get_info") || code.contains("    pub async fn get_info")
+            );
+            assert!(code.contains("fn clear_info"));
+        }
+        assert!(!generator.module_content.text().contains("impl ImEngine"));
+    }
 
-fn convert_java_params_to_rust(java_params: &str) -> String {
-    java_params
-        .split(',')
-        .filter_map(|param| {
-            let trimmed = param.trim().trim_end_matches(',').trim();
-            if trimmed.is_empty() {
-                return None;
-            }
+    #[test]
+    fn type_suggestions_complete_the_type_being_typed() {
+        let mut generator = CodeGenerator {
+            input_language: InputLanguage::Rust,
+            known_types: "ConversationType, MessageId, ConversationInfo".to_string(),
+            ..CodeGenerator::default()
+        };
 
-            // 去掉 final 关键字
-            let without_final = trimmed.replace("final ", "");
+        // 参数名位置不补全，冒号后面的类型才补全
+        generator.update(Message::FunctionParamsChanged("conv".to_string()));
+        assert!(generator.type_suggestions().is_empty());
+        generator.update(Message::FunctionParamsChanged(
+            "id: MessageId, conversation_type: conv".to_string(),
+        ));
+        assert_eq!(
+            generator.type_suggestions(),
+            vec![
+                "ConversationType".to_string(),
+                "ConversationInfo".to_string()
+            ]
+        );
+
+        generator.update(Message::TypeSuggestionSelected(
+            "ConversationType".to_string(),
+        ));
+        assert_eq!(
+            generator.spec.function_params,
+            "id: MessageId, conversation_type: ConversationType"
+        );
+        assert!(generator.type_suggestions().is_empty());
+
+        generator.update(Message::ParamRowTypeChanged(0, "Vec<Mess".to_string()));
+        assert_eq!(generator.type_suggestions(), vec!["MessageId".to_string()]);
+        generator.update(Message::TypeSuggestionSelected("MessageId".to_string()));
+        assert_eq!(generator.params[0].param_type, "Vec<MessageId");
+    }
 
-            // 找到最后一个单词作为变量名
-            // 类型部分可能是 String[], List<String>, Map<String, Integer> 等
-            let parts: Vec<&str> = without_final.split_whitespace().collect();
-            if parts.is_empty() {
-                return None;
-            }
+    #[test]
+    fn rust_lines_are_tokenized_for_highlighting() {
+        let line = "pub fn get_info(id: &str) -> Vec<String> { trace!(\"获取\", 'a', 10); // 注释";
+        let (tokens, in_block_comment) = tokenize_rust_line(line, false);
+        let highlighted: Vec<(&str, RustToken)> = tokens
+            .iter()
+            .map(|(range, token)| (&line[range.clone()], *token))
+            .collect();
+        assert_eq!(
+            highlighted,
+            vec![
+                ("pub", RustToken::Keyword),
+                ("fn", RustToken::Keyword),
+                ("str", RustToken::Type),
+                ("Vec", RustToken::Type),
+                ("String", RustToken::Type),
+                ("trace!", RustToken::Macro),
+                ("\"获取\"", RustToken::String),
+                ("'a'", RustToken::String),
+                ("10", RustToken::Number),
+                ("// 注释", RustToken::Comment),
+            ]
+        );
+        assert!(!in_block_comment);
+
+        // 块注释跨行，结束后继续高亮
+        let (tokens, in_block_comment) = tokenize_rust_line("let a = 1; /* 开始", false);
+        assert_eq!(
+            tokens.last().map(|(_, token)| *token),
+            Some(RustToken::Comment)
+        );
+        assert!(in_block_comment);
+        let line = "结束 */ let s = r#\"x\"#;";
+        let (tokens, in_block_comment) = tokenize_rust_line(line, true);
+        let highlighted: Vec<&str> = tokens
+            .iter()
+            .map(|(range, _)| &line[range.clone()])
+            .collect();
+        assert_eq!(highlighted, vec!["结束 */", "let", "r#\"x\"#"]);
+        assert!(!in_block_comment);
+    }
 
-            // 最后一个是变量名
-            let var_name = parts[parts.len() - 1].trim_end_matches(',');
+    #[test]
+    fn imported_spec_table_runs_the_batch_generator() {
+        let path = std::env::temp_dir().join("auto_universal_sdk_import_test.csv");
+        std::fs::write(
+            &path,
+            "getInfo,\"String targetId, int count\",bool,network\nclearInfo,,,push\nloadInfo,id: &str,,db\n",
+        )
+        .unwrap();
+        let mut generator = CodeGenerator::default();
+        generator.update(Message::ImportSpecCsv(path.clone()));
+        std::fs::remove_file(&path).unwrap();
+
+        let engine_async = generator.engine_async_content.text();
+        assert!(engine_async
+            .contains("pub async fn get_info<CB>(&self, target_id: &str, count: i32, cb: CB)"));
+        assert!(engine_async
+            .contains("pub async fn load_info(&self, id: &str) -> Result<(), EngineError>"));
+        assert!(!engine_async.contains("clear_info"));
+        assert!(generator
+            .status_message
+            .contains("导入成功：共 2 个函数，解析失败：第 2 行：未知的操作类型：push"));
+        assert_eq!(
+            generator
+                .session_generation_counts
+                .get(&OperationType::Database),
+            Some(&1)
+        );
+    }
 
-            // 前面的是类型，对于 String[] 这样的类型不应该有空格
-            let java_type = if parts.len() == 1 {
-                // 只有变量名，没有类型，跳过
-                return None;
-            } else {
-                // 类型可能包含空格（如泛型），但 String[] 不应该有空格
-                // 为了简化，我们直接拼接
-                parts[0..parts.len() - 1].join("")
-            };
+    #[test]
+    fn regenerating_one_section_keeps_manual_edits_in_the_others() {
+        let mut generator = generator_with_params("count: u32");
+        generator.update(Message::FunctionNameChanged("getInfo".to_string()));
+        generator.update(Message::GenerateCode);
+        generator.engine_sync_content = text_editor::Content::with_text("// 手动修改");
+        generator.test_method_content = text_editor::Content::with_text("// 旧的测试");
+
+        generator.update(Message::FunctionParamsChanged("count: u64".to_string()));
+        generator.update(Message::RegenerateSection(SectionId::TestMethod));
+
+        assert_eq!(
+            generator.engine_sync_content.text().trim_end(),
+            "// 手动修改"
+        );
+        assert_eq!(
+            generator.test_method_content.text().trim_end(),
+            generator
+                .render_section(SectionId::TestMethod, &generator.spec)
+                .trim_end()
+        );
+        assert!(generator.test_method_content.text().contains("u64"));
+        assert!(generator.status_message.contains("已重新生成"));
+    }
 
-            // 转换Java类型到Rust类型
-            let rust_type = convert_java_type_to_rust(&java_type);
+    // 需要本地 cargo 缓存中有 tokio 等依赖，只在开启 compile-check feature 时运行
+    #[cfg(feature = "compile-check")]
+    #[test]
+    fn compile_check_passes_on_default_output() {
+        let mut generator = generator_with_params("target_id: &str, count: u32");
+        generator.update(Message::FunctionNameChanged("getInfo".to_string()));
+        generator.update(Message::RequestBodyNameChanged(
+            "GetInfoRequest".to_string(),
+        ));
+        generator.update(Message::GenerateCode);
+
+        let sections = generator.compile_check_sections();
+        assert!(sections.len() >= 5);
+        let dir = std::env::temp_dir().join(format!(
+            "auto_universal_sdk_compile_check_test_{}",
+            std::process::id()
+        ));
+        write_compile_check_project(&dir, &sections, None).unwrap();
+        let outcome = run_cargo_check(&dir, sections.len());
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(
+            matches!(outcome, CompileCheckOutcome::Passed(count) if count == sections.len()),
+            "{:?}",
+            outcome
+        );
+    }
 
-            // 将Java驼峰命名转换为Rust下划线命名
-            let rust_var_name = java_to_rust_naming(var_name);
+    // 数据库操作使用 mpsc 通道时，各层的接收端类型需要一致
+    #[cfg(feature = "compile-check")]
+    #[test]
+    fn compile_check_passes_on_mpsc_database_output() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.spec.operation_type = Some(OperationType::Database);
+        generator.spec.channel_kind = ChannelKind::Mpsc;
+        generator.spec.callback_return_type = "Vec<String>".to_string();
+        generator.generate_db_functions = true;
+        generator.update(Message::FunctionNameChanged("getMessages".to_string()));
+        generator.update(Message::GenerateCode);
+
+        let sections = generator.compile_check_sections();
+        assert!(sections.iter().any(|(id, _)| *id == SectionId::DbSqlite));
+        let dir = std::env::temp_dir().join(format!(
+            "auto_universal_sdk_compile_check_mpsc_test_{}",
+            std::process::id()
+        ));
+        write_compile_check_project(&dir, &sections, None).unwrap();
+        let outcome = run_cargo_check(&dir, sections.len());
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(
+            matches!(outcome, CompileCheckOutcome::Passed(count) if count == sections.len()),
+            "{:?}",
+            outcome
+        );
+    }
 
-            Some(format!("{}: {}", rust_var_name, rust_type))
-        })
-        .collect::<Vec<_>>()
-        .join(", ")
-}
+    #[test]
+    fn config_is_saved_to_the_data_dir_on_explicit_actions_only() {
+        // 默认没有数据目录，测试中的修改不会写入用户的配置
+        let mut generator = CodeGenerator::default();
+        assert!(generator.data_dir.is_none());
+
+        let dir = std::env::temp_dir().join(format!(
+            "auto_universal_sdk_config_test_{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        generator.data_dir = Some(dir.clone());
+        let config_path = dir.join(Config::FILE_NAME);
+
+        // 输入框的每次按键只标记为未保存
+        generator.update(Message::TraceMacroChanged("trace_d_json".to_string()));
+        assert!(generator.config_dirty);
+        assert!(!config_path.exists());
+
+        // 关闭窗口时保存
+        generator.update(Message::CloseRequested);
+        assert!(!generator.config_dirty);
+        assert_eq!(Config::load(Some(&dir)).trace_macro, "trace_d_json");
+
+        // 开关等明确的操作立即保存
+        generator.update(Message::ToggleSyntaxHighlighting(false));
+        assert!(!Config::load(Some(&dir)).syntax_highlighting);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 
-fn convert_java_type_to_rust(java_type: &str) -> String {
-    let java_type = java_type.trim();
+    #[test]
+    fn section_order_skips_hidden_and_empty_sections() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.spec.function_name = "getInfo".to_string();
+        generator.section_order = vec![SectionId::TestMethod, SectionId::Module];
+        // 还没有生成代码时没有内容，不显示任何输出框
+        assert!(generator.displayed_sections().is_empty());
 
-    // 处理数组类型
-    if java_type.ends_with("[]") {
-        let base_type = java_type.trim_end_matches("[]").trim();
-        // 对于数组中的String，使用String而不是&str，因为Vec需要拥有所有权
-        let rust_base_type = match base_type {
-            "String" => "String".to_string(),
-            "int" => "i32".to_string(),
-            "long" => "i64".to_string(),
-            "short" => "i16".to_string(),
-            "byte" => "i8".to_string(),
-            "boolean" => "bool".to_string(),
-            "float" => "f32".to_string(),
-            "double" => "f64".to_string(),
-            "char" => "char".to_string(),
-            _ => base_type.to_string(),
-        };
-        return format!("Vec<{}>", rust_base_type);
+        for id in SectionId::ALL {
+            generator.regenerate_section(id);
+        }
+        let displayed = generator.displayed_sections();
+        assert_eq!(
+            displayed[..3],
+            [
+                SectionId::TestMethod,
+                SectionId::Module,
+                SectionId::EngineSync
+            ]
+        );
+        // 没有请求体名称和关闭数据库函数时，对应的输出框被跳过
+        assert!(!displayed.contains(&SectionId::RequestStruct));
+        assert!(!displayed.contains(&SectionId::DbAgent));
+
+        // 上移时越过隐藏的输出框，和上一个显示的输出框交换
+        generator.section_order = vec![
+            SectionId::Module,
+            SectionId::DbAgent,
+            SectionId::RequestStruct,
+            SectionId::TestMethod,
+        ];
+        generator.update(Message::MoveSectionUp(SectionId::TestMethod));
+        assert_eq!(
+            generator.section_order[..4],
+            [
+                SectionId::TestMethod,
+                SectionId::DbAgent,
+                SectionId::RequestStruct,
+                SectionId::Module
+            ]
+        );
+        assert_eq!(
+            generator.displayed_sections()[..2],
+            [SectionId::TestMethod, SectionId::Module]
+        );
     }
 
-    // 基本类型映射
-    match java_type {
-        "String" => "&str".to_string(),
-        "int" => "i32".to_string(),
-        "long" => "i64".to_string(),
-        "short" => "i16".to_string(),
-        "byte" => "i8".to_string(),
-        "boolean" => "bool".to_string(),
-        "float" => "f32".to_string(),
-        "double" => "f64".to_string(),
-        "char" => "char".to_string(),
-        // 自定义类型保持不变
-        _ => java_type.to_string(),
+    #[test]
+    fn reveal_target_dir_uses_section_paths_under_the_project() {
+        let project = std::env::temp_dir().join(format!(
+            "auto_universal_sdk_reveal_test_{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&project);
+        std::fs::create_dir_all(project.join("src/modules")).unwrap();
+
+        let mut generator = generator_with_params("target_id: &str");
+        generator.spec.function_name = "getInfo".to_string();
+        generator.project_path = project.display().to_string();
+        // 还没有生成代码时没有目标
+        assert_eq!(generator.reveal_target_dir(), None);
+
+        generator.section_order = vec![SectionId::Module];
+        for id in SectionId::ALL {
+            generator.regenerate_section(id);
+        }
+        assert_eq!(
+            generator.reveal_target_dir(),
+            Some(project.join("src/modules"))
+        );
+
+        // 写入过文件后打开最近写入的文件所在目录
+        generator.last_written_section = Some(SectionId::EngineSync);
+        assert_eq!(generator.reveal_target_dir(), Some(project.join("src")));
+        let _ = std::fs::remove_dir_all(&project);
     }
-}
 
-fn to_pascal_case(snake_case: &str) -> String {
-    snake_case
-        .split('_')
-        .map(|word| {
-            let mut chars = word.chars();
-            match chars.next() {
-                None => String::new(),
-                Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
-            }
-        })
-        .collect()
-}
+    #[test]
+    fn last_generated_output_round_trips_through_the_data_dir() {
+        let dir = std::env::temp_dir().join(format!(
+            "auto_universal_sdk_last_generated_test_{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut generator = generator_with_params("target_id: &str");
+        generator.data_dir = Some(dir.clone());
+        // 还没有缓存时只提示错误，不清空输出框
+        generator.update(Message::RestoreLastGenerated);
+        assert_eq!(generator.status_message, "错误：没有找到上次生成的代码！");
+
+        generator.spec.function_name = "getInfo".to_string();
+        generator.update(Message::GenerateCode);
+        let engine_sync = generator.engine_sync_content.text();
+        assert!(engine_sync.contains("pub fn get_info<CB>("));
+        assert!(dir.join(LastGenerated::FILE_NAME).exists());
+
+        // 重新打开后从缓存恢复所有输出框
+        let mut reopened = CodeGenerator {
+            data_dir: Some(dir.clone()),
+            ..CodeGenerator::default()
+        };
+        assert!(reopened.engine_sync_content.text().trim().is_empty());
+        reopened.update(Message::RestoreLastGenerated);
+        assert_eq!(reopened.status_message, "已恢复上次生成的代码！");
+        for id in SectionId::ALL {
+            assert_eq!(
+                reopened.output_content(id).text(),
+                generator.output_content(id).text()
+            );
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 
-#[test]
-fn search_messages_by_user_for_channels() {
-    SHARED_RUNTIME.block_on(async {
-        const ROOM_NAME: &str = "test_room";
-        let server_api = ServerApi::new();
-        if !server_api.is_chatroom_exist(ROOM_NAME).await {
-            server_api.create_chatroom(ROOM_NAME).await;
-        }
-        TESTER_A.connect().await.unwrap();
-        let engine = &TESTER_A.engine;
-        let (tx, rx) = oneshot::channel();
-        let target_id: &str = "test";
-        let channel_ids: Vec<String> = vec![];
-        let user_id: &str = "test";
-        let start_time: i64 = 0;
-        let limit: i32 = 0;
-        engine
-            .search_messages_by_user_for_channels(
-                target_id,
-                channel_ids,
-                user_id,
-                start_time,
-                limit,
-                |ret| {
-                    println!("search_messages_by_user_for_channels: {:?}", ret);
-                    assert!(ret.is_ok());
-                    tx.send(()).unwrap();
-                },
+    #[test]
+    fn write_plan_preview_lists_target_paths_without_writing() {
+        let project = std::env::temp_dir().join(format!(
+            "auto_universal_sdk_write_plan_test_{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&project);
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::write(project.join("src/engine_sync.rs"), "// existing\n").unwrap();
+
+        let mut generator = generator_with_params("target_id: &str");
+        generator.spec.function_name = "getInfo".to_string();
+        generator.spec.request_body_name = "GetInfoRequest".to_string();
+        generator.request_file_name = "get_info_request".to_string();
+        generator.project_path = project.display().to_string();
+        generator.update(Message::GenerateCode);
+        generator.update(Message::PreviewWritePlan);
+
+        let plan = generator.write_plan.as_ref().unwrap().text();
+        let line = |state: &str, relative: &str, id: SectionId| {
+            format!(
+                "{} {} ({})",
+                state,
+                project.join(relative).display(),
+                id.title()
             )
-            .await;
+        };
+        for expected in [
+            line("[已存在]", "src/engine_sync.rs", SectionId::EngineSync),
+            line("[新建]", "src/engine_async.rs", SectionId::EngineAsync),
+            line("[新建]", "src/modules/bugtags.rs", SectionId::Module),
+            line(
+                "[新建]",
+                "src/rmtp/request_builder.rs",
+                SectionId::RequestBuilder,
+            ),
+            line(
+                "[新建]",
+                "src/rmtp/request/get_info_request.rs",
+                SectionId::RequestStruct,
+            ),
+            format!("[跳过] 没有目标文件 ({})", SectionId::TestMethod.title()),
+        ] {
+            assert!(
+                plan.contains(&expected),
+                "{} 不在预览中：\n{}",
+                expected,
+                plan
+            );
+        }
+        // 没有生成内容的输出框不出现在预览中
+        assert!(!plan.contains("db_agent.rs"));
+
+        // 预览不写入任何文件
+        assert_eq!(
+            std::fs::read_to_string(project.join("src/engine_sync.rs")).unwrap(),
+            "// existing\n"
+        );
+        assert_eq!(std::fs::read_dir(project.join("src")).unwrap().count(), 1);
+        let _ = std::fs::remove_dir_all(&project);
+    }
 
-        match rx.await {
-            Ok(_) => {}
-            Err(e) => {
-                debug!("search_messages_by_user_for_channels err: {:?}", e);
-                assert!(false);
-            }
+    #[test]
+    fn search_skips_hidden_sections_and_keeps_the_status_log() {
+        let mut generator = generator_with_params("target_id: &str");
+        generator.spec.function_name = "getInfo".to_string();
+        for id in SectionId::ALL {
+            generator.regenerate_section(id);
         }
-    });
+        // 关闭 FFI 后输出框不显示，其中残留的内容不参与搜索
+        *generator.output_content_mut(SectionId::Ffi) =
+            text_editor::Content::with_text("hidden_ffi_marker");
+        let log_len = generator.log.len();
+
+        for query in ["h", "hi", "hidden_ffi_marker"] {
+            generator.update(Message::SearchInOutput(query.to_string()));
+        }
+        assert_eq!(generator.status_message, "未找到 \"hidden_ffi_marker\"");
+
+        generator.update(Message::SearchInOutput("get_info".to_string()));
+        assert!(generator.status_message.starts_with("已在 "));
+        // 逐字输入时只更新状态栏，不写入状态日志
+        assert_eq!(generator.log.len(), log_len);
+    }
+
+    #[test]
+    fn splice_at_cursor_inserts_on_a_fresh_indented_line() {
+        let file = "impl Engine {\n    fn a(&self) {}\n\n    fn b(&self) {}\n}\n";
+        let code = "fn c(&self) {\n    todo!()\n}";
+
+        // 光标在 fn a 的行中间：插在这一行之后
+        assert_eq!(
+            splice_at_cursor(file, (1, 10), code),
+            "impl Engine {\n    fn a(&self) {}\n\n    fn c(&self) {\n        todo!()\n    }\n\n    fn b(&self) {}\n}\n"
+        );
+        // 光标在块末尾的 } 行首：插在 } 之前
+        assert_eq!(
+            splice_at_cursor(file, (4, 0), code),
+            "impl Engine {\n    fn a(&self) {}\n\n    fn b(&self) {}\n\n    fn c(&self) {\n        todo!()\n    }\n}\n"
+        );
+        // 光标紧跟在 { 之后：不加多余的空行
+        assert_eq!(
+            splice_at_cursor(file, (0, 13), code),
+            "impl Engine {\n    fn c(&self) {\n        todo!()\n    }\n\n    fn a(&self) {}\n\n    fn b(&self) {}\n}\n"
+        );
+        assert_eq!(splice_at_cursor("", (0, 0), "fn c() {}"), "fn c() {}\n");
+    }
 }